@@ -0,0 +1,3 @@
+-- Precisión de microsegundos para que el ETag derivado de updated_at cambie en cada edición
+ALTER TABLE maps_svg
+    MODIFY updated_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6);
@@ -0,0 +1,2 @@
+ALTER TABLE maps_svg_versions DROP COLUMN storage_key;
+ALTER TABLE maps_svg DROP COLUMN storage_key;
@@ -0,0 +1,3 @@
+-- Clave del contenido en el almacenamiento externo (filesystem o S3). NULL = contenido en la columna content
+ALTER TABLE maps_svg ADD COLUMN storage_key VARCHAR(512) NULL;
+ALTER TABLE maps_svg_versions ADD COLUMN storage_key VARCHAR(512) NULL;
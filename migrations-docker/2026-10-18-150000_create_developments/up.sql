@@ -0,0 +1,24 @@
+CREATE TABLE developments (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    prefix VARCHAR(50) NOT NULL UNIQUE,
+    display_name VARCHAR(255) NOT NULL,
+    location VARCHAR(255) NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'America/Mexico_City',
+    logo_url VARCHAR(1024) NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
+
+-- Un desarrollo por cada prefijo que ya tienen los mapas
+INSERT INTO developments (prefix, display_name)
+SELECT DISTINCT prefix, prefix FROM maps_svg WHERE prefix <> '';
+
+ALTER TABLE maps_svg
+    ADD COLUMN development_id INT NULL,
+    ADD CONSTRAINT fk_maps_svg_development FOREIGN KEY (development_id) REFERENCES developments (id);
+
+-- Se conserva updated_at para no invalidar los ETags de los mapas existentes
+UPDATE maps_svg m
+JOIN developments d ON d.prefix = m.prefix
+SET m.development_id = d.id, m.updated_at = m.updated_at;
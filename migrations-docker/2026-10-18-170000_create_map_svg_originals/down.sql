@@ -0,0 +1 @@
+DROP TABLE map_svg_originals;
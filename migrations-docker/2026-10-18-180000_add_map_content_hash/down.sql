@@ -0,0 +1,2 @@
+DROP INDEX idx_maps_svg_content_hash ON maps_svg;
+ALTER TABLE maps_svg DROP COLUMN content_hash;
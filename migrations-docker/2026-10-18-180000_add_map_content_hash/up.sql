@@ -0,0 +1,4 @@
+-- SHA-256 (hex) del contenido sanitizado y optimizado, para detectar mapas duplicados.
+-- Los mapas existentes quedan en NULL y se calculan al iniciar el servidor
+ALTER TABLE maps_svg ADD COLUMN content_hash CHAR(64) NULL;
+CREATE INDEX idx_maps_svg_content_hash ON maps_svg (content_hash);
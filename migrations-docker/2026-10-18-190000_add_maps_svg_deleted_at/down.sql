@@ -0,0 +1,2 @@
+DROP INDEX idx_maps_svg_deleted_at ON maps_svg;
+ALTER TABLE maps_svg DROP COLUMN deleted_at;
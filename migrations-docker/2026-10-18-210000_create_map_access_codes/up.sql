@@ -0,0 +1,21 @@
+-- Códigos de acceso a mapas para brokers: cada código da acceso solo a los
+-- desarrollos o prefijos de sus alcances
+CREATE TABLE map_access_codes (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    code VARCHAR(64) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Un alcance es un desarrollo o un prefijo de mapas, nunca ambos
+CREATE TABLE map_access_code_scopes (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    access_code_id INT NOT NULL,
+    development_id INT NULL,
+    prefix VARCHAR(50) NULL,
+    CONSTRAINT fk_map_access_code_scopes_code FOREIGN KEY (access_code_id) REFERENCES map_access_codes (id) ON DELETE CASCADE,
+    CONSTRAINT fk_map_access_code_scopes_development FOREIGN KEY (development_id) REFERENCES developments (id) ON DELETE CASCADE,
+    CONSTRAINT chk_map_access_code_scopes_target CHECK ((development_id IS NULL) <> (prefix IS NULL))
+);
+
+CREATE INDEX idx_map_access_code_scopes_code ON map_access_code_scopes (access_code_id);
@@ -0,0 +1,3 @@
+-- Orígenes que pueden insertar los mapas del desarrollo en un iframe, separados por espacios.
+-- Sin orígenes los mapas del desarrollo no se pueden insertar.
+ALTER TABLE developments ADD COLUMN embed_origins TEXT NULL;
@@ -0,0 +1,2 @@
+-- La geometría medida en unidades del SVG sigue siendo válida; no hay nada que revertir.
+SELECT 1;
@@ -0,0 +1,12 @@
+CREATE TABLE map_lots (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    element_id VARCHAR(255) NOT NULL,
+    lot_number VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_map_lots_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE,
+    CONSTRAINT uq_map_lots_element UNIQUE (map_id, element_id)
+);
+
+-- Índice para buscar en qué mapas aparece un lote
+CREATE INDEX idx_map_lots_lot_number ON map_lots (lot_number);
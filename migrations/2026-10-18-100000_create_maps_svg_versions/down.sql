@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS maps_svg_versions;
+ALTER TABLE maps_svg DROP COLUMN version;
@@ -0,0 +1,13 @@
+ALTER TABLE maps_svg ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+CREATE TABLE maps_svg_versions (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    version INT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    prefix VARCHAR(50) NOT NULL,
+    content LONGTEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_maps_svg_versions_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE,
+    CONSTRAINT uq_maps_svg_versions_version UNIQUE (map_id, version)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE maps_svg
+    MODIFY updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP;
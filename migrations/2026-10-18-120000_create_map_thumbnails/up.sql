@@ -0,0 +1,9 @@
+CREATE TABLE map_thumbnails (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    size INT NOT NULL,
+    png LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_map_thumbnails_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE,
+    CONSTRAINT uq_map_thumbnails_size UNIQUE (map_id, size)
+);
@@ -0,0 +1,10 @@
+ALTER TABLE maps_svg DROP COLUMN units_per_meter;
+
+ALTER TABLE map_lots
+    DROP COLUMN bbox_x,
+    DROP COLUMN bbox_y,
+    DROP COLUMN bbox_width,
+    DROP COLUMN bbox_height,
+    DROP COLUMN centroid_x,
+    DROP COLUMN centroid_y,
+    DROP COLUMN area;
@@ -0,0 +1,12 @@
+-- Geometría de cada lote en unidades del SVG, calculada al indexar el mapa
+ALTER TABLE map_lots
+    ADD COLUMN bbox_x DOUBLE NULL,
+    ADD COLUMN bbox_y DOUBLE NULL,
+    ADD COLUMN bbox_width DOUBLE NULL,
+    ADD COLUMN bbox_height DOUBLE NULL,
+    ADD COLUMN centroid_x DOUBLE NULL,
+    ADD COLUMN centroid_y DOUBLE NULL,
+    ADD COLUMN area DOUBLE NULL;
+
+-- Calibración de escala del mapa: unidades del SVG por metro
+ALTER TABLE maps_svg ADD COLUMN units_per_meter DOUBLE NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE maps_svg
+    DROP FOREIGN KEY fk_maps_svg_development,
+    DROP COLUMN development_id;
+
+DROP TABLE developments;
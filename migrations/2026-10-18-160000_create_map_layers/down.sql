@@ -0,0 +1,2 @@
+DROP TABLE map_layer_versions;
+DROP TABLE map_layers;
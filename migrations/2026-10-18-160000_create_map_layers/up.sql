@@ -0,0 +1,27 @@
+-- Capas superpuestas de un mapa (calles, amenidades, áreas verdes, etiquetas...).
+-- El contenido de maps_svg es la capa base de lotes.
+CREATE TABLE map_layers (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    position INT NOT NULL,
+    visible_by_default BOOLEAN NOT NULL DEFAULT TRUE,
+    content LONGTEXT NOT NULL,
+    storage_key VARCHAR(512) NULL,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    CONSTRAINT fk_map_layers_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE,
+    CONSTRAINT uq_map_layers_name UNIQUE (map_id, name)
+);
+
+CREATE TABLE map_layer_versions (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    layer_id INT NOT NULL,
+    version INT NOT NULL,
+    content LONGTEXT NOT NULL,
+    storage_key VARCHAR(512) NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_map_layer_versions_layer FOREIGN KEY (layer_id) REFERENCES map_layers (id) ON DELETE CASCADE,
+    CONSTRAINT uq_map_layer_versions_version UNIQUE (layer_id, version)
+);
@@ -0,0 +1,11 @@
+-- Contenido subido de cada versión de un mapa, antes de optimizarlo, para poder descargarlo
+CREATE TABLE map_svg_originals (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    version INT NOT NULL,
+    content LONGTEXT NOT NULL,
+    storage_key VARCHAR(512) NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_map_svg_originals_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE,
+    CONSTRAINT uq_map_svg_originals_version UNIQUE (map_id, version)
+);
@@ -0,0 +1,3 @@
+-- Papelera: los mapas eliminados conservan su contenido hasta que se purgan
+ALTER TABLE maps_svg ADD COLUMN deleted_at TIMESTAMP NULL;
+CREATE INDEX idx_maps_svg_deleted_at ON maps_svg (deleted_at);
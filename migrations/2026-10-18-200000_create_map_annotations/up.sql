@@ -0,0 +1,17 @@
+-- Puntos de interés y notas sobre un mapa, en coordenadas del SVG.
+-- annotation_type: amenity, entrance, sales_office o note. visibility: internal o public
+CREATE TABLE map_annotations (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    map_id VARCHAR(36) NOT NULL,
+    annotation_type VARCHAR(32) NOT NULL,
+    x DOUBLE NOT NULL,
+    y DOUBLE NOT NULL,
+    label VARCHAR(255) NOT NULL,
+    icon VARCHAR(255) NULL,
+    visibility VARCHAR(16) NOT NULL DEFAULT 'internal',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT fk_map_annotations_map FOREIGN KEY (map_id) REFERENCES maps_svg (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_map_annotations_map ON map_annotations (map_id);
@@ -0,0 +1,2 @@
+DROP TABLE map_access_code_scopes;
+DROP TABLE map_access_codes;
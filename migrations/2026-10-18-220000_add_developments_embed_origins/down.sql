@@ -0,0 +1 @@
+ALTER TABLE developments DROP COLUMN embed_origins;
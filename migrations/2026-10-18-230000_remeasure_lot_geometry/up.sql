@@ -0,0 +1,10 @@
+-- La geometría de los lotes se guardaba en píxeles del lienzo y no en unidades del SVG.
+-- Se borra para que el servidor la vuelva a medir al iniciar.
+UPDATE map_lots
+SET bbox_x = NULL,
+    bbox_y = NULL,
+    bbox_width = NULL,
+    bbox_height = NULL,
+    centroid_x = NULL,
+    centroid_y = NULL,
+    area = NULL;
@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse, Result};
+use std::sync::Arc;
+use crate::auth::entities::auth_entities::{Claims, MapAccess};
+use crate::common::errors::ApiError;
+use super::access_code_service::AccessCodeService;
+use super::dto::access_code_dto::{AccessCodeResponse, AccessCodesListResponse, CreateAccessCodeRequest};
+
+/// Scoped sessions can't manage codes, or a broker could give themselves more developments.
+fn require_all_maps(claims: &Claims) -> Result<(), ApiError> {
+    if claims.map_access() != MapAccess::All {
+        return Err(ApiError::Forbidden("Solo las sesiones con acceso a todos los mapas administran códigos".to_string()));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/access-codes",
+    responses(
+        (status = 200, description = "Códigos de acceso con sus desarrollos y prefijos", body = AccessCodesListResponse),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas")
+    ),
+    tag = "Access Codes"
+)]
+#[actix_web::get("")]
+pub async fn get_access_codes(
+    claims: web::ReqData<Claims>,
+    access_code_service: web::Data<Arc<AccessCodeService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    let response = access_code_service.get_all()?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/access-codes",
+    request_body = CreateAccessCodeRequest,
+    responses(
+        (status = 201, description = "Código creado; iniciar sesión con él solo da acceso a los mapas de sus desarrollos y prefijos", body = AccessCodeResponse),
+        (status = 400, description = "Datos inválidos"),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas")
+    ),
+    tag = "Access Codes"
+)]
+#[actix_web::post("")]
+pub async fn create_access_code(
+    claims: web::ReqData<Claims>,
+    data: web::Json<CreateAccessCodeRequest>,
+    access_code_service: web::Data<Arc<AccessCodeService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    let access_code = access_code_service.create(data.into_inner())?;
+    Ok(HttpResponse::Created().json(access_code))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/access-codes/{id}",
+    params(
+        ("id" = i32, Path, description = "Access code id", example = 1)
+    ),
+    responses(
+        (status = 204, description = "Código eliminado; sus sesiones terminan al renovar el token"),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas"),
+        (status = 404, description = "Código no encontrado")
+    ),
+    tag = "Access Codes"
+)]
+#[actix_web::delete("/{id}")]
+pub async fn delete_access_code(
+    claims: web::ReqData<Claims>,
+    id: web::Path<i32>,
+    access_code_service: web::Data<Arc<AccessCodeService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    access_code_service.delete(id.into_inner())?;
+    Ok(HttpResponse::NoContent().finish())
+}
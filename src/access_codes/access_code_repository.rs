@@ -0,0 +1,148 @@
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection, Pool};
+use diesel::mysql::MysqlConnection;
+use diesel::result::Error as DieselError;
+use crate::db::schema::{developments, map_access_code_scopes, map_access_codes};
+use super::entities::access_code_entity::{AccessCode, AccessCodeScope, NewAccessCode, NewAccessCodeScope};
+
+pub struct AccessCodeRepository {
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+}
+
+impl AccessCodeRepository {
+    pub fn new(pool: Pool<ConnectionManager<MysqlConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DieselError> {
+        self.pool.get().map_err(|_| {
+            eprintln!("Failed to get DB connection");
+            DieselError::DatabaseError(
+                diesel::result::DatabaseErrorKind::UnableToSendCommand,
+                Box::new(String::from("Failed to get DB connection"))
+            )
+        })
+    }
+
+    pub fn get_all(&self) -> Result<Vec<(AccessCode, Vec<AccessCodeScope>)>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let access_codes = map_access_codes::table
+            .order(map_access_codes::name.asc())
+            .select(AccessCode::as_select())
+            .load(conn)?;
+        let scopes = AccessCodeScope::belonging_to(&access_codes)
+            .order(map_access_code_scopes::id.asc())
+            .select(AccessCodeScope::as_select())
+            .load(conn)?
+            .grouped_by(&access_codes);
+
+        Ok(access_codes.into_iter().zip(scopes).collect())
+    }
+
+    pub fn find_by_code(&self, code: &str) -> Result<Option<(AccessCode, Vec<AccessCodeScope>)>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let access_code = map_access_codes::table
+            .filter(map_access_codes::code.eq(code))
+            .select(AccessCode::as_select())
+            .first(conn)
+            .optional()?;
+
+        match access_code {
+            Some(access_code) => {
+                let scopes = load_scopes(conn, access_code.id)?;
+                Ok(Some((access_code, scopes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn find_by_id(&self, access_code_id: i32) -> Result<Option<(AccessCode, Vec<AccessCodeScope>)>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let access_code = map_access_codes::table
+            .filter(map_access_codes::id.eq(access_code_id))
+            .select(AccessCode::as_select())
+            .first(conn)
+            .optional()?;
+
+        match access_code {
+            Some(access_code) => {
+                let scopes = load_scopes(conn, access_code.id)?;
+                Ok(Some((access_code, scopes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Ids in `development_ids` that have no development.
+    pub fn missing_developments(&self, development_ids: &[i32]) -> Result<Vec<i32>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let existing: Vec<i32> = developments::table
+            .filter(developments::id.eq_any(development_ids))
+            .select(developments::id)
+            .load(conn)?;
+
+        Ok(development_ids.iter().copied().filter(|id| !existing.contains(id)).collect())
+    }
+
+    /// Inserts a code with one scope per development and per prefix.
+    pub fn create(
+        &self,
+        new_access_code: &NewAccessCode,
+        development_ids: &[i32],
+        prefixes: &[String],
+    ) -> Result<(AccessCode, Vec<AccessCodeScope>), DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        conn.transaction(|conn| {
+            diesel::insert_into(map_access_codes::table)
+                .values(new_access_code)
+                .execute(conn)?;
+
+            let access_code = map_access_codes::table
+                .filter(map_access_codes::code.eq(&new_access_code.code))
+                .select(AccessCode::as_select())
+                .first(conn)?;
+
+            let scopes: Vec<NewAccessCodeScope> = development_ids
+                .iter()
+                .map(|&development_id| NewAccessCodeScope {
+                    access_code_id: access_code.id,
+                    development_id: Some(development_id),
+                    prefix: None,
+                })
+                .chain(prefixes.iter().map(|prefix| NewAccessCodeScope {
+                    access_code_id: access_code.id,
+                    development_id: None,
+                    prefix: Some(prefix.clone()),
+                }))
+                .collect();
+            diesel::insert_into(map_access_code_scopes::table)
+                .values(&scopes)
+                .execute(conn)?;
+
+            let scopes = load_scopes(conn, access_code.id)?;
+            Ok((access_code, scopes))
+        })
+    }
+
+    pub fn delete(&self, access_code_id: i32) -> Result<bool, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let deleted = diesel::delete(map_access_codes::table.filter(map_access_codes::id.eq(access_code_id)))
+            .execute(conn)?;
+
+        Ok(deleted > 0)
+    }
+}
+
+fn load_scopes(conn: &mut MysqlConnection, access_code_id: i32) -> QueryResult<Vec<AccessCodeScope>> {
+    map_access_code_scopes::table
+        .filter(map_access_code_scopes::access_code_id.eq(access_code_id))
+        .order(map_access_code_scopes::id.asc())
+        .select(AccessCodeScope::as_select())
+        .load(conn)
+}
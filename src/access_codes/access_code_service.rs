@@ -0,0 +1,128 @@
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use uuid::Uuid;
+use crate::common::errors::ApiError;
+use super::access_code_repository::AccessCodeRepository;
+use super::entities::access_code_entity::{AccessCode, AccessCodeScope, NewAccessCode};
+use super::dto::access_code_dto::{AccessCodeResponse, AccessCodesListResponse, CreateAccessCodeRequest};
+
+/// Token subjects of sessions opened with an access code: `access-code:<id>`.
+const SUBJECT_PREFIX: &str = "access-code:";
+const CODE_LENGTH: usize = 12;
+
+/// What goes in the claims of a session opened with an access code.
+pub struct AccessCodeSession {
+    pub subject: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+pub struct AccessCodeService {
+    repository: AccessCodeRepository,
+}
+
+impl AccessCodeService {
+    pub fn new(repository: AccessCodeRepository) -> Self {
+        Self { repository }
+    }
+
+    pub fn get_all(&self) -> Result<AccessCodesListResponse, ApiError> {
+        let access_codes: Vec<AccessCodeResponse> = self.repository
+            .get_all()
+            .map_err(|e| internal_error("Error getting access codes", e))?
+            .iter()
+            .map(|(access_code, scopes)| AccessCodeResponse::from_entity(access_code, scopes))
+            .collect();
+
+        let total = access_codes.len();
+        Ok(AccessCodesListResponse { access_codes, total })
+    }
+
+    pub fn create(&self, data: CreateAccessCodeRequest) -> Result<AccessCodeResponse, ApiError> {
+        let name = data.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 255 {
+            return Err(ApiError::BadRequest("name must have 1 to 255 characters".to_string()));
+        }
+
+        let mut development_ids = data.development_ids;
+        development_ids.sort_unstable();
+        development_ids.dedup();
+        let mut prefixes: Vec<String> = data.prefixes.iter().map(|prefix| prefix.trim().to_string()).collect();
+        prefixes.sort();
+        prefixes.dedup();
+
+        if development_ids.is_empty() && prefixes.is_empty() {
+            return Err(ApiError::BadRequest("at least one development_id or prefix is required".to_string()));
+        }
+        if prefixes.iter().any(|prefix| prefix.is_empty() || prefix.len() > 50) {
+            return Err(ApiError::BadRequest("prefixes must have 1 to 50 characters".to_string()));
+        }
+
+        let missing = self.repository
+            .missing_developments(&development_ids)
+            .map_err(|e| internal_error("Error creating access code", e))?;
+        if !missing.is_empty() {
+            return Err(ApiError::BadRequest(format!("Desarrollos inexistentes: {:?}", missing)));
+        }
+
+        let new_access_code = NewAccessCode { code: generate_code(), name };
+        match self.repository.create(&new_access_code, &development_ids, &prefixes) {
+            Ok((access_code, scopes)) => Ok(AccessCodeResponse::from_entity(&access_code, &scopes)),
+            // Another code was generated with the same value; practically impossible, but retryable.
+            Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                Err(ApiError::Conflict("El código generado ya existe; inténtalo de nuevo".to_string()))
+            }
+            Err(e) => Err(internal_error("Error creating access code", e)),
+        }
+    }
+
+    /// Sessions already opened with the code keep working until their refresh token is used.
+    pub fn delete(&self, access_code_id: i32) -> Result<(), ApiError> {
+        match self.repository.delete(access_code_id) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ApiError::NotFound("Código de acceso no encontrado".to_string())),
+            Err(e) => Err(internal_error("Error deleting access code", e)),
+        }
+    }
+
+    /// `None` when `code` is not an access code.
+    pub fn login(&self, code: &str) -> Result<Option<AccessCodeSession>, ApiError> {
+        let access_code = self.repository
+            .find_by_code(code.trim())
+            .map_err(|e| internal_error("Error validating access code", e))?;
+
+        Ok(access_code.map(|(access_code, scopes)| session(&access_code, &scopes)))
+    }
+
+    /// Current scopes of the code a session was opened with, read again on every refresh so
+    /// changes and deletions take effect. `None` when the subject is not an access code.
+    pub fn refresh(&self, subject: &str) -> Result<Option<Vec<String>>, ApiError> {
+        let access_code_id = match subject.strip_prefix(SUBJECT_PREFIX).and_then(|id| id.parse().ok()) {
+            Some(access_code_id) => access_code_id,
+            None => return Ok(None),
+        };
+
+        let (access_code, scopes) = self.repository
+            .find_by_id(access_code_id)
+            .map_err(|e| internal_error("Error validating access code", e))?
+            .ok_or_else(|| ApiError::InvalidToken("El código de acceso ya no existe".to_string()))?;
+
+        Ok(Some(session(&access_code, &scopes).scopes))
+    }
+}
+
+fn session(access_code: &AccessCode, scopes: &[AccessCodeScope]) -> AccessCodeSession {
+    AccessCodeSession {
+        subject: format!("{}{}", SUBJECT_PREFIX, access_code.id),
+        name: access_code.name.clone(),
+        scopes: scopes.iter().filter_map(AccessCodeScope::claim).collect(),
+    }
+}
+
+fn generate_code() -> String {
+    Uuid::new_v4().simple().to_string()[..CODE_LENGTH].to_ascii_uppercase()
+}
+
+fn internal_error(context: &str, error: DieselError) -> ApiError {
+    eprintln!("{}: {:?}", context, error);
+    ApiError::InternalError(context.to_string())
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+use utoipa::ToSchema;
+use crate::access_codes::entities::access_code_entity::{AccessCode, AccessCodeScope};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccessCodeResponse {
+    pub id: i32,
+    /// Code to send to POST /api/auth/login
+    pub code: String,
+    pub name: String,
+    pub development_ids: Vec<i32>,
+    pub prefixes: Vec<String>,
+    #[schema(value_type = String)]
+    pub created_at: NaiveDateTime,
+}
+
+impl AccessCodeResponse {
+    pub fn from_entity(access_code: &AccessCode, scopes: &[AccessCodeScope]) -> Self {
+        Self {
+            id: access_code.id,
+            code: access_code.code.clone(),
+            name: access_code.name.clone(),
+            development_ids: scopes.iter().filter_map(|scope| scope.development_id).collect(),
+            prefixes: scopes.iter().filter_map(|scope| scope.prefix.clone()).collect(),
+            created_at: access_code.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccessCodesListResponse {
+    pub access_codes: Vec<AccessCodeResponse>,
+    pub total: usize,
+}
+
+/// The code is generated by the server. At least one development or prefix is required.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAccessCodeRequest {
+    /// Who the code is for, e.g. the broker's name. It is the name of the session
+    pub name: String,
+    #[serde(default)]
+    pub development_ids: Vec<i32>,
+    /// Map prefixes, e.g. `TC`
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+}
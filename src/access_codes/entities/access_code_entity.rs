@@ -0,0 +1,54 @@
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+use crate::auth::entities::auth_entities::{development_scope, prefix_scope};
+use crate::db::schema::{map_access_code_scopes, map_access_codes};
+
+/// A login code for brokers that only gives access to the maps of its scopes.
+#[derive(Queryable, Selectable, Identifiable, Debug, Serialize, Deserialize, Clone)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(table_name = map_access_codes)]
+pub struct AccessCode {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_access_codes)]
+pub struct NewAccessCode {
+    pub code: String,
+    pub name: String,
+}
+
+/// A development or a map prefix an access code gives access to; exactly one is set.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Serialize, Deserialize, Clone)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(belongs_to(AccessCode))]
+#[diesel(table_name = map_access_code_scopes)]
+pub struct AccessCodeScope {
+    pub id: i32,
+    pub access_code_id: i32,
+    pub development_id: Option<i32>,
+    pub prefix: Option<String>,
+}
+
+impl AccessCodeScope {
+    /// The scope as carried in the token claims.
+    pub fn claim(&self) -> Option<String> {
+        match (self.development_id, &self.prefix) {
+            (Some(development_id), _) => Some(development_scope(development_id)),
+            (None, Some(prefix)) => Some(prefix_scope(prefix)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_access_code_scopes)]
+pub struct NewAccessCodeScope {
+    pub access_code_id: i32,
+    pub development_id: Option<i32>,
+    pub prefix: Option<String>,
+}
@@ -0,0 +1 @@
+pub mod access_code_entity;
@@ -0,0 +1,5 @@
+pub mod access_code_repository;
+pub mod access_code_service;
+pub mod access_code_handler;
+pub mod entities;
+pub mod dto;
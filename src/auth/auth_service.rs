@@ -7,8 +7,9 @@ use std::sync::Arc;
 
 use crate::{common::errors::ApiError, http::zoho::{search_map_access_by_name, ZohoMapAccess}};
 use crate::zoho_code::zoho_code_service::ZohoCodeService;
+use crate::access_codes::access_code_service::AccessCodeService;
 
-use super::{auth_service_trait::AuthServiceTrait, dto::auth_dto::TokenResponseDto, entities::auth_entities::Claims};
+use super::{auth_service_trait::AuthServiceTrait, dto::auth_dto::TokenResponseDto, entities::auth_entities::{Claims, ALL_MAPS_SCOPE}};
 
 pub struct AuthService {
     jwt_secret: String,
@@ -16,6 +17,7 @@ pub struct AuthService {
     token_expiration: usize,
     token_refresh_expiration: usize,
     zoho_code_service: Arc<ZohoCodeService>,
+    access_code_service: Arc<AccessCodeService>,
 }
 
 impl AuthService {
@@ -24,7 +26,8 @@ impl AuthService {
         jwt_refresh_secret: String, 
         token_expiration: usize, 
         token_refresh_expiration: usize,
-        zoho_code_service: Arc<ZohoCodeService>
+        zoho_code_service: Arc<ZohoCodeService>,
+        access_code_service: Arc<AccessCodeService>,
     ) -> Self {
         Self {
             jwt_secret,
@@ -32,10 +35,11 @@ impl AuthService {
             token_expiration,
             token_refresh_expiration,
             zoho_code_service,
+            access_code_service,
         }
     }
 
-    pub fn generate_tokens(&self, user_id: &str, name: &str) -> Result<TokenResponseDto> {
+    pub fn generate_tokens(&self, user_id: &str, name: &str, scopes: &[String]) -> Result<TokenResponseDto> {
         let access_exp = Utc::now().timestamp() as usize + self.token_expiration;
         let refresh_exp = Utc::now().timestamp() as usize + self.token_refresh_expiration;
 
@@ -43,12 +47,14 @@ impl AuthService {
             id: user_id.to_string(),
             name: name.to_string(),
             exp: access_exp,
+            scopes: scopes.to_vec(),
         };
 
         let refresh_claims = Claims {
             id: user_id.to_string(),
             name: name.to_string(),
             exp: refresh_exp,
+            scopes: scopes.to_vec(),
         };
 
         let access_token = encode(
@@ -69,7 +75,16 @@ impl AuthService {
         })
     }
 
+    /// Map access codes log in with the scopes of the code; the Zoho code gives access to
+    /// every map.
     pub async fn login_with_code(&self, code: &str) -> Result<TokenResponseDto> {
+        let session = self.access_code_service
+            .login(code)
+            .map_err(|err| anyhow::anyhow!("Error validating code: {}", err))?;
+        if let Some(session) = session {
+            return self.generate_tokens(&session.subject, &session.name, &session.scopes);
+        }
+
         let is_valid = self.zoho_code_service
             .validate_code(code)
             .map_err(|err| anyhow::anyhow!("Error validating code: {}", err))?;
@@ -91,7 +106,7 @@ impl AuthService {
                 }
             })?;
         
-        self.generate_tokens(&map_access.id, &map_access.name)
+        self.generate_tokens(&map_access.id, &map_access.name, &[ALL_MAPS_SCOPE.to_string()])
     }
 
     pub async fn login_with_zoho(&self, name: &str) -> Result<TokenResponseDto> {
@@ -112,7 +127,7 @@ impl AuthService {
             );
         };
 
-        self.generate_tokens(&map_access.id, &map_access.name)
+        self.generate_tokens(&map_access.id, &map_access.name, &[ALL_MAPS_SCOPE.to_string()])
     }
 
     pub fn refresh_tokens(&self, refresh_token: &str) -> Result<TokenResponseDto> {
@@ -128,7 +143,13 @@ impl AuthService {
             return Err(anyhow::anyhow!("Refresh token has expired"));
         }
 
-        self.generate_tokens(&claims.id, &claims.name)
+        // Sessions of an access code pick up the current scopes of the code, or end if it was deleted.
+        let scopes = self.access_code_service
+            .refresh(&claims.id)
+            .map_err(|err| anyhow::anyhow!("{}", err))?
+            .unwrap_or(claims.scopes);
+
+        self.generate_tokens(&claims.id, &claims.name, &scopes)
     }
 
     async fn get_map_access_by_name(&self, name: &str) -> Result<ZohoMapAccess, ApiError> {
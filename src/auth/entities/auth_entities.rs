@@ -1,8 +1,142 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Scope of tokens that can access every map. Tokens issued before scopes existed have it.
+pub const ALL_MAPS_SCOPE: &str = "*";
+const DEVELOPMENT_SCOPE_PREFIX: &str = "development:";
+const PREFIX_SCOPE_PREFIX: &str = "prefix:";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub id: String,
     pub name: String,
     pub exp: usize,
-}
\ No newline at end of file
+    /// Maps the token can access: `*`, `development:<id>` or `prefix:<prefix>`
+    #[serde(default = "all_maps_scopes")]
+    pub scopes: Vec<String>,
+}
+
+impl Claims {
+    pub fn map_access(&self) -> MapAccess {
+        MapAccess::from_scopes(&self.scopes)
+    }
+}
+
+fn all_maps_scopes() -> Vec<String> {
+    vec![ALL_MAPS_SCOPE.to_string()]
+}
+
+pub fn development_scope(development_id: i32) -> String {
+    format!("{}{}", DEVELOPMENT_SCOPE_PREFIX, development_id)
+}
+
+pub fn prefix_scope(prefix: &str) -> String {
+    format!("{}{}", PREFIX_SCOPE_PREFIX, prefix)
+}
+
+/// Maps a token can read and change. A map is in scope when its development or its prefix is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MapAccess {
+    #[default]
+    All,
+    Scoped {
+        development_ids: Vec<i32>,
+        prefixes: Vec<String>,
+    },
+}
+
+impl MapAccess {
+    /// Unknown scopes grant nothing.
+    pub fn from_scopes(scopes: &[String]) -> Self {
+        if scopes.iter().any(|scope| scope == ALL_MAPS_SCOPE) {
+            return MapAccess::All;
+        }
+
+        let development_ids = scopes
+            .iter()
+            .filter_map(|scope| scope.strip_prefix(DEVELOPMENT_SCOPE_PREFIX))
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        let prefixes = scopes
+            .iter()
+            .filter_map(|scope| scope.strip_prefix(PREFIX_SCOPE_PREFIX))
+            .map(str::to_string)
+            .collect();
+
+        MapAccess::Scoped { development_ids, prefixes }
+    }
+
+    pub fn allows(&self, prefix: &str, development_id: Option<i32>) -> bool {
+        match self {
+            MapAccess::All => true,
+            MapAccess::Scoped { development_ids, prefixes } => {
+                prefixes.iter().any(|allowed| allowed == prefix)
+                    || development_id.is_some_and(|id| development_ids.contains(&id))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scopes(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|scope| scope.to_string()).collect()
+    }
+
+    #[test]
+    fn builds_scopes_that_map_access_reads() {
+        assert_eq!(development_scope(7), "development:7");
+        assert_eq!(prefix_scope("TC"), "prefix:TC");
+        assert_eq!(
+            MapAccess::from_scopes(&[development_scope(7), prefix_scope("TC")]),
+            MapAccess::Scoped {
+                development_ids: vec![7],
+                prefixes: vec!["TC".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn grants_every_map_with_the_wildcard_scope() {
+        let access = MapAccess::from_scopes(&scopes(&["prefix:TC", "*"]));
+
+        assert_eq!(access, MapAccess::All);
+        assert!(access.allows("XX", None));
+    }
+
+    #[test]
+    fn allows_maps_by_prefix_or_development() {
+        let access = MapAccess::from_scopes(&scopes(&["development:3", "prefix:TC"]));
+
+        assert!(access.allows("TC", None));
+        assert!(access.allows("TC", Some(9)));
+        assert!(access.allows("XX", Some(3)));
+        assert!(!access.allows("XX", Some(9)));
+        assert!(!access.allows("XX", None));
+        assert!(!access.allows("tc", None));
+    }
+
+    #[test]
+    fn ignores_unknown_and_malformed_scopes() {
+        let access = MapAccess::from_scopes(&scopes(&["development:abc", "admin", "prefix", "development:"]));
+
+        assert_eq!(
+            access,
+            MapAccess::Scoped {
+                development_ids: vec![],
+                prefixes: vec![],
+            }
+        );
+        assert!(!access.allows("", None));
+    }
+
+    #[test]
+    fn treats_tokens_without_scopes_as_full_access() {
+        let claims: Claims = serde_json::from_str(r#"{"id": "1", "name": "admin", "exp": 0}"#)
+            .expect("the claims should deserialize");
+
+        assert_eq!(claims.scopes, vec![ALL_MAPS_SCOPE]);
+        assert_eq!(claims.map_access(), MapAccess::All);
+    }
+}
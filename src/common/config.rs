@@ -10,6 +10,41 @@ pub struct Config {
     pub token_expiration: usize,
     pub token_refresh_expiration: usize,
     pub cors_allowed_origins: Vec<String>,
+    pub map_storage: MapStorageConfig,
+    /// Days a deleted map stays in the trash before it is purged.
+    pub map_trash_retention_days: i64,
+    /// Signs map share links; kept apart from the session secrets.
+    pub share_token_secret: String,
+    /// Seconds an embedded map is served from cache before its statuses are read again.
+    pub map_embed_cache_seconds: u64,
+}
+
+/// Where map content is stored: `database` (default), `filesystem` or `s3`.
+#[derive(Clone)]
+pub struct MapStorageConfig {
+    pub backend: String,
+    pub directory: String,
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    pub s3_endpoint: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
+    pub s3_prefix: String,
+}
+
+impl MapStorageConfig {
+    pub fn from_env() -> Self {
+        MapStorageConfig {
+            backend: env::var("MAP_STORAGE_BACKEND").unwrap_or_else(|_| "database".to_string()),
+            directory: env::var("MAP_STORAGE_DIR").unwrap_or_else(|_| "svg_storage".to_string()),
+            s3_bucket: env::var("MAP_STORAGE_S3_BUCKET").ok(),
+            s3_region: env::var("MAP_STORAGE_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_endpoint: env::var("MAP_STORAGE_S3_ENDPOINT").ok(),
+            s3_access_key: env::var("MAP_STORAGE_S3_ACCESS_KEY").ok(),
+            s3_secret_key: env::var("MAP_STORAGE_S3_SECRET_KEY").ok(),
+            s3_prefix: env::var("MAP_STORAGE_S3_PREFIX").unwrap_or_else(|_| "maps".to_string()),
+        }
+    }
 }
 
 impl Config {
@@ -31,6 +66,16 @@ impl Config {
             .and_then(|v| v.parse().ok())
             .unwrap_or(14400);
         let token_refresh_expiration = 30000;
+        let share_token_secret = env::var("SHARE_TOKEN_SECRET").unwrap_or_else(|_| "test_share_token_secret".to_string());
+        let map_trash_retention_days: i64 = env::var("MAP_TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|days| *days >= 0)
+            .unwrap_or(30);
+        let map_embed_cache_seconds: u64 = env::var("MAP_EMBED_CACHE_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
 
         Config {
             client_id,
@@ -41,6 +86,10 @@ impl Config {
             token_expiration,
             token_refresh_expiration,
             cors_allowed_origins,
+            map_storage: MapStorageConfig::from_env(),
+            map_trash_retention_days,
+            share_token_secret,
+            map_embed_cache_seconds,
         }
     }
 }
@@ -1,6 +1,6 @@
 use utoipa::{openapi::security::{Http, HttpAuthScheme, SecurityScheme}, Modify, OpenApi};
 use crate::auth::auth_handler::LoginRequest;
-use crate::interactive_maps::interactive_maps_handler::SvgRequestSchema;
+use crate::interactive_maps::interactive_maps_handler::{MapAnnotationRequestSchema, MapAnnotationUpdateRequestSchema, MapLayerRequestSchema, ShareLinkRequestSchema, SvgRequestSchema, SvgScaleRequestSchema, SvgUpdateRequestSchema};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -11,24 +11,89 @@ use crate::interactive_maps::interactive_maps_handler::SvgRequestSchema;
         crate::auth::auth_handler::get_me_handler,
         crate::interactive_maps::interactive_maps_handler::save_svg,
         crate::interactive_maps::interactive_maps_handler::save_svg_stream,
+        crate::interactive_maps::interactive_maps_handler::import_svgs,
+        crate::interactive_maps::interactive_maps_handler::export_svgs,
+        crate::interactive_maps::interactive_maps_handler::get_svgs_by_hash,
+        crate::interactive_maps::interactive_maps_handler::get_trashed_svgs,
+        crate::interactive_maps::interactive_maps_handler::restore_svg,
+        crate::interactive_maps::interactive_maps_handler::purge_svg,
         crate::interactive_maps::interactive_maps_handler::delete_svg_by_id,
         crate::interactive_maps::interactive_maps_handler::get_paginated_svgs,
         crate::interactive_maps::interactive_maps_handler::get_svg_by_id,
-        crate::status_colors::status_color_handler::get_all_status_colors
+        crate::interactive_maps::interactive_maps_handler::replace_svg,
+        crate::interactive_maps::interactive_maps_handler::patch_svg,
+        crate::interactive_maps::interactive_maps_handler::get_svg_lots,
+        crate::interactive_maps::interactive_maps_handler::set_svg_scale,
+        crate::interactive_maps::interactive_maps_handler::get_svg_reconciliation,
+        crate::interactive_maps::interactive_maps_handler::get_raw_svg,
+        crate::interactive_maps::interactive_maps_handler::get_rendered_svg,
+        crate::interactive_maps::interactive_maps_handler::get_composed_svg,
+        crate::interactive_maps::interactive_maps_handler::get_svg_layers,
+        crate::interactive_maps::interactive_maps_handler::get_svg_layer,
+        crate::interactive_maps::interactive_maps_handler::save_svg_layer,
+        crate::interactive_maps::interactive_maps_handler::delete_svg_layer,
+        crate::interactive_maps::interactive_maps_handler::get_svg_layer_versions,
+        crate::interactive_maps::interactive_maps_handler::get_svg_layer_version,
+        crate::interactive_maps::interactive_maps_handler::get_svg_annotations,
+        crate::interactive_maps::interactive_maps_handler::create_svg_annotation,
+        crate::interactive_maps::interactive_maps_handler::update_svg_annotation,
+        crate::interactive_maps::interactive_maps_handler::delete_svg_annotation,
+        crate::interactive_maps::interactive_maps_handler::create_svg_share_link,
+        crate::interactive_maps::interactive_maps_handler::get_shared_svg,
+        crate::interactive_maps::interactive_maps_handler::get_embedded_svg,
+        crate::interactive_maps::interactive_maps_handler::get_embed_viewer_script,
+        crate::interactive_maps::interactive_maps_handler::export_svg_pdf,
+        crate::interactive_maps::interactive_maps_handler::get_svg_thumbnail,
+        crate::interactive_maps::interactive_maps_handler::get_svg_versions,
+        crate::interactive_maps::interactive_maps_handler::get_svg_version,
+        crate::interactive_maps::interactive_maps_handler::get_svg_original,
+        crate::interactive_maps::interactive_maps_handler::rollback_svg_version,
+        crate::interactive_maps::interactive_maps_handler::get_svg_diff,
+        crate::status_colors::status_color_handler::get_all_status_colors,
+        crate::developments::development_handler::get_developments,
+        crate::developments::development_handler::create_development,
+        crate::developments::development_handler::get_development,
+        crate::developments::development_handler::update_development,
+        crate::developments::development_handler::delete_development,
+        crate::developments::development_handler::get_development_overview,
+        crate::access_codes::access_code_handler::get_access_codes,
+        crate::access_codes::access_code_handler::create_access_code,
+        crate::access_codes::access_code_handler::delete_access_code,
+        crate::lots::lot_handler::get_lot,
+        crate::lots::lot_handler::get_map_lot
     ),
     modifiers(&SecurityAddon),
     components(
         schemas(
             LoginRequest,
             SvgRequestSchema,
+            SvgUpdateRequestSchema,
+            SvgScaleRequestSchema,
+            MapLayerRequestSchema,
+            MapAnnotationRequestSchema,
+            MapAnnotationUpdateRequestSchema,
+            ShareLinkRequestSchema,
             crate::status_colors::dto::status_color_dto::StatusColorResponse,
-            crate::status_colors::dto::status_color_dto::StatusColorsListResponse
+            crate::status_colors::dto::status_color_dto::StatusColorsListResponse,
+            crate::developments::dto::development_dto::DevelopmentResponse,
+            crate::developments::dto::development_dto::DevelopmentsListResponse,
+            crate::developments::dto::development_dto::CreateDevelopmentRequest,
+            crate::developments::dto::development_dto::UpdateDevelopmentRequest,
+            crate::access_codes::dto::access_code_dto::AccessCodeResponse,
+            crate::access_codes::dto::access_code_dto::AccessCodesListResponse,
+            crate::access_codes::dto::access_code_dto::CreateAccessCodeRequest,
+            crate::lots::dto::lot_dto::LotDetailResponse,
+            crate::lots::dto::lot_dto::LotProductResponse,
+            crate::lots::dto::lot_dto::LotMapResponse
         )
     ),
     tags(
         (name = "auth", description = "Authentication related endpoints"),
         (name = "maps", description = "Maps related endpoints"),
-        (name = "Status Colors", description = "Status colors management endpoints")
+        (name = "Status Colors", description = "Status colors management endpoints"),
+        (name = "Developments", description = "Developments management endpoints"),
+        (name = "Access Codes", description = "Map access codes for brokers, limited to some developments or prefixes"),
+        (name = "Lots", description = "Lot details joining maps, Zoho products and status colors")
     ),
     servers(
         (url = "/api", description = "Local server")
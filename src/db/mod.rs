@@ -35,3 +35,11 @@ pub fn get_connection(pool: &DbPool) -> Result<PooledConnection<ConnectionManage
         }
     }
 }
+
+/// Escapes the LIKE wildcards so a search for `TC_1` matches the literal text.
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -1,5 +1,502 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    /// Representation of the `developments` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    developments (id) {
+        /// The `id` column of the `developments` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `prefix` column of the `developments` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 50]
+        prefix -> Varchar,
+        /// The `display_name` column of the `developments` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        display_name -> Varchar,
+        /// The `location` column of the `developments` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        location -> Nullable<Varchar>,
+        /// The `timezone` column of the `developments` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 64]
+        timezone -> Varchar,
+        /// The `logo_url` column of the `developments` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 1024]
+        logo_url -> Nullable<Varchar>,
+        /// The `active` column of the `developments` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        active -> Bool,
+        /// The `created_at` column of the `developments` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `updated_at` column of the `developments` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
+        /// The `embed_origins` column of the `developments` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        embed_origins -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_access_code_scopes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_access_code_scopes (id) {
+        /// The `id` column of the `map_access_code_scopes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `access_code_id` column of the `map_access_code_scopes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        access_code_id -> Integer,
+        /// The `development_id` column of the `map_access_code_scopes` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        development_id -> Nullable<Integer>,
+        /// The `prefix` column of the `map_access_code_scopes` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 50]
+        prefix -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_access_codes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_access_codes (id) {
+        /// The `id` column of the `map_access_codes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `code` column of the `map_access_codes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 64]
+        code -> Varchar,
+        /// The `name` column of the `map_access_codes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        name -> Varchar,
+        /// The `created_at` column of the `map_access_codes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_annotations` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_annotations (id) {
+        /// The `id` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `annotation_type` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 32]
+        annotation_type -> Varchar,
+        /// The `x` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Double`.
+        ///
+        /// (Automatically generated by Diesel.)
+        x -> Double,
+        /// The `y` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Double`.
+        ///
+        /// (Automatically generated by Diesel.)
+        y -> Double,
+        /// The `label` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        label -> Varchar,
+        /// The `icon` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        icon -> Nullable<Varchar>,
+        /// The `visibility` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 16]
+        visibility -> Varchar,
+        /// The `created_at` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `updated_at` column of the `map_annotations` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_layer_versions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_layer_versions (id) {
+        /// The `id` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `layer_id` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        layer_id -> Integer,
+        /// The `version` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Integer,
+        /// The `content` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Longtext`.
+        ///
+        /// (Automatically generated by Diesel.)
+        content -> Longtext,
+        /// The `storage_key` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 512]
+        storage_key -> Nullable<Varchar>,
+        /// The `created_at` column of the `map_layer_versions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_layers` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_layers (id) {
+        /// The `id` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `name` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 100]
+        name -> Varchar,
+        /// The `position` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        position -> Integer,
+        /// The `visible_by_default` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        visible_by_default -> Bool,
+        /// The `content` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Longtext`.
+        ///
+        /// (Automatically generated by Diesel.)
+        content -> Longtext,
+        /// The `storage_key` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 512]
+        storage_key -> Nullable<Varchar>,
+        /// The `version` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Integer,
+        /// The `created_at` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `updated_at` column of the `map_layers` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_lots` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_lots (id) {
+        /// The `id` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `element_id` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        element_id -> Varchar,
+        /// The `lot_number` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 50]
+        lot_number -> Varchar,
+        /// The `created_at` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `bbox_x` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        bbox_x -> Nullable<Double>,
+        /// The `bbox_y` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        bbox_y -> Nullable<Double>,
+        /// The `bbox_width` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        bbox_width -> Nullable<Double>,
+        /// The `bbox_height` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        bbox_height -> Nullable<Double>,
+        /// The `centroid_x` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        centroid_x -> Nullable<Double>,
+        /// The `centroid_y` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        centroid_y -> Nullable<Double>,
+        /// The `area` column of the `map_lots` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        area -> Nullable<Double>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_svg_originals` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_svg_originals (id) {
+        /// The `id` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `version` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Integer,
+        /// The `content` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Longtext`.
+        ///
+        /// (Automatically generated by Diesel.)
+        content -> Longtext,
+        /// The `storage_key` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 512]
+        storage_key -> Nullable<Varchar>,
+        /// The `created_at` column of the `map_svg_originals` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `map_thumbnails` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    map_thumbnails (id) {
+        /// The `id` column of the `map_thumbnails` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `map_thumbnails` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `size` column of the `map_thumbnails` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        size -> Integer,
+        /// The `png` column of the `map_thumbnails` table.
+        ///
+        /// Its SQL type is `Longblob`.
+        ///
+        /// (Automatically generated by Diesel.)
+        png -> Longblob,
+        /// The `created_at` column of the `map_thumbnails` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Representation of the `maps_svg` table.
     ///
@@ -44,6 +541,104 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         updated_at -> Timestamp,
+        /// The `version` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Integer,
+        /// The `units_per_meter` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Nullable<Double>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        units_per_meter -> Nullable<Double>,
+        /// The `storage_key` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 512]
+        storage_key -> Nullable<Varchar>,
+        /// The `development_id` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        development_id -> Nullable<Integer>,
+        /// The `content_hash` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Nullable<Char>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 64]
+        content_hash -> Nullable<Char>,
+        /// The `deleted_at` column of the `maps_svg` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `maps_svg_versions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    maps_svg_versions (id) {
+        /// The `id` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `map_id` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 36]
+        map_id -> Varchar,
+        /// The `version` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Integer,
+        /// The `name` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 255]
+        name -> Varchar,
+        /// The `prefix` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 50]
+        prefix -> Varchar,
+        /// The `content` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Longtext`.
+        ///
+        /// (Automatically generated by Diesel.)
+        content -> Longtext,
+        /// The `created_at` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `storage_key` column of the `maps_svg_versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        #[max_length = 512]
+        storage_key -> Nullable<Varchar>,
     }
 }
 
@@ -168,4 +763,15 @@ diesel::table! {
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(maps_svg, products, status_colors, zoho_code,);
+diesel::joinable!(map_access_code_scopes -> developments (development_id));
+diesel::joinable!(map_access_code_scopes -> map_access_codes (access_code_id));
+diesel::joinable!(map_annotations -> maps_svg (map_id));
+diesel::joinable!(map_layer_versions -> map_layers (layer_id));
+diesel::joinable!(map_layers -> maps_svg (map_id));
+diesel::joinable!(map_lots -> maps_svg (map_id));
+diesel::joinable!(map_svg_originals -> maps_svg (map_id));
+diesel::joinable!(map_thumbnails -> maps_svg (map_id));
+diesel::joinable!(maps_svg -> developments (development_id));
+diesel::joinable!(maps_svg_versions -> maps_svg (map_id));
+
+diesel::allow_tables_to_appear_in_same_query!(developments, map_access_code_scopes, map_access_codes, map_annotations, map_layer_versions, map_layers, map_lots, map_svg_originals, map_thumbnails, maps_svg, maps_svg_versions, products, status_colors, zoho_code,);
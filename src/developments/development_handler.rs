@@ -0,0 +1,163 @@
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::auth::entities::auth_entities::{Claims, MapAccess};
+use crate::common::errors::ApiError;
+use super::development_service::DevelopmentService;
+use super::dto::development_dto::{CreateDevelopmentRequest, DevelopmentResponse, DevelopmentsListResponse, UpdateDevelopmentRequest};
+
+/// Scoped sessions only read the developments in their scope; changing them would let a
+/// broker reach other developments' maps through the prefix.
+fn require_all_maps(claims: &Claims) -> Result<(), ApiError> {
+    if claims.map_access() != MapAccess::All {
+        return Err(ApiError::Forbidden("Solo las sesiones con acceso a todos los mapas administran desarrollos".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DevelopmentsQuery {
+    pub active: Option<bool>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/developments",
+    params(
+        ("active" = Option<bool>, Query, description = "Only active (true) or inactive (false) developments")
+    ),
+    responses(
+        (status = 200, description = "Desarrollos a los que la sesión tiene acceso", body = DevelopmentsListResponse),
+        (status = 500, description = "Error interno del servidor")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::get("")]
+pub async fn get_developments(
+    query: web::Query<DevelopmentsQuery>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    let response = development_service.get_all(query.active, &claims.map_access())?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/developments",
+    request_body = CreateDevelopmentRequest,
+    responses(
+        (status = 201, description = "Desarrollo creado; los mapas con su prefijo quedan asociados", body = DevelopmentResponse),
+        (status = 400, description = "Datos inválidos"),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas"),
+        (status = 409, description = "Ya existe un desarrollo con ese prefijo")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::post("")]
+pub async fn create_development(
+    data: web::Json<CreateDevelopmentRequest>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    let development = development_service.create(data.into_inner())?;
+    Ok(HttpResponse::Created().json(development))
+}
+
+#[utoipa::path(
+    get,
+    path = "/developments/{id}",
+    params(
+        ("id" = i32, Path, description = "Development id", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Desarrollo", body = DevelopmentResponse),
+        (status = 404, description = "Desarrollo no encontrado")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::get("/{id}")]
+pub async fn get_development(
+    id: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    let development = development_service.get_accessible_development(id.into_inner(), &claims.map_access())?;
+    Ok(HttpResponse::Ok().json(DevelopmentResponse::from_entity(&development)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/developments/{id}",
+    params(
+        ("id" = i32, Path, description = "Development id", example = 1)
+    ),
+    request_body = UpdateDevelopmentRequest,
+    responses(
+        (status = 200, description = "Desarrollo actualizado", body = DevelopmentResponse),
+        (status = 400, description = "Datos inválidos"),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas"),
+        (status = 404, description = "Desarrollo no encontrado")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::put("/{id}")]
+pub async fn update_development(
+    id: web::Path<i32>,
+    data: web::Json<UpdateDevelopmentRequest>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    let development = development_service.update(id.into_inner(), data.into_inner())?;
+    Ok(HttpResponse::Ok().json(development))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/developments/{id}",
+    params(
+        ("id" = i32, Path, description = "Development id", example = 1)
+    ),
+    responses(
+        (status = 204, description = "Desarrollo eliminado"),
+        (status = 403, description = "La sesión solo tiene acceso a algunos mapas"),
+        (status = 404, description = "Desarrollo no encontrado"),
+        (status = 409, description = "El desarrollo tiene mapas asociados")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::delete("/{id}")]
+pub async fn delete_development(
+    id: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    require_all_maps(&claims)?;
+    development_service.delete(id.into_inner())?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/developments/{id}/overview",
+    params(
+        ("id" = i32, Path, description = "Development id", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Desarrollo con sus mapas y el número de lotes por estatus de venta, por mapa y en total"),
+        (status = 404, description = "Desarrollo no encontrado"),
+        (status = 500, description = "Error interno del servidor")
+    ),
+    tag = "Developments"
+)]
+#[actix_web::get("/{id}/overview")]
+pub async fn get_development_overview(
+    id: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> Result<HttpResponse, ApiError> {
+    let overview = development_service.get_overview(id.into_inner(), &claims.map_access()).await?;
+    Ok(HttpResponse::Ok().json(overview))
+}
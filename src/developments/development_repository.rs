@@ -0,0 +1,136 @@
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection, Pool};
+use diesel::mysql::MysqlConnection;
+use diesel::result::Error as DieselError;
+use chrono::NaiveDateTime;
+use crate::db::schema::{developments, map_lots, maps_svg};
+use crate::interactive_maps::entities::map_lot_entity::MapLot;
+use super::entities::development_entity::{Development, DevelopmentChanges, NewDevelopment};
+
+pub struct DevelopmentRepository {
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+}
+
+impl DevelopmentRepository {
+    pub fn new(pool: Pool<ConnectionManager<MysqlConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DieselError> {
+        self.pool.get().map_err(|_| {
+            eprintln!("Failed to get DB connection");
+            DieselError::DatabaseError(
+                diesel::result::DatabaseErrorKind::UnableToSendCommand,
+                Box::new(String::from("Failed to get DB connection"))
+            )
+        })
+    }
+
+    pub fn get_all(&self, active: Option<bool>) -> Result<Vec<Development>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let mut query = developments::table.into_boxed();
+        if let Some(active) = active {
+            query = query.filter(developments::active.eq(active));
+        }
+
+        query
+            .order(developments::display_name.asc())
+            .load::<Development>(conn)
+    }
+
+    pub fn get_by_id(&self, development_id: i32) -> Result<Option<Development>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        developments::table
+            .filter(developments::id.eq(development_id))
+            .first::<Development>(conn)
+            .optional()
+    }
+
+    /// Inserts a development and links the maps that already use its prefix.
+    pub fn create(&self, new_development: &NewDevelopment) -> Result<Development, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        conn.transaction(|conn| {
+            diesel::insert_into(developments::table)
+                .values(new_development)
+                .execute(conn)?;
+
+            let development = developments::table
+                .filter(developments::prefix.eq(&new_development.prefix))
+                .first::<Development>(conn)?;
+
+            // updated_at is kept so the ETags of the linked maps stay valid.
+            diesel::update(
+                maps_svg::table
+                    .filter(maps_svg::prefix.eq(&development.prefix))
+                    .filter(maps_svg::development_id.is_null()),
+            )
+            .set((
+                maps_svg::development_id.eq(development.id),
+                maps_svg::updated_at.eq(maps_svg::updated_at),
+            ))
+            .execute(conn)?;
+
+            Ok(development)
+        })
+    }
+
+    /// Returns `None` when the development does not exist.
+    pub fn update(&self, development_id: i32, changes: &DevelopmentChanges) -> Result<Option<Development>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        diesel::update(developments::table.filter(developments::id.eq(development_id)))
+            .set(changes)
+            .execute(conn)?;
+
+        developments::table
+            .filter(developments::id.eq(development_id))
+            .first::<Development>(conn)
+            .optional()
+    }
+
+    pub fn delete(&self, development_id: i32) -> Result<bool, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        let deleted = diesel::delete(developments::table.filter(developments::id.eq(development_id)))
+            .execute(conn)?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Maps in the trash are counted too: they reference the development until purged.
+    pub fn count_maps(&self, development_id: i32) -> Result<i64, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        maps_svg::table
+            .filter(maps_svg::development_id.eq(development_id))
+            .count()
+            .get_result(conn)
+    }
+
+    /// Id, name, version and last update of the maps of a development, without their content.
+    pub fn get_maps(&self, development_id: i32) -> Result<Vec<(String, String, i32, NaiveDateTime)>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        maps_svg::table
+            .filter(maps_svg::development_id.eq(development_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .select((maps_svg::id, maps_svg::name, maps_svg::version, maps_svg::updated_at))
+            .order(maps_svg::name.asc())
+            .load(conn)
+    }
+
+    pub fn get_lots(&self, development_id: i32) -> Result<Vec<MapLot>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        map_lots::table
+            .inner_join(maps_svg::table)
+            .filter(maps_svg::development_id.eq(development_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .select(MapLot::as_select())
+            .order(map_lots::id.asc())
+            .load(conn)
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono_tz::Tz;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use crate::auth::entities::auth_entities::MapAccess;
+use crate::common::errors::ApiError;
+use crate::interactive_maps::dto::svg_dto::thumbnail_url;
+use crate::interactive_maps::map_status_service::MapStatusService;
+use super::development_repository::DevelopmentRepository;
+use super::entities::development_entity::{is_valid_prefix, Development, DevelopmentChanges, NewDevelopment, MAX_PREFIX_LENGTH};
+use super::dto::development_dto::{
+    CreateDevelopmentRequest, DevelopmentMapSummary, DevelopmentOverview, DevelopmentResponse,
+    DevelopmentsListResponse, UpdateDevelopmentRequest,
+};
+
+/// Time zone of developments created without one.
+pub const DEFAULT_TIMEZONE: &str = "America/Mexico_City";
+const MAX_EMBED_ORIGINS: usize = 20;
+
+pub struct DevelopmentService {
+    repository: DevelopmentRepository,
+    map_status_service: Arc<MapStatusService>,
+}
+
+impl DevelopmentService {
+    pub fn new(repository: DevelopmentRepository, map_status_service: Arc<MapStatusService>) -> Self {
+        Self {
+            repository,
+            map_status_service,
+        }
+    }
+
+    /// Developments whose maps are within `access`.
+    pub fn get_all(&self, active: Option<bool>, access: &MapAccess) -> Result<DevelopmentsListResponse, ApiError> {
+        let developments: Vec<DevelopmentResponse> = self.repository
+            .get_all(active)
+            .map_err(|e| internal_error("Error getting developments", e))?
+            .iter()
+            .filter(|development| access.allows(&development.prefix, Some(development.id)))
+            .map(DevelopmentResponse::from_entity)
+            .collect();
+
+        let total = developments.len();
+        Ok(DevelopmentsListResponse { developments, total })
+    }
+
+    pub fn get_development(&self, development_id: i32) -> Result<Development, ApiError> {
+        self.repository
+            .get_by_id(development_id)
+            .map_err(|e| internal_error("Error getting development", e))?
+            .ok_or_else(|| ApiError::NotFound("Desarrollo no encontrado".to_string()))
+    }
+
+    /// Like [`Self::get_development`], but developments outside `access` are not found.
+    pub fn get_accessible_development(&self, development_id: i32, access: &MapAccess) -> Result<Development, ApiError> {
+        let development = self.get_development(development_id)?;
+        if !access.allows(&development.prefix, Some(development.id)) {
+            return Err(ApiError::NotFound("Desarrollo no encontrado".to_string()));
+        }
+        Ok(development)
+    }
+
+    pub fn create(&self, data: CreateDevelopmentRequest) -> Result<DevelopmentResponse, ApiError> {
+        let prefix = data.prefix.trim().to_string();
+        if !is_valid_prefix(&prefix) {
+            return Err(ApiError::BadRequest(format!(
+                "prefix must have 1 to {} characters without spaces or '-'",
+                MAX_PREFIX_LENGTH
+            )));
+        }
+
+        let new_development = NewDevelopment {
+            prefix,
+            display_name: validate_display_name(&data.display_name)?,
+            location: optional_text(data.location),
+            timezone: validate_timezone(data.timezone)?,
+            logo_url: validate_logo_url(data.logo_url)?,
+            active: data.active.unwrap_or(true),
+            embed_origins: validate_embed_origins(data.embed_origins)?,
+        };
+
+        match self.repository.create(&new_development) {
+            Ok(development) => Ok(DevelopmentResponse::from_entity(&development)),
+            Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(ApiError::Conflict(
+                format!("Ya existe un desarrollo con el prefijo {}", new_development.prefix),
+            )),
+            Err(e) => Err(internal_error("Error creating development", e)),
+        }
+    }
+
+    pub fn update(&self, development_id: i32, data: UpdateDevelopmentRequest) -> Result<DevelopmentResponse, ApiError> {
+        let changes = DevelopmentChanges {
+            display_name: validate_display_name(&data.display_name)?,
+            location: optional_text(data.location),
+            timezone: validate_timezone(data.timezone)?,
+            logo_url: validate_logo_url(data.logo_url)?,
+            active: data.active,
+            embed_origins: data.embed_origins
+                .map(|origins| validate_embed_origins(Some(origins)))
+                .transpose()?,
+        };
+
+        self.repository
+            .update(development_id, &changes)
+            .map_err(|e| internal_error("Error updating development", e))?
+            .map(|development| DevelopmentResponse::from_entity(&development))
+            .ok_or_else(|| ApiError::NotFound("Desarrollo no encontrado".to_string()))
+    }
+
+    /// Only developments without maps can be deleted; the rest can be deactivated instead.
+    pub fn delete(&self, development_id: i32) -> Result<(), ApiError> {
+        let maps = self.repository
+            .count_maps(development_id)
+            .map_err(|e| internal_error("Error deleting development", e))?;
+        if maps > 0 {
+            return Err(ApiError::Conflict(format!(
+                "El desarrollo tiene {} mapas; desactívalo en lugar de eliminarlo",
+                maps
+            )));
+        }
+
+        match self.repository.delete(development_id) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ApiError::NotFound("Desarrollo no encontrado".to_string())),
+            Err(e) => Err(internal_error("Error deleting development", e)),
+        }
+    }
+
+    /// A development with its maps and the number of lots per sales status, per map and in
+    /// total. The statuses of every lot are resolved with a single products query.
+    pub async fn get_overview(&self, development_id: i32, access: &MapAccess) -> Result<DevelopmentOverview, ApiError> {
+        let development = self.get_accessible_development(development_id, access)?;
+        let maps = self.repository
+            .get_maps(development_id)
+            .map_err(|e| internal_error("Error getting development maps", e))?;
+        let lots = self.repository
+            .get_lots(development_id)
+            .map_err(|e| internal_error("Error getting development lots", e))?;
+
+        let statuses = self.map_status_service
+            .resolve_lot_statuses(&development.prefix, &lots)
+            .await?;
+        let status_counts = self.map_status_service.status_legend(&statuses)?;
+
+        let mut statuses_by_map: HashMap<&str, Vec<_>> = HashMap::new();
+        for (lot, status) in lots.iter().zip(statuses) {
+            statuses_by_map.entry(lot.map_id.as_str()).or_default().push(status);
+        }
+
+        let maps = maps
+            .into_iter()
+            .map(|(id, name, version, updated_at)| {
+                let map_statuses = statuses_by_map.remove(id.as_str()).unwrap_or_default();
+                Ok(DevelopmentMapSummary {
+                    thumbnail_url: thumbnail_url(&id),
+                    total_lots: map_statuses.len(),
+                    status_counts: self.map_status_service.status_legend(&map_statuses)?,
+                    id,
+                    name,
+                    version,
+                    updated_at,
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        Ok(DevelopmentOverview {
+            development: DevelopmentResponse::from_entity(&development),
+            total_maps: maps.len(),
+            total_lots: lots.len(),
+            status_counts,
+            maps,
+        })
+    }
+}
+
+fn internal_error(context: &str, error: DieselError) -> ApiError {
+    eprintln!("{}: {:?}", context, error);
+    ApiError::InternalError(context.to_string())
+}
+
+fn optional_text(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn validate_display_name(display_name: &str) -> Result<String, ApiError> {
+    let display_name = display_name.trim();
+    if display_name.is_empty() || display_name.chars().count() > 255 {
+        return Err(ApiError::BadRequest("display_name must have 1 to 255 characters".to_string()));
+    }
+    Ok(display_name.to_string())
+}
+
+fn validate_timezone(timezone: Option<String>) -> Result<String, ApiError> {
+    match optional_text(timezone) {
+        None => Ok(DEFAULT_TIMEZONE.to_string()),
+        Some(timezone) => timezone
+            .parse::<Tz>()
+            .map(|tz| tz.name().to_string())
+            .map_err(|_| ApiError::BadRequest(format!("Unknown time zone: {}", timezone))),
+    }
+}
+
+/// Logos are rendered by the front end, so only http(s) URLs and paths of this server are
+/// accepted.
+fn validate_logo_url(logo_url: Option<String>) -> Result<Option<String>, ApiError> {
+    match optional_text(logo_url) {
+        None => Ok(None),
+        Some(url) if url.len() <= 1024
+            && (url.starts_with("https://") || url.starts_with("http://") || (url.starts_with('/') && !url.starts_with("//"))) =>
+        {
+            Ok(Some(url))
+        }
+        Some(_) => Err(ApiError::BadRequest(
+            "logo_url must be an http(s) URL or a path of up to 1024 characters".to_string(),
+        )),
+    }
+}
+
+/// Embed origins go into the `frame-ancestors` directive of the embedded maps, so each one
+/// must be a bare http(s) origin, optionally with a `*.` subdomain wildcard.
+fn validate_embed_origins(origins: Option<Vec<String>>) -> Result<Option<String>, ApiError> {
+    let mut valid: Vec<String> = Vec::new();
+    for origin in origins.unwrap_or_default() {
+        let origin = origin.trim().trim_end_matches('/').to_lowercase();
+        let host = origin
+            .strip_prefix("https://")
+            .or_else(|| origin.strip_prefix("http://"))
+            .map(|host| host.strip_prefix("*.").unwrap_or(host));
+        let is_origin = host.is_some_and(|host| {
+            !host.is_empty()
+                && !host.starts_with('.')
+                && host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+        });
+        if !is_origin {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid embed origin {:?}: use scheme and host only, e.g. https://www.example.com",
+                origin
+            )));
+        }
+        if !valid.contains(&origin) {
+            valid.push(origin);
+        }
+    }
+
+    if valid.len() > MAX_EMBED_ORIGINS {
+        return Err(ApiError::BadRequest(format!("At most {} embed origins are allowed", MAX_EMBED_ORIGINS)));
+    }
+    Ok(Some(valid.join(" ")).filter(|origins| !origins.is_empty()))
+}
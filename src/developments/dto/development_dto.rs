@@ -0,0 +1,96 @@
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+use utoipa::ToSchema;
+use crate::developments::entities::development_entity::Development;
+use crate::interactive_maps::dto::svg_dto::StatusLegendEntry;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DevelopmentResponse {
+    pub id: i32,
+    pub prefix: String,
+    pub display_name: String,
+    pub location: Option<String>,
+    pub timezone: String,
+    pub logo_url: Option<String>,
+    pub active: bool,
+    /// Origins allowed to embed the maps of the development with `/embed/maps/{id}`
+    pub embed_origins: Vec<String>,
+    #[schema(value_type = String)]
+    pub created_at: NaiveDateTime,
+    #[schema(value_type = String)]
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DevelopmentsListResponse {
+    pub developments: Vec<DevelopmentResponse>,
+    pub total: usize,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateDevelopmentRequest {
+    /// Prefix of the map names and Zoho products of the development, e.g. `TC`
+    pub prefix: String,
+    pub display_name: String,
+    pub location: Option<String>,
+    /// IANA time zone, `America/Mexico_City` by default
+    pub timezone: Option<String>,
+    pub logo_url: Option<String>,
+    pub active: Option<bool>,
+    /// Origins allowed to embed the maps in an iframe, e.g. `https://www.urvic.mx`.
+    /// Without origins the maps can't be embedded
+    pub embed_origins: Option<Vec<String>>,
+}
+
+/// Replaces the editable fields of a development; an omitted location or logo is cleared and
+/// an omitted time zone goes back to the default.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateDevelopmentRequest {
+    pub display_name: String,
+    pub location: Option<String>,
+    pub timezone: Option<String>,
+    pub logo_url: Option<String>,
+    /// Unchanged when omitted
+    pub active: Option<bool>,
+    /// Origins allowed to embed the maps in an iframe, e.g. `https://www.urvic.mx`.
+    /// Unchanged when omitted; an empty list stops the maps from being embedded
+    pub embed_origins: Option<Vec<String>>,
+}
+
+/// A map of a development with the number of lots per sales status.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DevelopmentMapSummary {
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+    pub updated_at: NaiveDateTime,
+    pub thumbnail_url: String,
+    pub total_lots: usize,
+    pub status_counts: Vec<StatusLegendEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DevelopmentOverview {
+    pub development: DevelopmentResponse,
+    pub total_maps: usize,
+    pub total_lots: usize,
+    pub status_counts: Vec<StatusLegendEntry>,
+    pub maps: Vec<DevelopmentMapSummary>,
+}
+
+impl DevelopmentResponse {
+    pub fn from_entity(entity: &Development) -> Self {
+        Self {
+            id: entity.id,
+            prefix: entity.prefix.clone(),
+            display_name: entity.display_name.clone(),
+            location: entity.location.clone(),
+            timezone: entity.timezone.clone(),
+            logo_url: entity.logo_url.clone(),
+            active: entity.active,
+            embed_origins: entity.embed_origins(),
+            created_at: entity.created_at,
+            updated_at: entity.updated_at,
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod development_dto;
\ No newline at end of file
@@ -0,0 +1,71 @@
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+use crate::db::schema::developments;
+
+#[derive(Queryable, Selectable, Debug, Serialize, Deserialize, Clone)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(table_name = developments)]
+pub struct Development {
+    pub id: i32,
+    pub prefix: String,
+    pub display_name: String,
+    pub location: Option<String>,
+    pub timezone: String,
+    pub logo_url: Option<String>,
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Origins that can embed the maps of the development, separated by spaces.
+    pub embed_origins: Option<String>,
+}
+
+impl Development {
+    pub fn embed_origins(&self) -> Vec<String> {
+        self.embed_origins
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Longest development prefix, the size of its column.
+pub const MAX_PREFIX_LENGTH: usize = 50;
+
+/// Prefixes are the start of map names up to the first `-`, so they can't contain one.
+pub fn is_valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix.len() <= MAX_PREFIX_LENGTH
+        && !prefix.contains('-')
+        && !prefix.contains(char::is_whitespace)
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = developments)]
+pub struct NewDevelopment {
+    pub prefix: String,
+    pub display_name: String,
+    pub location: Option<String>,
+    pub timezone: String,
+    pub logo_url: Option<String>,
+    pub active: bool,
+    pub embed_origins: Option<String>,
+}
+
+/// Editable fields of a development. The prefix is fixed: it links the maps and the Zoho
+/// products of the development. `active` and `embed_origins` are left unchanged when `None`.
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = developments)]
+#[diesel(treat_none_as_null = true)]
+pub struct DevelopmentChanges {
+    pub display_name: String,
+    pub location: Option<String>,
+    pub timezone: String,
+    pub logo_url: Option<String>,
+    #[diesel(treat_none_as_null = false)]
+    pub active: Option<bool>,
+    #[diesel(treat_none_as_null = false)]
+    pub embed_origins: Option<Option<String>>,
+}
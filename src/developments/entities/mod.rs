@@ -0,0 +1 @@
+pub mod development_entity;
\ No newline at end of file
@@ -0,0 +1,5 @@
+pub mod development_repository;
+pub mod development_service;
+pub mod development_handler;
+pub mod entities;
+pub mod dto;
\ No newline at end of file
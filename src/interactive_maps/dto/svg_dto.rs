@@ -1,15 +1,664 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::interactive_maps::entities::maps_entity::SvgItem;
+use crate::interactive_maps::entities::map_lot_entity::MapLot;
+use crate::interactive_maps::entities::map_layer_entity::MapLayer;
+use crate::interactive_maps::entities::map_annotation_entity::MapAnnotation;
+use crate::interactive_maps::svg::svg_diff::LotDiff;
+use crate::interactive_maps::svg::svg_geometry::LotGeometry;
+use crate::interactive_maps::svg::svg_optimizer::OptimizationReport;
+use crate::interactive_maps::svg::svg_sanitizer::SanitizationReport;
+use crate::interactive_maps::svg::svg_thumbnails::DEFAULT_THUMBNAIL_SIZE;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SvgInfo {
     pub prefix: String,
     pub name: String,
     pub id: String,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub units_per_meter: Option<f64>,
+    pub development_id: Option<i32>,
+    pub content_hash: Option<String>,
+    pub thumbnail_url: String,
 }
 
+impl SvgInfo {
+    pub fn from_item(item: &SvgItem) -> Self {
+        Self {
+            prefix: item.prefix.clone(),
+            name: item.name.clone(),
+            id: item.id.clone(),
+            version: item.version,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+            units_per_meter: item.units_per_meter,
+            development_id: item.development_id,
+            content_hash: item.content_hash.clone(),
+            thumbnail_url: thumbnail_url(&item.id),
+        }
+    }
+}
+
+/// URL of the default-size thumbnail of a map.
+pub fn thumbnail_url(svg_id: &str) -> String {
+    format!("/api/maps/{}/thumbnail?size={}", svg_id, DEFAULT_THUMBNAIL_SIZE)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LotBoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LotPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A lot of a map. `bbox`, `centroid` and `area` are in SVG units; `area_m2` is only present
+/// once the map has a scale calibration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLotInfo {
+    pub element_id: String,
+    pub lot_number: String,
+    pub bbox: Option<LotBoundingBox>,
+    pub centroid: Option<LotPoint>,
+    pub area: Option<f64>,
+    pub area_m2: Option<f64>,
+}
+
+impl MapLotInfo {
+    pub fn from_lot(lot: MapLot, units_per_meter: Option<f64>) -> Self {
+        let bbox = match (lot.bbox_x, lot.bbox_y, lot.bbox_width, lot.bbox_height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(LotBoundingBox { x, y, width, height }),
+            _ => None,
+        };
+        let centroid = match (lot.centroid_x, lot.centroid_y) {
+            (Some(x), Some(y)) => Some(LotPoint { x, y }),
+            _ => None,
+        };
+        let area_m2 = lot.area
+            .zip(units_per_meter)
+            .map(|(area, units_per_meter)| area / (units_per_meter * units_per_meter));
+
+        Self {
+            element_id: lot.element_id,
+            lot_number: lot.lot_number,
+            bbox,
+            centroid,
+            area: lot.area,
+            area_m2,
+        }
+    }
+}
+
+/// Body of `PUT /api/maps/{id}/scale`. `null` removes the calibration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SvgScaleRequest {
+    pub units_per_meter: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LotStatus {
+    pub element_id: String,
+    pub lot_number: String,
+    pub product_name: String,
+    pub estatus_venta: Option<String>,
+    pub color: String,
+}
+
+/// A status of the map legend, in the order of `status_colors`, with its number of lots.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusLegendEntry {
+    pub name: String,
+    pub color: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnmatchedLot {
+    pub element_id: String,
+    pub lot_number: String,
+    pub product_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnmatchedProduct {
+    pub product_id: String,
+    pub product_name: String,
+    pub estatus_venta: Option<String>,
+}
+
+/// Mismatches between the lots drawn on a map and the products of its prefix in Zoho:
+/// lots without a product, products without a lot, and lots whose `estatus_venta` has no
+/// entry in `status_colors`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReconciliationReport {
+    pub id: String,
+    pub prefix: String,
+    pub total_lots: usize,
+    pub total_products: usize,
+    pub lots_without_product: Vec<UnmatchedLot>,
+    pub products_without_lot: Vec<UnmatchedProduct>,
+    pub statuses_without_color: Vec<LotStatus>,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SvgRequest {
     pub name: String,
     pub content: String,
-}
\ No newline at end of file
+    /// Save the map even if another map has the same content.
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SvgUpdateRequest {
+    pub name: Option<String>,
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgUpdateResponse {
+    pub svg: SvgInfo,
+    pub sanitization: Option<SanitizationReport>,
+    pub optimization: Option<OptimizationReport>,
+    /// Another map with the same content as the new one.
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgSaveResponse {
+    pub id: String,
+    pub content_hash: String,
+    pub sanitization: SanitizationReport,
+    pub optimization: OptimizationReport,
+    /// Set when the map was saved with `allow_duplicate` and another map has the same content.
+    pub duplicate_of: Option<String>,
+}
+
+/// Body of the 409 returned when an upload has the same content as an existing map. The
+/// existing map is only described when it is within the caller's scopes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgDuplicateResponse {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing: Option<SvgInfo>,
+}
+
+/// `manifest.json` of a map archive. Import reads `file`, `name`, `prefix` and
+/// `development_id`; the other fields describe the exported map and are ignored.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MapManifest {
+    pub maps: Vec<MapManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MapManifestEntry {
+    /// Path of the SVG inside the archive.
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Defaults to the file name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Development prefix. Defaults to the one in the name.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Assigns the map to this development, using its prefix. Takes precedence over `prefix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub development_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MapImportStatus {
+    Created,
+    Duplicate,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapImportResult {
+    pub file: String,
+    pub status: MapImportStatus,
+    /// Id of the created map.
+    pub id: Option<String>,
+    /// Map with the same content, for duplicates.
+    pub existing_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapImportReport {
+    pub created: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub results: Vec<MapImportResult>,
+}
+
+/// A map in the trash and when it will be purged automatically.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedSvgInfo {
+    #[serde(flatten)]
+    pub svg: SvgInfo,
+    pub deleted_at: NaiveDateTime,
+    pub purge_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgsByHashResponse {
+    pub content_hash: String,
+    pub maps: Vec<SvgInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgVersionInfo {
+    pub version: i32,
+    pub name: String,
+    pub prefix: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgVersionsResponse {
+    pub id: String,
+    pub current_version: i32,
+    pub versions: Vec<SvgVersionInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgVersionContent {
+    pub version: i32,
+    pub name: String,
+    pub prefix: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgRollbackResponse {
+    pub id: String,
+    pub version: i32,
+    pub restored_from: i32,
+}
+
+/// Creates or replaces a layer. Without `content` only the position and default visibility
+/// of an existing layer change, and no version is created.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapLayerRequest {
+    pub content: Option<String>,
+    pub position: Option<i32>,
+    pub visible_by_default: Option<bool>,
+}
+
+/// A layer of a map. The lots layer is the content of the map itself and always has
+/// position 0; layers with a lower position are drawn below it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayerInfo {
+    pub name: String,
+    pub position: i32,
+    pub visible_by_default: bool,
+    pub version: i32,
+    pub updated_at: NaiveDateTime,
+}
+
+impl MapLayerInfo {
+    pub fn from_layer(layer: &MapLayer) -> Self {
+        Self {
+            name: layer.name.clone(),
+            position: layer.position,
+            visible_by_default: layer.visible_by_default,
+            version: layer.version,
+            updated_at: layer.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayersResponse {
+    pub id: String,
+    pub layers: Vec<MapLayerInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayerSaveResponse {
+    pub layer: MapLayerInfo,
+    pub sanitization: Option<SanitizationReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayerVersionInfo {
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayerVersionsResponse {
+    pub id: String,
+    pub layer: String,
+    pub current_version: i32,
+    pub versions: Vec<MapLayerVersionInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapLayerVersionContent {
+    pub version: i32,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SvgSortField {
+    #[default]
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters and ordering accepted by the map listing.
+#[derive(Debug, Clone, Default)]
+pub struct SvgListFilter {
+    pub prefix: Option<String>,
+    pub development_id: Option<i32>,
+    pub search: Option<String>,
+    pub created_from: Option<NaiveDateTime>,
+    pub created_to: Option<NaiveDateTime>,
+    pub updated_from: Option<NaiveDateTime>,
+    pub updated_to: Option<NaiveDateTime>,
+    pub sort_by: SvgSortField,
+    pub order: SortOrder,
+}
+
+impl SvgListFilter {
+    pub fn from_query(query: &HashMap<String, String>) -> Result<Self, String> {
+        let text = |key: &str| {
+            query
+                .get(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let sort_by = match text("sort_by").as_deref() {
+            None | Some("name") => SvgSortField::Name,
+            Some("created_at") => SvgSortField::CreatedAt,
+            Some("updated_at") => SvgSortField::UpdatedAt,
+            Some(other) => return Err(format!("sort_by must be name, created_at or updated_at, got {}", other)),
+        };
+
+        let order = match text("order").as_deref() {
+            None | Some("asc") => SortOrder::Asc,
+            Some("desc") => SortOrder::Desc,
+            Some(other) => return Err(format!("order must be asc or desc, got {}", other)),
+        };
+
+        let development_id = text("development_id")
+            .map(|value| value.parse::<i32>())
+            .transpose()
+            .map_err(|_| "development_id must be an integer".to_string())?;
+
+        Ok(Self {
+            prefix: text("prefix"),
+            development_id,
+            search: text("search"),
+            created_from: parse_date_bound(query, "created_from", false)?,
+            created_to: parse_date_bound(query, "created_to", true)?,
+            updated_from: parse_date_bound(query, "updated_from", false)?,
+            updated_to: parse_date_bound(query, "updated_to", true)?,
+            sort_by,
+            order,
+        })
+    }
+}
+
+/// Parses `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`. A bare date used as an upper bound covers
+/// the whole day.
+fn parse_date_bound(query: &HashMap<String, String>, key: &str, end_of_day: bool) -> Result<Option<NaiveDateTime>, String> {
+    let value = match query.get(key).map(|value| value.trim()).filter(|value| !value.is_empty()) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(Some(datetime));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("{} must be a date (YYYY-MM-DD) or datetime (YYYY-MM-DDTHH:MM:SS)", key))?;
+
+    let datetime = if end_of_day {
+        date.and_hms_micro_opt(23, 59, 59, 999_999)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+
+    Ok(datetime)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationType {
+    Amenity,
+    Entrance,
+    SalesOffice,
+    Note,
+}
+
+impl AnnotationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationType::Amenity => "amenity",
+            AnnotationType::Entrance => "entrance",
+            AnnotationType::SalesOffice => "sales_office",
+            AnnotationType::Note => "note",
+        }
+    }
+}
+
+/// `internal` annotations are for sales staff; `public` ones can be shown to buyers.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationVisibility {
+    #[default]
+    Internal,
+    Public,
+}
+
+impl AnnotationVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationVisibility::Internal => "internal",
+            AnnotationVisibility::Public => "public",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapAnnotationInfo {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub annotation_type: String,
+    pub x: f64,
+    pub y: f64,
+    pub label: String,
+    pub icon: Option<String>,
+    pub visibility: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl MapAnnotationInfo {
+    pub fn from_annotation(annotation: &MapAnnotation) -> Self {
+        Self {
+            id: annotation.id,
+            annotation_type: annotation.annotation_type.clone(),
+            x: annotation.x,
+            y: annotation.y,
+            label: annotation.label.clone(),
+            icon: annotation.icon.clone(),
+            visibility: annotation.visibility.clone(),
+            created_at: annotation.created_at,
+            updated_at: annotation.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateMapAnnotationRequest {
+    #[serde(rename = "type")]
+    pub annotation_type: AnnotationType,
+    pub x: f64,
+    pub y: f64,
+    pub label: String,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub visibility: AnnotationVisibility,
+}
+
+/// Fields left out are not changed. An empty `icon` removes it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateMapAnnotationRequest {
+    #[serde(rename = "type")]
+    pub annotation_type: Option<AnnotationType>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub label: Option<String>,
+    pub icon: Option<String>,
+    pub visibility: Option<AnnotationVisibility>,
+}
+
+/// `expires_in_hours` defaults to 72. Without `visible_statuses` every sales status is shown.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct CreateShareLinkRequest {
+    pub expires_in_hours: Option<i64>,
+    pub visible_statuses: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShareLinkResponse {
+    pub token: String,
+    pub url: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// A map opened with a share link: content, lot statuses filtered by the link and public annotations.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedMapResponse {
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+    pub content: String,
+    pub statuses: Vec<LotStatus>,
+    pub legend: Vec<StatusLegendEntry>,
+    pub annotations: Vec<MapAnnotationInfo>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// URL of the public view of a share token.
+pub fn shared_map_url(token: &str) -> String {
+    format!("/api/public/maps/{}", token)
+}
+
+/// Query of `GET /api/maps/{id}/diff`: the map at `from_version` against `to` at `to_version`.
+/// Versions default to the current ones and `to` to the same map.
+#[derive(Debug, Deserialize)]
+pub struct MapDiffQuery {
+    pub from_version: Option<i32>,
+    pub to: Option<String>,
+    pub to_version: Option<i32>,
+    pub tolerance: Option<f64>,
+    pub visual: Option<bool>,
+}
+
+/// One side of a comparison.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapDiffSide {
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LotRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// A lot whose outline moved or changed size; `centroid_shift` and `area_change` are in SVG units.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LotGeometryChangeInfo {
+    pub element_id: String,
+    pub before: LotBoundingBox,
+    pub after: LotBoundingBox,
+    pub centroid_shift: f64,
+    pub area_change: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapDiffResponse {
+    pub from: MapDiffSide,
+    pub to: MapDiffSide,
+    pub tolerance: f64,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<LotRename>,
+    pub geometry_changed: Vec<LotGeometryChangeInfo>,
+    pub unchanged: usize,
+}
+
+impl MapDiffResponse {
+    pub fn from_diff(from: MapDiffSide, to: MapDiffSide, tolerance: f64, diff: &LotDiff) -> Self {
+        let bbox = |geometry: &LotGeometry| LotBoundingBox {
+            x: geometry.bbox_x,
+            y: geometry.bbox_y,
+            width: geometry.bbox_width,
+            height: geometry.bbox_height,
+        };
+
+        Self {
+            from,
+            to,
+            tolerance,
+            added: diff.added.clone(),
+            removed: diff.removed.clone(),
+            renamed: diff.renamed
+                .iter()
+                .map(|(from, to)| LotRename { from: from.clone(), to: to.clone() })
+                .collect(),
+            geometry_changed: diff.geometry_changed
+                .iter()
+                .map(|change| LotGeometryChangeInfo {
+                    element_id: change.element_id.clone(),
+                    before: bbox(&change.before),
+                    after: bbox(&change.after),
+                    centroid_shift: change.centroid_shift(),
+                    area_change: change.after.area - change.before.area,
+                })
+                .collect(),
+            unchanged: diff.unchanged,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::db::schema::map_annotations;
+
+/// A point of interest or note on a map, in SVG user units of the root `<svg>`.
+#[derive(Debug, Queryable, Selectable, Serialize, Deserialize, Clone)]
+#[diesel(table_name = map_annotations)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct MapAnnotation {
+    pub id: i32,
+    pub map_id: String,
+    pub annotation_type: String,
+    pub x: f64,
+    pub y: f64,
+    pub label: String,
+    pub icon: Option<String>,
+    pub visibility: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_annotations)]
+pub struct NewMapAnnotation {
+    pub map_id: String,
+    pub annotation_type: String,
+    pub x: f64,
+    pub y: f64,
+    pub label: String,
+    pub icon: Option<String>,
+    pub visibility: String,
+}
+
+/// `None` fields are left unchanged; `icon: Some(None)` removes the icon.
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = map_annotations)]
+pub struct MapAnnotationChanges {
+    pub annotation_type: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub label: Option<String>,
+    pub icon: Option<Option<String>>,
+    pub visibility: Option<String>,
+}
@@ -0,0 +1,36 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::db::schema::{map_layer_versions, map_layers};
+
+/// A layer without its content, which is loaded separately from the storage backend.
+#[derive(Debug, Queryable, Selectable, Serialize, Deserialize, Clone)]
+#[diesel(table_name = map_layers)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct MapLayer {
+    pub id: i32,
+    pub map_id: String,
+    pub name: String,
+    pub position: i32,
+    pub visible_by_default: bool,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_layer_versions)]
+pub struct NewMapLayerVersion {
+    pub layer_id: i32,
+    pub version: i32,
+    pub content: String,
+    pub storage_key: Option<String>,
+}
+
+/// Position and visibility of a layer; `None` fields are left unchanged.
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = map_layers)]
+pub struct MapLayerChanges {
+    pub position: Option<i32>,
+    pub visible_by_default: Option<bool>,
+}
@@ -0,0 +1,37 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::db::schema::map_lots;
+
+#[derive(Debug, Queryable, Selectable, Serialize, Deserialize, Clone)]
+#[diesel(table_name = map_lots)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct MapLot {
+    pub id: i32,
+    pub map_id: String,
+    pub element_id: String,
+    pub lot_number: String,
+    pub created_at: NaiveDateTime,
+    pub bbox_x: Option<f64>,
+    pub bbox_y: Option<f64>,
+    pub bbox_width: Option<f64>,
+    pub bbox_height: Option<f64>,
+    pub centroid_x: Option<f64>,
+    pub centroid_y: Option<f64>,
+    pub area: Option<f64>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_lots)]
+pub struct NewMapLot {
+    pub map_id: String,
+    pub element_id: String,
+    pub lot_number: String,
+    pub bbox_x: Option<f64>,
+    pub bbox_y: Option<f64>,
+    pub bbox_width: Option<f64>,
+    pub bbox_height: Option<f64>,
+    pub centroid_x: Option<f64>,
+    pub centroid_y: Option<f64>,
+    pub area: Option<f64>,
+}
@@ -0,0 +1,10 @@
+use diesel::prelude::*;
+use crate::db::schema::map_thumbnails;
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = map_thumbnails)]
+pub struct NewMapThumbnail {
+    pub map_id: String,
+    pub size: i32,
+    pub png: Vec<u8>,
+}
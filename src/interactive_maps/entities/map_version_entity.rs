@@ -0,0 +1,29 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::db::schema::maps_svg_versions;
+
+#[derive(Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = maps_svg_versions)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct SvgVersion {
+    pub id: i32,
+    pub map_id: String,
+    pub version: i32,
+    pub name: String,
+    pub prefix: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub storage_key: Option<String>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = maps_svg_versions)]
+pub struct NewSvgVersion {
+    pub map_id: String,
+    pub version: i32,
+    pub name: String,
+    pub prefix: String,
+    pub content: String,
+    pub storage_key: Option<String>,
+}
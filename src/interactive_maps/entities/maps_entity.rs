@@ -20,6 +20,18 @@ pub struct SvgItem {
     pub created_at: NaiveDateTime,
     #[diesel(column_name = updated_at)]
     pub updated_at: NaiveDateTime,
+    #[diesel(column_name = version)]
+    pub version: i32,
+    #[diesel(column_name = units_per_meter)]
+    pub units_per_meter: Option<f64>,
+    #[diesel(column_name = storage_key)]
+    pub storage_key: Option<String>,
+    #[diesel(column_name = development_id)]
+    pub development_id: Option<i32>,
+    #[diesel(column_name = content_hash)]
+    pub content_hash: Option<String>,
+    #[diesel(column_name = deleted_at)]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable, Debug)]
@@ -33,16 +45,19 @@ pub struct NewSvgItem {
     pub prefix: String,
     #[diesel(column_name = content)]
     pub content: String,
+    #[diesel(column_name = content_hash)]
+    pub content_hash: String,
 }
 
 
 impl NewSvgItem {
-    pub fn new(name: String, prefix: String, content: String) -> Self {
+    pub fn new(name: String, prefix: String, content: String, content_hash: String) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             name,
             prefix,
             content,
+            content_hash,
         }
     }
 }
\ No newline at end of file
@@ -1 +1,6 @@
-pub mod maps_entity;
\ No newline at end of file
+pub mod maps_entity;
+pub mod map_lot_entity;
+pub mod map_version_entity;
+pub mod map_thumbnail_entity;
+pub mod map_layer_entity;
+pub mod map_annotation_entity;
\ No newline at end of file
@@ -1,18 +1,111 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{http::header, middleware::Compress, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use serde::Deserialize;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use actix_files::NamedFile;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::sync::Arc;
+use std::time::SystemTime;
 use crate::{
+    auth::entities::auth_entities::{Claims, MapAccess},
+    common::{config::Config, errors::ApiError},
     db::DbPool,
+    developments::development_service::{DevelopmentService, DEFAULT_TIMEZONE},
     interactive_maps::{
-        dto::svg_dto::SvgRequest,
-        interactive_maps_service::SvgService,
+        dto::svg_dto::{
+            AnnotationVisibility, CreateMapAnnotationRequest, CreateShareLinkRequest, LotStatus, MapDiffQuery,
+            MapLayerRequest, SharedMapResponse, SvgListFilter, SvgRequest, SvgScaleRequest, SvgUpdateRequest,
+            UpdateMapAnnotationRequest,
+        },
+        entities::maps_entity::SvgItem,
+        interactive_maps_service::{content_hash, etag_matches, prefix_from_name, read_archive_payload, read_svg_payload, svg_etag, SvgSaveOutcome, SvgService},
+        map_embed::{content_security_policy, render_embedded_map, EmbedCache, EmbeddedMap, VIEWER_SCRIPT},
+        map_share::verify_share_token,
+        map_status_service::MapStatusService,
+        storage::map_storage::MapStorage,
+        svg::{
+            svg_pdf::{render_pdf, PdfSheet},
+            svg_renderer::apply_fills,
+            svg_thumbnails::{DEFAULT_THUMBNAIL_SIZE, THUMBNAIL_SIZES},
+        },
     },
 };
 use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Raw SVGs smaller than this are sent uncompressed; compressing them costs more than it saves.
+const RAW_COMPRESSION_MIN_BYTES: usize = 8 * 1024;
 
 #[derive(ToSchema)]
 pub struct SvgRequestSchema {
     pub name: String,
     pub content: String,
+    /// Save the map even if another map has the same content. Ignored when replacing a map
+    pub allow_duplicate: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct SvgUpdateRequestSchema {
+    pub name: Option<String>,
+    pub content: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct MapLayerRequestSchema {
+    /// SVG of the layer, drawn on the same canvas as the map. Omit it to only change the settings
+    pub content: Option<String>,
+    /// Stacking order; the lots layer is 0, so negative values go below the lots
+    pub position: Option<i32>,
+    pub visible_by_default: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct MapAnnotationRequestSchema {
+    /// amenity, entrance, sales_office or note
+    #[schema(rename = "type")]
+    pub annotation_type: String,
+    /// Position in SVG units of the map
+    pub x: f64,
+    pub y: f64,
+    pub label: String,
+    pub icon: Option<String>,
+    /// internal (default) or public
+    pub visibility: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct MapAnnotationUpdateRequestSchema {
+    #[schema(rename = "type")]
+    pub annotation_type: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub label: Option<String>,
+    /// Empty to remove the icon
+    pub icon: Option<String>,
+    pub visibility: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct ShareLinkRequestSchema {
+    /// Hours until the link expires, 1 to 720 (default 72)
+    pub expires_in_hours: Option<i64>,
+    /// Sales statuses shown to whoever opens the link; lots in other statuses are shown without one.
+    /// Omit to show every status
+    pub visible_statuses: Option<Vec<String>>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct SvgScaleRequestSchema {
+    /// SVG units per meter, or null to remove the calibration
+    pub units_per_meter: Option<f64>,
 }
 
 #[utoipa::path(
@@ -20,7 +113,9 @@ pub struct SvgRequestSchema {
     path = "/maps",
     request_body = SvgRequestSchema,
     responses(
-        (status = 200, description = "SVG saved successfully"),
+        (status = 200, description = "SVG sanitized and saved successfully. The response lists what the sanitizer removed"),
+        (status = 403, description = "The prefix of the name is outside the scopes of the session"),
+        (status = 409, description = "Another map has the same content; the response has its id in existing_id when the session can access it. Send allow_duplicate to save it anyway"),
         (status = 500, description = "Internal server error")
     ),
     tag = "SVG"
@@ -29,25 +124,38 @@ pub struct SvgRequestSchema {
 async fn save_svg(
     data: web::Json<SvgRequest>,
     pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
 ) -> impl Responder {
     let conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => return HttpResponse::InternalServerError().body(format!("Connection Error: {}", e)),
     };
 
-    let mut service = SvgService::new(conn);
-    match service.save_svg(data.into_inner()) {
-        Ok(svg_info) => HttpResponse::Ok().json(svg_info),
-        Err(error) => HttpResponse::InternalServerError().body(error),
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_prefix_access(&prefix_from_name(&data.name)) {
+        return error.error_response();
+    }
+    let data = data.into_inner();
+    match web::block(move || service.save_svg(data)).await {
+        Ok(Ok(outcome)) => save_outcome_response(outcome),
+        Ok(Err(error)) => HttpResponse::InternalServerError().body(error),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error al guardar SVG: {}", e)),
     }
 }
 
 #[utoipa::path(
     post,
     path = "/maps/stream",
+    params(
+        ("name" = String, Query, description = "Map name", example = "TC-LOT.svg"),
+        ("allow_duplicate" = Option<bool>, Query, description = "Save the map even if another map has the same content", example = false)
+    ),
     responses(
-        (status = 200, description = "SVG stream saved successfully"),
+        (status = 200, description = "SVG stream sanitized and saved successfully. The response lists what the sanitizer removed"),
         (status = 400, description = "Missing required parameter"),
+        (status = 403, description = "The prefix of the name is outside the scopes of the session"),
+        (status = 409, description = "Another map has the same content; the response has its id in existing_id when the session can access it"),
         (status = 500, description = "Internal server error")
     ),
     tag = "SVG"
@@ -57,24 +165,176 @@ async fn save_svg_stream(
     payload: web::Payload,
     query: web::Query<HashMap<String, String>>,
     pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
 ) -> impl Responder {
     let name = match query.get("name") {
         Some(name) => name.clone(),
         None => return HttpResponse::BadRequest().body("name parameter is required"),
     };
+    let allow_duplicate = query.get("allow_duplicate").is_some_and(|value| value == "true");
 
     let conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
     };
 
-    let mut service = SvgService::new(conn);
-    match service.save_svg_streaming(payload, name).await {
-        Ok(svg_id) => HttpResponse::Ok().json(svg_id),
-        Err(error) => HttpResponse::InternalServerError().body(error),
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_prefix_access(&prefix_from_name(&name)) {
+        return error.error_response();
+    }
+    let content = match read_svg_payload(payload).await {
+        Ok(content) => content,
+        Err(error) => return HttpResponse::InternalServerError().body(error),
+    };
+    let data = SvgRequest { name, content, allow_duplicate };
+    match web::block(move || service.save_svg(data)).await {
+        Ok(Ok(outcome)) => save_outcome_response(outcome),
+        Ok(Err(error)) => HttpResponse::InternalServerError().body(error),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error al guardar SVG: {}", e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/maps/import",
+    params(
+        ("allow_duplicate" = Option<bool>, Query, description = "Save files whose content matches an existing map instead of reporting them as duplicates", example = false)
+    ),
+    request_body(content = Vec<u8>, description = "ZIP with SVG files and an optional manifest.json: {\"maps\": [{\"file\", \"name\", \"prefix\", \"development_id\"}]}. Files are named after their path unless the manifest says otherwise", content_type = "application/zip"),
+    responses(
+        (status = 200, description = "Per-file report: created, duplicate (with the id of the existing map) or failed with the reason"),
+        (status = 400, description = "Not a ZIP, invalid manifest.json or archive too large"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::post("/import")]
+async fn import_svgs(
+    payload: web::Payload,
+    query: web::Query<HashMap<String, String>>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let allow_duplicate = query.get("allow_duplicate").is_some_and(|value| value == "true");
+    let archive = read_archive_payload(payload).await?;
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+
+    let report = web::block(move || service.import_maps(&archive, allow_duplicate))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Error al importar el ZIP: {}", e)))??;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/export",
+    params(
+        ("prefix" = Option<String>, Query, description = "Only maps of this development prefix", example = "TC"),
+        ("development_id" = Option<i32>, Query, description = "Only maps of this development", example = 1),
+        ("search" = Option<String>, Query, description = "Substring to search in the map name", example = "LOT")
+    ),
+    responses(
+        (status = 200, description = "ZIP with each map as <name>.svg and a manifest.json with ids, names, prefixes, timestamps and content hashes, accepted by POST /maps/import", content_type = "application/zip"),
+        (status = 400, description = "Invalid parameters"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/export")]
+async fn export_svgs(
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let filter = SvgListFilter::from_query(&query).map_err(ApiError::BadRequest)?;
+    let file_name = match &filter.prefix {
+        Some(prefix) => format!("maps-{}.zip", prefix),
+        None => "maps.zip".to_string(),
+    };
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+
+    // The ZIP is built in a temporary file, which is unlinked once open and streamed from disk.
+    let path = std::env::temp_dir().join(format!("maps-export-{}.zip", Uuid::new_v4()));
+    let file = web::block(move || {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| ApiError::InternalError(format!("Error al crear el ZIP: {}", e)))?;
+        let _ = std::fs::remove_file(&path);
+
+        let (mut file, _) = service.export_maps(&filter, file)?;
+        file.seek(SeekFrom::Start(0))
+            .map_err(|e| ApiError::InternalError(format!("Error al leer el ZIP: {}", e)))?;
+        Ok::<_, ApiError>(file)
+    })
+    .await
+    .map_err(|e| ApiError::InternalError(format!("Error al generar el ZIP: {}", e)))??;
+
+    let named_file = NamedFile::from_file(file, &file_name)
+        .map_err(|e| ApiError::InternalError(format!("Error al leer el ZIP: {}", e)))?
+        .set_content_type("application/zip".parse().expect("valid mime type"))
+        .set_content_disposition(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters: vec![header::DispositionParam::Filename(file_name)],
+        })
+        .use_etag(false)
+        .use_last_modified(false);
+
+    Ok(named_file.into_response(&req))
+}
+
+fn save_outcome_response(outcome: SvgSaveOutcome) -> HttpResponse {
+    match outcome {
+        SvgSaveOutcome::Saved(response) => HttpResponse::Ok().json(response),
+        SvgSaveOutcome::Duplicate(duplicate) => HttpResponse::Conflict().json(duplicate),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/maps/by-hash/{hash}",
+    params(
+        ("hash" = String, Path, description = "SHA-256 of the map content, as returned in content_hash", example = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")
+    ),
+    responses(
+        (status = 200, description = "Maps whose current content has this hash, oldest first"),
+        (status = 400, description = "Not a hex SHA-256"),
+        (status = 404, description = "No map has this content")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/by-hash/{hash}")]
+async fn get_svgs_by_hash(
+    hash: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    let maps = service.get_svgs_by_hash(&hash)?;
+
+    Ok(HttpResponse::Ok().json(maps))
+}
+
 #[utoipa::path(
     get,
     path = "/maps/{id}",
@@ -82,7 +342,7 @@ async fn save_svg_stream(
         ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
     ),
     responses(
-        (status = 200, description = "SVG retrieved successfully"),
+        (status = 200, description = "SVG retrieved successfully as [info, content, annotations]; without a session only public annotations are included. The ETag header is the value to send in If-Match when updating"),
         (status = 404, description = "SVG not found")
     ),
     tag = "SVG"
@@ -92,84 +352,1511 @@ async fn save_svg_stream(
 async fn get_svg_by_id(
     id: web::Path<String>,
     pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: Option<web::ReqData<Claims>>,
 ) -> impl Responder {
     let conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
     };
 
-    let mut service = SvgService::new(conn);
-    match service.get_svg_by_id(id.to_string()) {
-        Ok((svg_info, svg_content)) => HttpResponse::Ok().json((svg_info, svg_content)),
-        Err(error) => HttpResponse::NotFound().body(error),
+    // Also served without a session under `/api/zoho` for the Zoho integration, which
+    // reads every map but only its public annotations.
+    let visibility = claims.is_none().then_some(AnnotationVisibility::Public);
+    let access = claims.map(|claims| claims.map_access()).unwrap_or(MapAccess::All);
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(access);
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
     }
+    let (svg_info, svg_content) = match service.get_svg_by_id(id.to_string()) {
+        Ok(svg) => svg,
+        Err(error) => return HttpResponse::NotFound().body(error),
+    };
+    let annotations = match service.get_annotations(id.to_string(), visibility) {
+        Ok(annotations) => annotations,
+        Err(error) => return error.error_response(),
+    };
+
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, svg_etag(svg_info.updated_at)))
+        .json((svg_info, svg_content, annotations))
 }
 
 #[utoipa::path(
-    delete,
-    path = "/maps/delete/{id}",
+    get,
+    path = "/maps/{id}/raw",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of the cached copy"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Last-Modified of the cached copy")
+    ),
+    responses(
+        (status = 200, description = "SVG content with ETag and Last-Modified headers. Large maps are gzip/brotli compressed when the client accepts it", content_type = "image/svg+xml"),
+        (status = 304, description = "The cached copy is still current"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/raw", wrap = "Compress::default()")]
+async fn get_raw_svg(
+    req: HttpRequest,
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> impl Responder {
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    let updated_at = match service.get_svg_updated_at(id.to_string()) {
+        Ok(updated_at) => updated_at,
+        Err(error) => return HttpResponse::NotFound().body(error),
+    };
+
+    if is_not_modified(&req, updated_at) {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, svg_etag(updated_at)))
+            .insert_header((header::LAST_MODIFIED, http_date(updated_at)))
+            .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+            .finish();
+    }
+
+    let (svg_info, svg_content) = match service.get_svg_by_id(id.to_string()) {
+        Ok(result) => result,
+        Err(error) => return HttpResponse::NotFound().body(error),
+    };
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type("image/svg+xml")
+        .insert_header((header::ETAG, svg_etag(svg_info.updated_at)))
+        .insert_header((header::LAST_MODIFIED, http_date(svg_info.updated_at)))
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"));
+
+    if svg_content.len() < RAW_COMPRESSION_MIN_BYTES {
+        response.insert_header(header::ContentEncoding::Identity);
+    }
+
+    response.body(svg_content)
+}
+
+/// `If-None-Match` takes precedence over `If-Modified-Since`, as in RFC 9110.
+/// `Last-Modified` only has second precision, so the comparison truncates `updated_at`.
+fn is_not_modified(req: &HttpRequest, updated_at: NaiveDateTime) -> bool {
+    if let Some(if_none_match) = req.headers().get(header::IF_NONE_MATCH) {
+        return if_none_match
+            .to_str()
+            .map(|value| etag_matches(value, &svg_etag(updated_at)))
+            .unwrap_or(false);
+    }
+
+    req.headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<header::HttpDate>().ok())
+        .map(|since| updated_at.and_utc().timestamp() <= DateTime::<Utc>::from(SystemTime::from(since)).timestamp())
+        .unwrap_or(false)
+}
+
+fn http_date(updated_at: NaiveDateTime) -> header::HttpDate {
+    header::HttpDate::from(SystemTime::from(updated_at.and_utc()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/maps/{id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-Match" = String, Header, description = "ETag returned when the map was read")
+    ),
+    request_body = SvgRequestSchema,
+    responses(
+        (status = 200, description = "SVG replaced successfully. The new ETag is returned in the ETag header"),
+        (status = 400, description = "Invalid SVG"),
+        (status = 404, description = "SVG not found"),
+        (status = 412, description = "The map was modified since it was read"),
+        (status = 428, description = "Missing If-Match header")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::put("/{id}")]
+async fn replace_svg(
+    req: HttpRequest,
+    id: web::Path<String>,
+    data: web::Json<SvgRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let data = data.into_inner();
+    update_svg(req, id.into_inner(), SvgUpdateRequest { name: Some(data.name), content: Some(data.content) }, pool, storage, claims).await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/maps/{id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-Match" = String, Header, description = "ETag returned when the map was read")
+    ),
+    request_body = SvgUpdateRequestSchema,
+    responses(
+        (status = 200, description = "SVG updated successfully. The new ETag is returned in the ETag header"),
+        (status = 400, description = "Invalid SVG or empty update"),
+        (status = 404, description = "SVG not found"),
+        (status = 412, description = "The map was modified since it was read"),
+        (status = 428, description = "Missing If-Match header")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::patch("/{id}")]
+async fn patch_svg(
+    req: HttpRequest,
+    id: web::Path<String>,
+    data: web::Json<SvgUpdateRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    update_svg(req, id.into_inner(), data.into_inner(), pool, storage, claims).await
+}
+
+async fn update_svg(
+    req: HttpRequest,
+    id: String,
+    data: SvgUpdateRequest,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let if_match = req
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let (response, etag) = web::block(move || service.update_svg(id, if_match.as_deref(), data))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Error al actualizar SVG: {}", e)))??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/lots",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Lots of the map with bounding box, centroid and area in SVG units. area_m2 is present when the map has a scale"),
+        (status = 404, description = "SVG not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/lots")]
+async fn get_svg_lots(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let lots = service.get_svg_lots(id.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(lots))
+}
+
+#[utoipa::path(
+    put,
+    path = "/maps/{id}/scale",
     params(
         ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
     ),
+    request_body = SvgScaleRequestSchema,
     responses(
-        (status = 200, description = "SVG deleted successfully"),
+        (status = 200, description = "Scale calibration updated"),
+        (status = 400, description = "units_per_meter is not a positive number"),
         (status = 404, description = "SVG not found")
     ),
     tag = "SVG"
 )]
-#[actix_web::delete("/delete/{id}")]
-async fn delete_svg_by_id(
+#[actix_web::put("/{id}/scale")]
+async fn set_svg_scale(
+    id: web::Path<String>,
+    data: web::Json<SvgScaleRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let svg_info = service.set_svg_scale(id.into_inner(), data.into_inner().units_per_meter)?;
+
+    Ok(HttpResponse::Ok().json(svg_info))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/reconciliation",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Lots without a product, products of the map prefix without a lot, and lots whose status has no color"),
+        (status = 404, description = "SVG not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/reconciliation")]
+async fn get_svg_reconciliation(
     id: web::Path<String>,
     pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
 ) -> impl Responder {
     let conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
     };
 
-    let mut service = SvgService::new(conn);
-    match service.delete_svg_by_id(id.to_string()) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(error) => HttpResponse::NotFound().body(error),
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    let (svg_item, lots) = match service.get_svg_with_lots(id.to_string()) {
+        Ok(result) => result,
+        Err(error) => return HttpResponse::NotFound().body(error),
+    };
+    drop(service);
+
+    match map_status_service.reconcile(&svg_item.id, &svg_item.prefix, &lots).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(error) => HttpResponse::InternalServerError().body(error.to_string()),
     }
 }
 
 #[utoipa::path(
     get,
-    path = "/maps",
+    path = "/maps/{id}/rendered",
     params(
-        ("page" = u32, Query, description = "Page number for pagination", example = 1),
-        ("per_page" = u32, Query, description = "Number of items per page", example = 10)
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
     ),
     responses(
-        (status = 200, description = "SVGs retrieved successfully"),
-        (status = 400, description = "Invalid parameters"),
+        (status = 200, description = "SVG with every lot filled with the color of its sales status", content_type = "image/svg+xml"),
+        (status = 404, description = "SVG not found"),
         (status = 500, description = "Internal server error")
     ),
     tag = "SVG"
 )]
-#[actix_web::get("")]
-async fn get_paginated_svgs(
+#[actix_web::get("/{id}/rendered")]
+async fn get_rendered_svg(
+    id: web::Path<String>,
     pool: web::Data<DbPool>,
-    query: web::Query<HashMap<String, String>>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
 ) -> impl Responder {
-    let page = query.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
-    let per_page = query
-        .get("per_page")
-        .and_then(|pp| pp.parse().ok())
-        .unwrap_or(10);
+    match render_map(&id, &pool, &storage, &map_status_service, claims.map_access()).await {
+        Ok((_, _, rendered)) => HttpResponse::Ok().content_type("image/svg+xml").body(rendered),
+        Err(response) => response,
+    }
+}
 
-    if page < 1 || per_page < 1 {
-        return HttpResponse::BadRequest().body("page and per_page must be positive integers");
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/export.pdf",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Printable A4 sheet with the map colored by sales status, the legend with lots per status, the development and the generation time in its time zone", content_type = "application/pdf"),
+        (status = 404, description = "SVG not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/export.pdf")]
+async fn export_svg_pdf(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+) -> impl Responder {
+    let (svg_item, statuses, rendered) = match render_map(&id, &pool, &storage, &map_status_service, claims.map_access()).await {
+        Ok(result) => result,
+        Err(response) => return response,
+    };
+
+    let legend = match map_status_service.status_legend(&statuses) {
+        Ok(legend) => legend,
+        Err(error) => return HttpResponse::InternalServerError().body(error.to_string()),
+    };
+
+    let development = match svg_item.development_id.map(|id| development_service.get_development(id)) {
+        Some(Ok(development)) => Some(development),
+        Some(Err(ApiError::NotFound(_))) | None => None,
+        Some(Err(error)) => return HttpResponse::InternalServerError().body(error.to_string()),
+    };
+
+    let (development_label, timezone) = match &development {
+        Some(development) => (
+            format!("{} ({})", development.display_name, development.prefix),
+            development.timezone.as_str(),
+        ),
+        None => (svg_item.prefix.clone(), DEFAULT_TIMEZONE),
+    };
+    let timezone: Tz = timezone.parse().unwrap_or(Tz::America__Mexico_City);
+    let generated_at = Utc::now().with_timezone(&timezone).format("%Y-%m-%d %H:%M %Z").to_string();
+
+    let title = svg_item.name.clone();
+    let pdf = web::block(move || {
+        let sheet = PdfSheet {
+            title: &title,
+            development: &development_label,
+            generated_at: &generated_at,
+            legend: &legend,
+        };
+        render_pdf(&rendered, &sheet)
+    })
+    .await;
+
+    match pdf {
+        Ok(Ok(pdf)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(header::ContentDisposition {
+                disposition: header::DispositionType::Inline,
+                parameters: vec![header::DispositionParam::Filename(format!("{}.pdf", svg_item.name))],
+            })
+            .body(pdf),
+        Ok(Err(error)) => HttpResponse::InternalServerError().body(error),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error al generar el PDF: {}", e)),
     }
+}
+
+/// Loads a map and fills each lot with the color of its sales status.
+async fn render_map(
+    id: &str,
+    pool: &DbPool,
+    storage: &Arc<dyn MapStorage>,
+    map_status_service: &MapStatusService,
+    access: MapAccess,
+) -> Result<(SvgItem, Vec<LotStatus>, String), HttpResponse> {
+    let conn = pool
+        .get()
+        .map_err(|e| HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.clone()).with_access(access);
+    service.ensure_map_access(id).map_err(|error| error.error_response())?;
+    let (svg_item, lots) = service
+        .get_svg_with_lots(id.to_string())
+        .map_err(|error| HttpResponse::NotFound().body(error))?;
+    drop(service);
+
+    let statuses = map_status_service
+        .resolve_lot_statuses(&svg_item.prefix, &lots)
+        .await
+        .map_err(|error| HttpResponse::InternalServerError().body(error.to_string()))?;
+
+    let fills: HashMap<String, String> = statuses
+        .iter()
+        .map(|status| (status.element_id.clone(), status.color.clone()))
+        .collect();
+
+    let rendered = apply_fills(&svg_item.content, &fills)
+        .map_err(|error| HttpResponse::InternalServerError().body(error))?;
+
+    Ok((svg_item, statuses, rendered))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/thumbnail",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("size" = Option<i32>, Query, description = "Longest side in pixels: 128, 256 (default) or 512", example = 256)
+    ),
+    responses(
+        (status = 200, description = "PNG thumbnail of the map", content_type = "image/png"),
+        (status = 400, description = "Unsupported size"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/thumbnail")]
+async fn get_svg_thumbnail(
+    id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> impl Responder {
+    let size = match query.get("size") {
+        Some(size) => match size.parse::<i32>() {
+            Ok(size) if THUMBNAIL_SIZES.contains(&size) => size,
+            _ => return HttpResponse::BadRequest().body(format!("size must be one of {:?}", THUMBNAIL_SIZES)),
+        },
+        None => DEFAULT_THUMBNAIL_SIZE,
+    };
+
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    // Maps without thumbnails are rasterized here, so it runs on a blocking thread.
+    match web::block(move || service.get_thumbnail(id.into_inner(), size)).await {
+        Ok(Ok(png)) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((header::CACHE_CONTROL, "private, max-age=300"))
+            .body(png),
+        Ok(Err(error)) => HttpResponse::NotFound().body(error),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error al generar miniatura: {}", e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/versions",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Current version and previous revisions of the map"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/versions")]
+async fn get_svg_versions(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> impl Responder {
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    match service.get_svg_versions(id.to_string()) {
+        Ok(versions) => HttpResponse::Ok().json(versions),
+        Err(error) => HttpResponse::NotFound().body(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/versions/{version}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("version" = i32, Path, description = "Version number", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Content of the requested version"),
+        (status = 404, description = "SVG or version not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/versions/{version}")]
+async fn get_svg_version(
+    path: web::Path<(String, i32)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> impl Responder {
+    let (id, version) = path.into_inner();
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    match service.get_svg_version(id, version) {
+        Ok(svg_version) => HttpResponse::Ok().json(svg_version),
+        Err(error) => HttpResponse::NotFound().body(error),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OriginalQuery {
+    version: Option<i32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/original",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("version" = Option<i32>, Query, description = "Version to download. Defaults to the current version", example = 1)
+    ),
+    responses(
+        (status = 200, description = "SVG as uploaded, before the optimization applied on save", content_type = "image/svg+xml"),
+        (status = 404, description = "SVG or version not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/original")]
+async fn get_svg_original(
+    id: web::Path<String>,
+    query: web::Query<OriginalQuery>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let (name, content) = service.get_svg_original(id.into_inner(), query.version)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters: vec![header::DispositionParam::Filename(format!("{}.svg", name))],
+        })
+        .body(content))
+}
+
+#[utoipa::path(
+    post,
+    path = "/maps/{id}/versions/{version}/rollback",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("version" = i32, Path, description = "Version to restore", example = 1),
+        ("If-Match" = String, Header, description = "ETag returned when the map was read")
+    ),
+    responses(
+        (status = 200, description = "Version sanitized, optimized and restored as the new current version of the map. The new ETag is returned in the ETag header"),
+        (status = 400, description = "The version is not a valid SVG"),
+        (status = 404, description = "SVG or version not found"),
+        (status = 412, description = "The map was modified since it was read"),
+        (status = 428, description = "Missing If-Match header"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::post("/{id}/versions/{version}/rollback")]
+async fn rollback_svg_version(
+    req: HttpRequest,
+    path: web::Path<(String, i32)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, version) = path.into_inner();
+    let if_match = req
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let (response, etag) = web::block(move || service.rollback_svg(id, version, if_match.as_deref()))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Error al restaurar la versión: {}", e)))??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/diff",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("from_version" = Option<i32>, Query, description = "Version of the map to compare from; the current one by default", example = 1),
+        ("to" = Option<String>, Query, description = "Map to compare against; the same map by default"),
+        ("to_version" = Option<i32>, Query, description = "Version of the compared map; the current one by default", example = 2),
+        ("tolerance" = Option<f64>, Query, description = "SVG units a lot outline can move or grow before it counts as changed (default 1)", example = 1.0),
+        ("visual" = Option<bool>, Query, description = "Return the compared map with added lots in green, renamed in blue, changed in orange and removed lots outlined in red")
+    ),
+    responses(
+        (status = 200, description = "Lot element ids added, removed and renamed, and lots whose outline changed beyond the tolerance; an SVG when visual=true"),
+        (status = 400, description = "Invalid tolerance, or both sides are the same version"),
+        (status = 404, description = "SVG or version not found"),
+        (status = 422, description = "One of the SVGs can't be analyzed")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/diff")]
+async fn get_svg_diff(
+    id: web::Path<String>,
+    query: web::Query<MapDiffQuery>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+
+    // Both maps are parsed and measured, so the comparison runs on a blocking thread.
+    let id = id.into_inner();
+    let query = query.into_inner();
+    if query.visual.unwrap_or(false) {
+        let rendered = web::block(move || service.render_svg_diff(id, &query))
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Error al comparar los mapas: {}", e)))??;
+        return Ok(HttpResponse::Ok().content_type("image/svg+xml").body(rendered));
+    }
+
+    let diff = web::block(move || service.diff_svgs(id, &query))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Error al comparar los mapas: {}", e)))??;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/layers",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Layers of the map, bottom first. The lots layer is the map content and sits at position 0"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/layers")]
+async fn get_svg_layers(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let layers = service.get_layers(id.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(layers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/layers/{name}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("name" = String, Path, description = "Layer name", example = "amenities")
+    ),
+    responses(
+        (status = 200, description = "SVG of the layer", content_type = "image/svg+xml"),
+        (status = 404, description = "SVG or layer not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/layers/{name}")]
+async fn get_svg_layer(
+    path: web::Path<(String, String)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, name) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let content = service.get_layer_content(id, name)?;
+
+    Ok(HttpResponse::Ok().content_type("image/svg+xml").body(content))
+}
+
+#[utoipa::path(
+    put,
+    path = "/maps/{id}/layers/{name}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("name" = String, Path, description = "Layer name: lowercase letters, digits, '-' or '_'", example = "amenities")
+    ),
+    request_body = MapLayerRequestSchema,
+    responses(
+        (status = 200, description = "Layer created, or replaced as a new version. The response lists what the sanitizer removed"),
+        (status = 400, description = "Invalid layer name, position or SVG"),
+        (status = 404, description = "SVG not found, or layer not found when only its settings are sent")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::put("/{id}/layers/{name}")]
+async fn save_svg_layer(
+    path: web::Path<(String, String)>,
+    data: web::Json<MapLayerRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, name) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let response = service.save_layer(id, name, data.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/maps/{id}/layers/{name}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("name" = String, Path, description = "Layer name", example = "amenities")
+    ),
+    responses(
+        (status = 204, description = "Layer and its versions deleted"),
+        (status = 404, description = "SVG or layer not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::delete("/{id}/layers/{name}")]
+async fn delete_svg_layer(
+    path: web::Path<(String, String)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, name) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    service.delete_layer(id, name)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/layers/{name}/versions",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("name" = String, Path, description = "Layer name", example = "amenities")
+    ),
+    responses(
+        (status = 200, description = "Current version of the layer and its previous versions"),
+        (status = 404, description = "SVG or layer not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/layers/{name}/versions")]
+async fn get_svg_layer_versions(
+    path: web::Path<(String, String)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, name) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let versions = service.get_layer_versions(id, name)?;
+
+    Ok(HttpResponse::Ok().json(versions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/layers/{name}/versions/{version}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("name" = String, Path, description = "Layer name", example = "amenities"),
+        ("version" = i32, Path, description = "Version number", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Content of the requested version of the layer"),
+        (status = 404, description = "SVG, layer or version not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/layers/{name}/versions/{version}")]
+async fn get_svg_layer_version(
+    path: web::Path<(String, String, i32)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, name, version) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let layer_version = service.get_layer_version(id, name, version)?;
+
+    Ok(HttpResponse::Ok().json(layer_version))
+}
+
+#[derive(Debug, Deserialize)]
+struct AnnotationsQuery {
+    visibility: Option<AnnotationVisibility>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/annotations",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("visibility" = Option<String>, Query, description = "Only annotations with this visibility: internal or public", example = "public")
+    ),
+    responses(
+        (status = 200, description = "Annotations of the map in creation order"),
+        (status = 400, description = "Invalid visibility"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/annotations")]
+async fn get_svg_annotations(
+    id: web::Path<String>,
+    query: web::Query<AnnotationsQuery>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let annotations = service.get_annotations(id.into_inner(), query.visibility)?;
+
+    Ok(HttpResponse::Ok().json(annotations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/maps/{id}/annotations",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    request_body = MapAnnotationRequestSchema,
+    responses(
+        (status = 201, description = "Annotation created"),
+        (status = 400, description = "Invalid type, coordinates, label, icon or visibility"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::post("/{id}/annotations")]
+async fn create_svg_annotation(
+    id: web::Path<String>,
+    data: web::Json<CreateMapAnnotationRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let annotation = service.create_annotation(id.into_inner(), data.into_inner())?;
+
+    Ok(HttpResponse::Created().json(annotation))
+}
+
+#[utoipa::path(
+    put,
+    path = "/maps/{id}/annotations/{annotation_id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("annotation_id" = i32, Path, description = "Annotation id", example = 1)
+    ),
+    request_body = MapAnnotationUpdateRequestSchema,
+    responses(
+        (status = 200, description = "Annotation updated"),
+        (status = 400, description = "No fields, or an invalid one"),
+        (status = 404, description = "SVG or annotation not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::put("/{id}/annotations/{annotation_id}")]
+async fn update_svg_annotation(
+    path: web::Path<(String, i32)>,
+    data: web::Json<UpdateMapAnnotationRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, annotation_id) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let annotation = service.update_annotation(id, annotation_id, data.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(annotation))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/maps/{id}/annotations/{annotation_id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("annotation_id" = i32, Path, description = "Annotation id", example = 1)
+    ),
+    responses(
+        (status = 204, description = "Annotation deleted"),
+        (status = 404, description = "SVG or annotation not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::delete("/{id}/annotations/{annotation_id}")]
+async fn delete_svg_annotation(
+    path: web::Path<(String, i32)>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, annotation_id) = path.into_inner();
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    service.delete_annotation(id, annotation_id)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/maps/{id}/share",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    request_body = ShareLinkRequestSchema,
+    responses(
+        (status = 201, description = "Signed link that opens the map and its lot statuses without a session until it expires"),
+        (status = 400, description = "Invalid expiration or statuses"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::post("/{id}/share")]
+async fn create_svg_share_link(
+    id: web::Path<String>,
+    data: web::Json<CreateShareLinkRequest>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let share_link = service.create_share_link(id.into_inner(), &config.share_token_secret, data.into_inner())?;
+
+    Ok(HttpResponse::Created().json(share_link))
+}
+
+#[utoipa::path(
+    get,
+    path = "/public/maps/{token}",
+    params(
+        ("token" = String, Path, description = "Share token returned by POST /maps/{id}/share")
+    ),
+    responses(
+        (status = 200, description = "Map content, lot statuses allowed by the link, legend and public annotations; no session needed"),
+        (status = 401, description = "Invalid or expired link"),
+        (status = 404, description = "The map was deleted")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/maps/{token}")]
+async fn get_shared_svg(
+    token: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    config: web::Data<Config>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
+) -> Result<HttpResponse, ApiError> {
+    let share = verify_share_token(&config.share_token_secret, &token)?;
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone());
+    let (svg_item, lots) = service
+        .get_svg_with_lots(share.map_id.clone())
+        .map_err(|_| ApiError::NotFound("SVG no encontrado".to_string()))?;
+    let annotations = service.get_annotations(share.map_id.clone(), Some(AnnotationVisibility::Public))?;
+    drop(service);
+
+    let mut statuses = map_status_service.resolve_lot_statuses(&svg_item.prefix, &lots).await?;
+    share.apply_status_policy(&mut statuses);
+    let legend = map_status_service.status_legend(&statuses)?;
+
+    let shared_map = SharedMapResponse {
+        id: svg_item.id,
+        name: svg_item.name,
+        version: svg_item.version,
+        content: svg_item.content,
+        statuses,
+        legend,
+        annotations,
+        expires_at: share.expires_at(),
+    };
+
+    // The token is in the URL, so it must not be cached by proxies nor leak through the Referer.
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+        .insert_header((header::REFERRER_POLICY, "no-referrer"))
+        .json(shared_map))
+}
+
+#[utoipa::path(
+    get,
+    path = "/public/embed/maps/{id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of the cached copy")
+    ),
+    responses(
+        (status = 200, description = "Standalone HTML viewer of the map colored by sales status, with tooltips and legend, for an iframe. Only the embed origins of the development can frame it", content_type = "text/html"),
+        (status = 304, description = "The cached copy is still current"),
+        (status = 404, description = "The map doesn't exist or its development has no embed origins")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/embed/maps/{id}")]
+async fn get_embedded_svg(
+    req: HttpRequest,
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
+    development_service: web::Data<Arc<DevelopmentService>>,
+    embed_cache: web::Data<Arc<EmbedCache>>,
+) -> Result<HttpResponse, ApiError> {
+    let embedded = match embed_cache.get(&id) {
+        Some(embedded) => embedded,
+        None => {
+            let embedded = build_embedded_map(&id, &pool, &storage, &map_status_service, &development_service).await?;
+            embed_cache.insert(id.into_inner(), embedded)
+        }
+    };
+
+    let cache_control = format!("public, max-age={}", embed_cache.ttl_seconds());
+    let not_modified = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &embedded.etag));
+
+    let mut response = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    response
+        .insert_header((header::ETAG, embedded.etag.clone()))
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .insert_header((header::CONTENT_SECURITY_POLICY, embedded.content_security_policy.clone()))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+
+    if not_modified {
+        return Ok(response.finish());
+    }
+    Ok(response.content_type("text/html; charset=utf-8").body(embedded.html.clone()))
+}
+
+/// Renders the embedded page of a map. Maps of inactive developments, or of developments
+/// without embed origins, are reported as missing.
+async fn build_embedded_map(
+    id: &str,
+    pool: &DbPool,
+    storage: &Arc<dyn MapStorage>,
+    map_status_service: &MapStatusService,
+    development_service: &DevelopmentService,
+) -> Result<EmbeddedMap, ApiError> {
+    let not_embeddable = || ApiError::NotFound("Mapa no disponible para insertar".to_string());
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+    let (svg_item, lots) = SvgService::new(conn, storage.clone())
+        .get_svg_with_lots(id.to_string())
+        .map_err(|_| not_embeddable())?;
+
+    let development = match svg_item.development_id.map(|id| development_service.get_development(id)) {
+        Some(Ok(development)) => development,
+        Some(Err(ApiError::NotFound(_))) | None => return Err(not_embeddable()),
+        Some(Err(error)) => return Err(error),
+    };
+    let origins = development.embed_origins();
+    if !development.active || origins.is_empty() {
+        return Err(not_embeddable());
+    }
+
+    let statuses = map_status_service.resolve_lot_statuses(&svg_item.prefix, &lots).await?;
+    let legend = map_status_service.status_legend(&statuses)?;
+    let fills: HashMap<String, String> = statuses
+        .iter()
+        .map(|status| (status.element_id.clone(), status.color.clone()))
+        .collect();
+
+    let rendered = apply_fills(&svg_item.content, &fills).map_err(ApiError::InternalError)?;
+    let html = render_embedded_map(&svg_item.name, &rendered, &statuses, &legend).map_err(ApiError::InternalError)?;
+
+    Ok(EmbeddedMap {
+        etag: format!("\"{}\"", content_hash(&html)),
+        content_security_policy: content_security_policy(&origins),
+        html,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/public/embed/viewer.js",
+    responses(
+        (status = 200, description = "Script of the embedded map viewer", content_type = "text/javascript")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/embed/viewer.js")]
+async fn get_embed_viewer_script() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/javascript; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+        .body(VIEWER_SCRIPT)
+}
+
+#[derive(Debug, Deserialize)]
+struct ComposedQuery {
+    layers: Option<String>,
+    colored: Option<bool>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/composed",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("layers" = Option<String>, Query, description = "Comma-separated layers to include, e.g. lots,streets,amenities. Defaults to the lots and the layers visible by default; empty for none", example = "lots,amenities"),
+        ("colored" = Option<bool>, Query, description = "Fill the lots with the color of their sales status", example = true)
+    ),
+    responses(
+        (status = 200, description = "Single SVG with the selected layers, bottom first", content_type = "image/svg+xml"),
+        (status = 400, description = "The map has no layer with one of the given names"),
+        (status = 404, description = "SVG not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/{id}/composed")]
+async fn get_composed_svg(
+    id: web::Path<String>,
+    query: web::Query<ComposedQuery>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    map_status_service: web::Data<Arc<MapStatusService>>,
+) -> impl Responder {
+    let base = if query.colored.unwrap_or(false) {
+        match render_map(&id, &pool, &storage, &map_status_service, claims.map_access()).await {
+            Ok((_, _, rendered)) => rendered,
+            Err(response) => return response,
+        }
+    } else {
+        let conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+        };
+        let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+        if let Err(error) = service.ensure_map_access(&id) {
+            return error.error_response();
+        }
+        match service.get_svg_by_id(id.to_string()) {
+            Ok((_, content)) => content,
+            Err(error) => return HttpResponse::NotFound().body(error),
+        }
+    };
+
+    let selection: Option<Vec<String>> = query.layers.as_deref().map(|layers| {
+        layers
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    });
+
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    match service.compose_map(&id, &base, selection.as_deref()) {
+        Ok(composed) => HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+            .body(composed),
+        Err(error) => error.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/maps/delete/{id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "SVG moved to the trash. It can be restored until it is purged"),
+        (status = 404, description = "SVG not found")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::delete("/delete/{id}")]
+async fn delete_svg_by_id(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> impl Responder {
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
+    };
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    if let Err(error) = service.ensure_map_access(&id) {
+        return error.error_response();
+    }
+    match service.delete_svg_by_id(id.to_string()) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(error) => HttpResponse::NotFound().body(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/trash",
+    params(
+        ("page" = Option<u32>, Query, description = "Page number for pagination", example = 1),
+        ("per_page" = Option<u32>, Query, description = "Number of items per page", example = 10)
+    ),
+    responses(
+        (status = 200, description = "Maps in the trash, most recently deleted first, with the date each one will be purged"),
+        (status = 400, description = "Invalid parameters"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("/trash")]
+async fn get_trashed_svgs(
+    query: web::Query<HashMap<String, String>>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let page = query.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+    let per_page = query
+        .get("per_page")
+        .and_then(|pp| pp.parse().ok())
+        .unwrap_or(10);
+
+    if page < 1 || per_page < 1 {
+        return Err(ApiError::BadRequest("page and per_page must be positive integers".to_string()));
+    }
+
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    let trash = service.get_trash(page, per_page, config.map_trash_retention_days)?;
+
+    Ok(HttpResponse::Ok().json(trash))
+}
+
+#[utoipa::path(
+    post,
+    path = "/maps/trash/{id}/restore",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 200, description = "Map taken out of the trash"),
+        (status = 404, description = "The map is not in the trash")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::post("/trash/{id}/restore")]
+async fn restore_svg(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    let svg = service.restore_svg(id.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(svg))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/maps/trash/{id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000")
+    ),
+    responses(
+        (status = 204, description = "Map, revisions and layers permanently deleted"),
+        (status = 404, description = "The map is not in the trash")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::delete("/trash/{id}")]
+async fn purge_svg(
+    id: web::Path<String>,
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, ApiError> {
+    let conn = pool
+        .get()
+        .map_err(|e| ApiError::InternalError(format!("Error de conexión: {}", e)))?;
+
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    service.ensure_map_access(&id)?;
+    service.purge_svg(id.into_inner())?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps",
+    params(
+        ("page" = u32, Query, description = "Page number for pagination", example = 1),
+        ("per_page" = u32, Query, description = "Number of items per page", example = 10),
+        ("prefix" = Option<String>, Query, description = "Only maps of this development prefix", example = "TC"),
+        ("development_id" = Option<i32>, Query, description = "Only maps of this development", example = 1),
+        ("search" = Option<String>, Query, description = "Substring to search in the map name", example = "LOT"),
+        ("created_from" = Option<String>, Query, description = "Created on or after (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"),
+        ("created_to" = Option<String>, Query, description = "Created on or before (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"),
+        ("updated_from" = Option<String>, Query, description = "Updated on or after (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"),
+        ("updated_to" = Option<String>, Query, description = "Updated on or before (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"),
+        ("sort_by" = Option<String>, Query, description = "name (default), created_at or updated_at"),
+        ("order" = Option<String>, Query, description = "asc (default) or desc")
+    ),
+    responses(
+        (status = 200, description = "SVGs retrieved successfully"),
+        (status = 400, description = "Invalid parameters"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "SVG"
+)]
+#[actix_web::get("")]
+async fn get_paginated_svgs(
+    pool: web::Data<DbPool>,
+    storage: web::Data<Arc<dyn MapStorage>>,
+    claims: web::ReqData<Claims>,
+    query: web::Query<HashMap<String, String>>,
+) -> impl Responder {
+    let page = query.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+    let per_page = query
+        .get("per_page")
+        .and_then(|pp| pp.parse().ok())
+        .unwrap_or(10);
+
+    if page < 1 || per_page < 1 {
+        return HttpResponse::BadRequest().body("page and per_page must be positive integers");
+    }
+
+    let filter = match SvgListFilter::from_query(&query) {
+        Ok(filter) => filter,
+        Err(error) => return HttpResponse::BadRequest().body(error),
+    };
 
     let conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => return HttpResponse::InternalServerError().body(format!("Error de conexión: {}", e)),
     };
 
-    let mut service = SvgService::new(conn);
-    match service.get_paginated_svgs(page, per_page) {
+    let mut service = SvgService::new(conn, storage.get_ref().clone()).with_access(claims.map_access());
+    match service.get_paginated_svgs(page, per_page, &filter) {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(error) => HttpResponse::InternalServerError().body(error),
     }
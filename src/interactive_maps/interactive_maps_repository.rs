@@ -1,45 +1,845 @@
+use std::sync::Arc;
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use diesel::mysql::MysqlConnection;
+use diesel::dsl::{exists, not, sql};
+use diesel::sql_types::{BigInt, Bool, Unsigned};
+use thiserror::Error;
+use diesel::mysql::{Mysql, MysqlConnection};
+use crate::developments::entities::development_entity::is_valid_prefix;
 use crate::interactive_maps::entities::maps_entity::{SvgItem, NewSvgItem};
-use crate::db::schema::maps_svg;
+use crate::interactive_maps::entities::map_lot_entity::{MapLot, NewMapLot};
+use crate::interactive_maps::entities::map_version_entity::{NewSvgVersion, SvgVersion};
+use crate::interactive_maps::entities::map_thumbnail_entity::NewMapThumbnail;
+use crate::interactive_maps::entities::map_layer_entity::{MapLayer, MapLayerChanges, NewMapLayerVersion};
+use crate::interactive_maps::entities::map_annotation_entity::{MapAnnotation, MapAnnotationChanges, NewMapAnnotation};
+use crate::db::schema::{developments, map_annotations, map_layer_versions, map_layers, map_lots, map_svg_originals, map_thumbnails, maps_svg, maps_svg_versions};
+use chrono::NaiveDateTime;
+use crate::auth::entities::auth_entities::MapAccess;
 use crate::common::types::PaginatedResponse;
+use crate::db::escape_like;
+use crate::interactive_maps::dto::svg_dto::{SortOrder, SvgListFilter, SvgSortField};
+use crate::interactive_maps::storage::map_storage::{new_storage_key, MapStorage, StorageError};
 
 pub type PooledConn = r2d2::PooledConnection<ConnectionManager<MysqlConnection>>;
 
+#[derive(Debug, Error)]
+pub enum SvgRepositoryError {
+    #[error(transparent)]
+    Database(#[from] diesel::result::Error),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+impl SvgRepositoryError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, SvgRepositoryError::Database(diesel::result::Error::NotFound))
+    }
+}
+
+/// Map metadata, lots and thumbnails live in MySQL; the SVG content of each revision goes
+/// through `storage`, which may keep it inline in the `content` column or elsewhere.
 pub struct SvgRepository {
     conn: PooledConn,
+    storage: Arc<dyn MapStorage>,
 }
 
 impl SvgRepository {
-    pub fn new(conn: PooledConn) -> Self {
-        Self { conn }
+    pub fn new(conn: PooledConn, storage: Arc<dyn MapStorage>) -> Self {
+        Self { conn, storage }
     }
 
-    pub fn create_svg(&mut self, new_svg: &NewSvgItem) -> Result<String, diesel::result::Error> {
-        diesel::insert_into(maps_svg::table)
-            .values(new_svg)
-            .execute(&mut self.conn)?;
+    /// Inserts a map with its lots and thumbnails. `original` is the uploaded content when
+    /// the optimizer changed it, kept for download.
+    pub fn create_svg(
+        &mut self,
+        new_svg: &NewSvgItem,
+        original: Option<&str>,
+        lots: &[NewMapLot],
+        thumbnails: &[NewMapThumbnail],
+    ) -> Result<String, SvgRepositoryError> {
+        let (content, storage_key) = self.storage
+            .store(&new_storage_key(&new_svg.name), &new_svg.content)?
+            .into_columns();
+        let original = match self.store_original(&new_svg.name, original) {
+            Ok(original) => original,
+            Err(e) => {
+                self.discard_stored(storage_key.as_deref());
+                return Err(e.into());
+            }
+        };
+
+        let result = self.conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let development_id = development_for_prefix(conn, &new_svg.prefix)?;
+
+            diesel::insert_into(maps_svg::table)
+                .values((
+                    maps_svg::id.eq(&new_svg.id),
+                    maps_svg::name.eq(&new_svg.name),
+                    maps_svg::prefix.eq(&new_svg.prefix),
+                    maps_svg::content.eq(&content),
+                    maps_svg::storage_key.eq(&storage_key),
+                    maps_svg::development_id.eq(development_id),
+                    maps_svg::content_hash.eq(&new_svg.content_hash),
+                ))
+                .execute(conn)?;
+
+            if let Some((original_content, original_storage_key)) = &original {
+                diesel::insert_into(map_svg_originals::table)
+                    .values((
+                        map_svg_originals::map_id.eq(&new_svg.id),
+                        map_svg_originals::version.eq(1),
+                        map_svg_originals::content.eq(original_content),
+                        map_svg_originals::storage_key.eq(original_storage_key),
+                    ))
+                    .execute(conn)?;
+            }
+
+            if !lots.is_empty() {
+                diesel::insert_into(map_lots::table)
+                    .values(lots)
+                    .execute(conn)?;
+            }
+
+            if !thumbnails.is_empty() {
+                diesel::insert_into(map_thumbnails::table)
+                    .values(thumbnails)
+                    .execute(conn)?;
+            }
+
+            Ok(new_svg.id.clone())
+        });
+
+        if result.is_err() {
+            self.discard_stored(storage_key.as_deref());
+            self.discard_stored(original.and_then(|(_, key)| key).as_deref());
+        }
+        Ok(result?)
+    }
+
+    /// Loads a map with its content, fetched from the storage backend when it isn't inline.
+    pub fn get_svg_by_id(&mut self, svg_id: &str) -> Result<SvgItem, SvgRepositoryError> {
+        let mut svg_item: SvgItem = maps_svg::table
+            .filter(maps_svg::id.eq(svg_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .first(&mut self.conn)?;
 
-        Ok(new_svg.id.clone())
+        if let Some(key) = &svg_item.storage_key {
+            svg_item.content = self.storage.load(key)?;
+        }
+        Ok(svg_item)
     }
 
-    pub fn get_svg_by_id(&mut self, svg_id: &str) -> Result<SvgItem, diesel::result::Error> {
+    pub fn get_svg_updated_at(&mut self, svg_id: &str) -> Result<NaiveDateTime, diesel::result::Error> {
         maps_svg::table
             .filter(maps_svg::id.eq(svg_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .select(maps_svg::updated_at)
             .first(&mut self.conn)
     }
 
-    pub fn get_paginated_svgs(&mut self, page_num: i64, items_per_page: i64) -> Result<PaginatedResponse<SvgItem>, diesel::result::Error> {
-        let total_items = maps_svg::table
+    pub fn get_lots_by_map_id(&mut self, svg_id: &str) -> Result<Vec<MapLot>, diesel::result::Error> {
+        map_lots::table
+            .filter(map_lots::map_id.eq(svg_id))
+            .order(map_lots::id.asc())
+            .load::<MapLot>(&mut self.conn)
+    }
+
+    pub fn get_paginated_svgs(
+        &mut self,
+        page_num: i64,
+        items_per_page: i64,
+        filter: &SvgListFilter,
+        access: &MapAccess,
+    ) -> Result<PaginatedResponse<SvgItem>, diesel::result::Error> {
+        let total_items = filtered_svgs(filter, access)
             .count()
             .get_result::<i64>(&mut self.conn)?;
             
         let total_pages = (total_items + items_per_page - 1) / items_per_page;
         let offset = (page_num - 1) * items_per_page;
-        
-        let items = maps_svg::table
+
+        let query = filtered_svgs(filter, access);
+        let query = match (filter.sort_by, filter.order) {
+            (SvgSortField::Name, SortOrder::Asc) => query.order(maps_svg::name.asc()),
+            (SvgSortField::Name, SortOrder::Desc) => query.order(maps_svg::name.desc()),
+            (SvgSortField::CreatedAt, SortOrder::Asc) => query.order((maps_svg::created_at.asc(), maps_svg::name.asc())),
+            (SvgSortField::CreatedAt, SortOrder::Desc) => query.order((maps_svg::created_at.desc(), maps_svg::name.asc())),
+            (SvgSortField::UpdatedAt, SortOrder::Asc) => query.order((maps_svg::updated_at.asc(), maps_svg::name.asc())),
+            (SvgSortField::UpdatedAt, SortOrder::Desc) => query.order((maps_svg::updated_at.desc(), maps_svg::name.asc())),
+        };
+
+        let items = query
+            .offset(offset)
+            .limit(items_per_page)
+            .load::<SvgItem>(&mut self.conn)?;
+
+        Ok(PaginatedResponse {
+            items,
+            total_items,
+            per_page: items_per_page,
+            current_page: page_num,
+            total_pages,
+        })
+    }
+
+    /// Maps whose current content has the given hash, oldest first. `excluding` leaves a map
+    /// out, to look for duplicates of a map that is already saved.
+    pub fn get_svgs_by_hash(
+        &mut self,
+        content_hash: &str,
+        excluding: Option<&str>,
+        access: &MapAccess,
+    ) -> Result<Vec<SvgItem>, diesel::result::Error> {
+        let query = maps_svg::table
+            .filter(maps_svg::content_hash.eq(content_hash))
+            .filter(maps_svg::deleted_at.is_null())
+            .into_boxed();
+        let mut query = within_access(query, access);
+        if let Some(svg_id) = excluding {
+            query = query.filter(maps_svg::id.ne(svg_id.to_string()));
+        }
+
+        query
+            .order((maps_svg::created_at.asc(), maps_svg::name.asc()))
+            .load::<SvgItem>(&mut self.conn)
+    }
+
+    pub fn get_development_prefix(&mut self, development_id: i32) -> Result<Option<String>, diesel::result::Error> {
+        developments::table
+            .filter(developments::id.eq(development_id))
+            .select(developments::prefix)
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    /// Prefix and development of a map, also when it is in the trash.
+    pub fn get_svg_scope(&mut self, svg_id: &str) -> Result<Option<(String, Option<i32>)>, diesel::result::Error> {
+        maps_svg::table
+            .filter(maps_svg::id.eq(svg_id))
+            .select((maps_svg::prefix, maps_svg::development_id))
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    pub fn get_development_id_by_prefix(&mut self, prefix: &str) -> Result<Option<i32>, diesel::result::Error> {
+        developments::table
+            .filter(developments::prefix.eq(prefix))
+            .select(developments::id)
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    /// Ids of the maps saved before content hashes existed.
+    pub fn get_unhashed_svg_ids(&mut self) -> Result<Vec<String>, diesel::result::Error> {
+        maps_svg::table
+            .filter(maps_svg::content_hash.is_null())
+            .filter(maps_svg::deleted_at.is_null())
+            .select(maps_svg::id)
+            .load::<String>(&mut self.conn)
+    }
+
+    /// Sets the hash of a map without touching `updated_at`, so its ETag stays valid.
+    pub fn set_content_hash(&mut self, svg_id: &str, content_hash: &str) -> Result<(), diesel::result::Error> {
+        diesel::update(maps_svg::table.filter(maps_svg::id.eq(svg_id)))
+            .set((
+                maps_svg::content_hash.eq(content_hash),
+                maps_svg::updated_at.eq(maps_svg::updated_at),
+            ))
+            .execute(&mut self.conn)?;
+        Ok(())
+    }
+
+    /// Maps without indexed lots: saved before lots were indexed, or without lots at all.
+    pub fn get_unindexed_svg_ids(&mut self) -> Result<Vec<String>, diesel::result::Error> {
+        maps_svg::table
+            .filter(maps_svg::deleted_at.is_null())
+            .filter(not(exists(map_lots::table.filter(map_lots::map_id.eq(maps_svg::id)))))
+            .select(maps_svg::id)
+            .load::<String>(&mut self.conn)
+    }
+
+    /// Maps with lots whose geometry hasn't been measured: indexed before it was measured in user
+    /// units, or whose lots aren't shapes.
+    pub fn get_unmeasured_svg_ids(&mut self) -> Result<Vec<String>, diesel::result::Error> {
+        maps_svg::table
+            .filter(maps_svg::deleted_at.is_null())
+            .filter(exists(
+                map_lots::table
+                    .filter(map_lots::map_id.eq(maps_svg::id))
+                    .filter(map_lots::bbox_x.is_null()),
+            ))
+            .select(maps_svg::id)
+            .load::<String>(&mut self.conn)
+    }
+
+    /// Sets the geometry of the lots of a map by element id, keeping their ids. Returns how many
+    /// lots were measured.
+    pub fn update_lot_geometry(&mut self, svg_id: &str, lots: &[NewMapLot]) -> Result<usize, diesel::result::Error> {
+        self.conn.transaction(|conn| {
+            let mut measured = 0;
+            for lot in lots.iter().filter(|lot| lot.bbox_x.is_some()) {
+                measured += diesel::update(
+                    map_lots::table
+                        .filter(map_lots::map_id.eq(svg_id))
+                        .filter(map_lots::element_id.eq(&lot.element_id))
+                        .filter(map_lots::bbox_x.is_null()),
+                )
+                .set((
+                    map_lots::bbox_x.eq(lot.bbox_x),
+                    map_lots::bbox_y.eq(lot.bbox_y),
+                    map_lots::bbox_width.eq(lot.bbox_width),
+                    map_lots::bbox_height.eq(lot.bbox_height),
+                    map_lots::centroid_x.eq(lot.centroid_x),
+                    map_lots::centroid_y.eq(lot.centroid_y),
+                    map_lots::area.eq(lot.area),
+                ))
+                .execute(conn)?;
+            }
+            Ok(measured)
+        })
+    }
+
+    /// Replaces the lots of a map without creating a version.
+    pub fn replace_lots(&mut self, svg_id: &str, lots: &[NewMapLot]) -> Result<(), diesel::result::Error> {
+        self.conn.transaction(|conn| {
+            diesel::delete(map_lots::table.filter(map_lots::map_id.eq(svg_id))).execute(conn)?;
+            if !lots.is_empty() {
+                diesel::insert_into(map_lots::table).values(lots).execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Ids of the maps that match a listing filter, in name order.
+    pub fn get_svg_ids(&mut self, filter: &SvgListFilter, access: &MapAccess) -> Result<Vec<String>, diesel::result::Error> {
+        filtered_svgs(filter, access)
+            .select(maps_svg::id)
             .order(maps_svg::name.asc())
+            .load(&mut self.conn)
+    }
+
+    /// Archives the current revision of the map in `maps_svg_versions`, replaces it with
+    /// `revision` (matched by id) and re-indexes its lots and thumbnails. `original` is kept as
+    /// the uploaded content of the new version. Returns the new version number, or `None` when
+    /// `expected_updated_at` is given and the map was modified in the meantime.
+    pub fn replace_svg_content(
+        &mut self,
+        revision: &NewSvgItem,
+        original: Option<&str>,
+        lots: &[NewMapLot],
+        thumbnails: &[NewMapThumbnail],
+        expected_updated_at: Option<NaiveDateTime>,
+    ) -> Result<Option<i32>, SvgRepositoryError> {
+        let (content, storage_key) = self.storage
+            .store(&new_storage_key(&revision.name), &revision.content)?
+            .into_columns();
+        let original = match self.store_original(&revision.name, original) {
+            Ok(original) => original,
+            Err(e) => {
+                self.discard_stored(storage_key.as_deref());
+                return Err(e.into());
+            }
+        };
+
+        let result = self.conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let current: SvgItem = match maps_svg::table
+                .filter(maps_svg::id.eq(&revision.id))
+                .filter(maps_svg::deleted_at.is_null())
+                .for_update()
+                .first(conn)
+                .optional()?
+            {
+                Some(current) => current,
+                None => return Ok(None),
+            };
+
+            if expected_updated_at.is_some_and(|expected| expected != current.updated_at) {
+                return Ok(None);
+            }
+
+            diesel::insert_into(maps_svg_versions::table)
+                .values(&NewSvgVersion {
+                    map_id: current.id.clone(),
+                    version: current.version,
+                    name: current.name,
+                    prefix: current.prefix,
+                    content: current.content,
+                    storage_key: current.storage_key,
+                })
+                .execute(conn)?;
+
+            let new_version = current.version + 1;
+            let development_id = development_for_prefix(conn, &revision.prefix)?;
+            diesel::update(maps_svg::table.filter(maps_svg::id.eq(&revision.id)))
+                .set((
+                    maps_svg::name.eq(&revision.name),
+                    maps_svg::prefix.eq(&revision.prefix),
+                    maps_svg::development_id.eq(development_id),
+                    maps_svg::content.eq(&content),
+                    maps_svg::storage_key.eq(&storage_key),
+                    maps_svg::content_hash.eq(&revision.content_hash),
+                    maps_svg::version.eq(new_version),
+                ))
+                .execute(conn)?;
+
+            if let Some((original_content, original_storage_key)) = &original {
+                diesel::insert_into(map_svg_originals::table)
+                    .values((
+                        map_svg_originals::map_id.eq(&revision.id),
+                        map_svg_originals::version.eq(new_version),
+                        map_svg_originals::content.eq(original_content),
+                        map_svg_originals::storage_key.eq(original_storage_key),
+                    ))
+                    .execute(conn)?;
+            }
+
+            diesel::delete(map_lots::table.filter(map_lots::map_id.eq(&revision.id)))
+                .execute(conn)?;
+
+            if !lots.is_empty() {
+                diesel::insert_into(map_lots::table)
+                    .values(lots)
+                    .execute(conn)?;
+            }
+
+            diesel::delete(map_thumbnails::table.filter(map_thumbnails::map_id.eq(&revision.id)))
+                .execute(conn)?;
+
+            if !thumbnails.is_empty() {
+                diesel::insert_into(map_thumbnails::table)
+                    .values(thumbnails)
+                    .execute(conn)?;
+            }
+
+            Ok(Some(new_version))
+        });
+
+        if !matches!(result, Ok(Some(_))) {
+            self.discard_stored(storage_key.as_deref());
+            self.discard_stored(original.and_then(|(_, key)| key).as_deref());
+        }
+        Ok(result?)
+    }
+
+    /// Changes the scale calibration without touching `updated_at`, so the ETag of the content
+    /// stays valid. Returns `false` when the map does not exist or is in the trash.
+    pub fn set_units_per_meter(&mut self, svg_id: &str, units_per_meter: Option<f64>) -> Result<bool, diesel::result::Error> {
+        // Diesel connects with CLIENT_FOUND_ROWS, so a map whose scale doesn't change still counts.
+        let updated = diesel::update(
+            maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::deleted_at.is_null()),
+        )
+        .set((
+            maps_svg::units_per_meter.eq(units_per_meter),
+            maps_svg::updated_at.eq(maps_svg::updated_at),
+        ))
+        .execute(&mut self.conn)?;
+
+        Ok(updated > 0)
+    }
+
+    /// Renames a map without creating a new version. Returns `false` when the map does not
+    /// exist or was modified after `expected_updated_at`.
+    pub fn rename_svg(
+        &mut self,
+        svg_id: &str,
+        name: &str,
+        prefix: &str,
+        expected_updated_at: Option<NaiveDateTime>,
+    ) -> Result<bool, diesel::result::Error> {
+        let development_id = development_for_prefix(&mut self.conn, prefix)?;
+
+        let mut query = diesel::update(maps_svg::table)
+            .filter(maps_svg::id.eq(svg_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .into_boxed();
+
+        if let Some(expected) = expected_updated_at {
+            query = query.filter(maps_svg::updated_at.eq(expected));
+        }
+
+        let updated = query
+            .set((
+                maps_svg::name.eq(name),
+                maps_svg::prefix.eq(prefix),
+                maps_svg::development_id.eq(development_id),
+            ))
+            .execute(&mut self.conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn get_thumbnail(&mut self, svg_id: &str, size: i32) -> Result<Option<Vec<u8>>, diesel::result::Error> {
+        map_thumbnails::table
+            .inner_join(maps_svg::table)
+            .filter(map_thumbnails::map_id.eq(svg_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .filter(map_thumbnails::size.eq(size))
+            .select(map_thumbnails::png)
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    pub fn replace_thumbnails(&mut self, svg_id: &str, thumbnails: &[NewMapThumbnail]) -> Result<(), diesel::result::Error> {
+        self.conn.transaction(|conn| {
+            diesel::delete(map_thumbnails::table.filter(map_thumbnails::map_id.eq(svg_id)))
+                .execute(conn)?;
+
+            diesel::insert_into(map_thumbnails::table)
+                .values(thumbnails)
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn get_svg_versions(&mut self, svg_id: &str) -> Result<Vec<(i32, String, String, NaiveDateTime)>, diesel::result::Error> {
+        maps_svg_versions::table
+            .filter(maps_svg_versions::map_id.eq(svg_id))
+            .select((
+                maps_svg_versions::version,
+                maps_svg_versions::name,
+                maps_svg_versions::prefix,
+                maps_svg_versions::created_at,
+            ))
+            .order(maps_svg_versions::version.desc())
+            .load(&mut self.conn)
+    }
+
+    pub fn get_svg_version(&mut self, svg_id: &str, version: i32) -> Result<SvgVersion, SvgRepositoryError> {
+        let mut svg_version: SvgVersion = maps_svg_versions::table
+            .filter(maps_svg_versions::map_id.eq(svg_id))
+            .filter(maps_svg_versions::version.eq(version))
+            .first(&mut self.conn)?;
+
+        if let Some(key) = &svg_version.storage_key {
+            svg_version.content = self.storage.load(key)?;
+        }
+        Ok(svg_version)
+    }
+
+    /// Uploaded content of a version, when the optimizer changed it.
+    pub fn get_svg_original(&mut self, svg_id: &str, version: i32) -> Result<Option<String>, SvgRepositoryError> {
+        let original: Option<(String, Option<String>)> = map_svg_originals::table
+            .filter(map_svg_originals::map_id.eq(svg_id))
+            .filter(map_svg_originals::version.eq(version))
+            .select((map_svg_originals::content, map_svg_originals::storage_key))
+            .first(&mut self.conn)
+            .optional()?;
+
+        match original {
+            Some((_, Some(key))) => Ok(Some(self.storage.load(&key)?)),
+            Some((content, None)) => Ok(Some(content)),
+            None => Ok(None),
+        }
+    }
+
+    /// Overlay layers of a map, bottom first.
+    pub fn get_layers(&mut self, svg_id: &str) -> Result<Vec<MapLayer>, diesel::result::Error> {
+        map_layers::table
+            .inner_join(maps_svg::table)
+            .filter(map_layers::map_id.eq(svg_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .select(MapLayer::as_select())
+            .order((map_layers::position.asc(), map_layers::name.asc()))
+            .load(&mut self.conn)
+    }
+
+    pub fn get_layer(&mut self, svg_id: &str, name: &str) -> Result<Option<MapLayer>, diesel::result::Error> {
+        map_layers::table
+            .inner_join(maps_svg::table)
+            .filter(map_layers::map_id.eq(svg_id))
+            .filter(map_layers::name.eq(name))
+            .filter(maps_svg::deleted_at.is_null())
+            .select(MapLayer::as_select())
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    /// Content of the current revision of a layer.
+    pub fn get_layer_content(&mut self, layer_id: i32) -> Result<String, SvgRepositoryError> {
+        let (content, storage_key): (String, Option<String>) = map_layers::table
+            .filter(map_layers::id.eq(layer_id))
+            .select((map_layers::content, map_layers::storage_key))
+            .first(&mut self.conn)?;
+
+        match storage_key {
+            Some(key) => Ok(self.storage.load(&key)?),
+            None => Ok(content),
+        }
+    }
+
+    /// Creates the layer `name` of a map, or archives its current revision in
+    /// `map_layer_versions` and replaces it. New layers go above the existing ones unless a
+    /// position is given. Returns `None` when the map does not exist.
+    pub fn save_layer(
+        &mut self,
+        svg_id: &str,
+        name: &str,
+        content: &str,
+        changes: &MapLayerChanges,
+    ) -> Result<Option<MapLayer>, SvgRepositoryError> {
+        let (content, storage_key) = self.storage
+            .store(&new_storage_key(&format!("{}-{}", svg_id, name)), content)?
+            .into_columns();
+
+        let result = self.conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            // Locking the map serializes the creation of its layers.
+            let map_exists = maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::deleted_at.is_null())
+                .select(maps_svg::id)
+                .for_update()
+                .first::<String>(conn)
+                .optional()?
+                .is_some();
+            if !map_exists {
+                return Ok(None);
+            }
+
+            let current: Option<(i32, i32, String, Option<String>)> = map_layers::table
+                .filter(map_layers::map_id.eq(svg_id))
+                .filter(map_layers::name.eq(name))
+                .select((map_layers::id, map_layers::version, map_layers::content, map_layers::storage_key))
+                .for_update()
+                .first(conn)
+                .optional()?;
+
+            match current {
+                Some((layer_id, version, current_content, current_storage_key)) => {
+                    diesel::insert_into(map_layer_versions::table)
+                        .values(&NewMapLayerVersion {
+                            layer_id,
+                            version,
+                            content: current_content,
+                            storage_key: current_storage_key,
+                        })
+                        .execute(conn)?;
+
+                    diesel::update(map_layers::table.filter(map_layers::id.eq(layer_id)))
+                        .set((
+                            map_layers::content.eq(&content),
+                            map_layers::storage_key.eq(&storage_key),
+                            map_layers::version.eq(version + 1),
+                            changes,
+                        ))
+                        .execute(conn)?;
+                }
+                None => {
+                    let position = match changes.position {
+                        Some(position) => position,
+                        None => map_layers::table
+                            .filter(map_layers::map_id.eq(svg_id))
+                            .select(diesel::dsl::max(map_layers::position))
+                            .first::<Option<i32>>(conn)?
+                            .unwrap_or(0)
+                            .max(0) + 1,
+                    };
+
+                    diesel::insert_into(map_layers::table)
+                        .values((
+                            map_layers::map_id.eq(svg_id),
+                            map_layers::name.eq(name),
+                            map_layers::position.eq(position),
+                            map_layers::visible_by_default.eq(changes.visible_by_default.unwrap_or(true)),
+                            map_layers::content.eq(&content),
+                            map_layers::storage_key.eq(&storage_key),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+
+            map_layers::table
+                .filter(map_layers::map_id.eq(svg_id))
+                .filter(map_layers::name.eq(name))
+                .select(MapLayer::as_select())
+                .first(conn)
+                .map(Some)
+        });
+
+        if !matches!(result, Ok(Some(_))) {
+            self.discard_stored(storage_key.as_deref());
+        }
+        Ok(result?)
+    }
+
+    /// Changes the position and/or default visibility of a layer without creating a version.
+    pub fn update_layer_settings(&mut self, layer_id: i32, changes: &MapLayerChanges) -> Result<MapLayer, diesel::result::Error> {
+        diesel::update(map_layers::table.filter(map_layers::id.eq(layer_id)))
+            .set(changes)
+            .execute(&mut self.conn)?;
+
+        map_layers::table
+            .filter(map_layers::id.eq(layer_id))
+            .select(MapLayer::as_select())
+            .first(&mut self.conn)
+    }
+
+    pub fn get_layer_versions(&mut self, layer_id: i32) -> Result<Vec<(i32, NaiveDateTime)>, diesel::result::Error> {
+        map_layer_versions::table
+            .filter(map_layer_versions::layer_id.eq(layer_id))
+            .select((map_layer_versions::version, map_layer_versions::created_at))
+            .order(map_layer_versions::version.desc())
+            .load(&mut self.conn)
+    }
+
+    pub fn get_layer_version(&mut self, layer_id: i32, version: i32) -> Result<(String, NaiveDateTime), SvgRepositoryError> {
+        let (content, storage_key, created_at): (String, Option<String>, NaiveDateTime) = map_layer_versions::table
+            .filter(map_layer_versions::layer_id.eq(layer_id))
+            .filter(map_layer_versions::version.eq(version))
+            .select((map_layer_versions::content, map_layer_versions::storage_key, map_layer_versions::created_at))
+            .first(&mut self.conn)?;
+
+        match storage_key {
+            Some(key) => Ok((self.storage.load(&key)?, created_at)),
+            None => Ok((content, created_at)),
+        }
+    }
+
+    /// Deletes a layer with its revisions, then removes their content from the storage backend.
+    pub fn delete_layer(&mut self, layer_id: i32) -> Result<(), SvgRepositoryError> {
+        let mut storage_keys: Vec<String> = map_layer_versions::table
+            .filter(map_layer_versions::layer_id.eq(layer_id))
+            .filter(map_layer_versions::storage_key.is_not_null())
+            .select(map_layer_versions::storage_key.assume_not_null())
+            .load(&mut self.conn)?;
+        storage_keys.extend(
+            map_layers::table
+                .filter(map_layers::id.eq(layer_id))
+                .filter(map_layers::storage_key.is_not_null())
+                .select(map_layers::storage_key.assume_not_null())
+                .load::<String>(&mut self.conn)?,
+        );
+
+        diesel::delete(map_layers::table.filter(map_layers::id.eq(layer_id)))
+            .execute(&mut self.conn)?;
+
+        for key in &storage_keys {
+            self.discard_stored(Some(key));
+        }
+
+        Ok(())
+    }
+
+    /// Annotations of a map in creation order, optionally only those with a visibility.
+    pub fn get_annotations(&mut self, svg_id: &str, visibility: Option<&str>) -> Result<Vec<MapAnnotation>, diesel::result::Error> {
+        let mut query = map_annotations::table
+            .filter(map_annotations::map_id.eq(svg_id))
+            .into_boxed();
+        if let Some(visibility) = visibility {
+            query = query.filter(map_annotations::visibility.eq(visibility.to_string()));
+        }
+
+        query
+            .select(MapAnnotation::as_select())
+            .order(map_annotations::id.asc())
+            .load(&mut self.conn)
+    }
+
+    pub fn get_annotation(&mut self, svg_id: &str, annotation_id: i32) -> Result<Option<MapAnnotation>, diesel::result::Error> {
+        map_annotations::table
+            .filter(map_annotations::id.eq(annotation_id))
+            .filter(map_annotations::map_id.eq(svg_id))
+            .select(MapAnnotation::as_select())
+            .first(&mut self.conn)
+            .optional()
+    }
+
+    pub fn create_annotation(&mut self, annotation: &NewMapAnnotation) -> Result<MapAnnotation, diesel::result::Error> {
+        self.conn.transaction(|conn| {
+            diesel::insert_into(map_annotations::table)
+                .values(annotation)
+                .execute(conn)?;
+
+            let annotation_id: u64 = diesel::select(sql::<Unsigned<BigInt>>("LAST_INSERT_ID()")).get_result(conn)?;
+            map_annotations::table
+                .filter(map_annotations::id.eq(annotation_id as i32))
+                .select(MapAnnotation::as_select())
+                .first(conn)
+        })
+    }
+
+    /// Returns `None` when the map has no annotation with that id.
+    pub fn update_annotation(
+        &mut self,
+        svg_id: &str,
+        annotation_id: i32,
+        changes: &MapAnnotationChanges,
+    ) -> Result<Option<MapAnnotation>, diesel::result::Error> {
+        diesel::update(
+            map_annotations::table
+                .filter(map_annotations::id.eq(annotation_id))
+                .filter(map_annotations::map_id.eq(svg_id)),
+        )
+        .set(changes)
+        .execute(&mut self.conn)?;
+
+        self.get_annotation(svg_id, annotation_id)
+    }
+
+    pub fn delete_annotation(&mut self, svg_id: &str, annotation_id: i32) -> Result<bool, diesel::result::Error> {
+        let deleted = diesel::delete(
+            map_annotations::table
+                .filter(map_annotations::id.eq(annotation_id))
+                .filter(map_annotations::map_id.eq(svg_id)),
+        )
+        .execute(&mut self.conn)?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Moves a map to the trash. Its content, revisions and layers are kept until it is
+    /// purged. Returns `false` when the map does not exist or is already in the trash.
+    pub fn trash_svg(&mut self, svg_id: &str) -> Result<bool, diesel::result::Error> {
+        let trashed = diesel::update(
+            maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::deleted_at.is_null()),
+        )
+        .set((
+            maps_svg::deleted_at.eq(diesel::dsl::now.nullable()),
+            maps_svg::updated_at.eq(maps_svg::updated_at),
+        ))
+        .execute(&mut self.conn)?;
+
+        Ok(trashed > 0)
+    }
+
+    /// Takes a map out of the trash. Returns `false` when it is not in the trash.
+    pub fn restore_svg(&mut self, svg_id: &str) -> Result<bool, diesel::result::Error> {
+        let restored = diesel::update(
+            maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::deleted_at.is_not_null()),
+        )
+        .set((
+            maps_svg::deleted_at.eq(None::<NaiveDateTime>),
+            maps_svg::updated_at.eq(maps_svg::updated_at),
+        ))
+        .execute(&mut self.conn)?;
+
+        Ok(restored > 0)
+    }
+
+    /// Maps in the trash, most recently deleted first, without their content.
+    pub fn get_trashed_svgs(
+        &mut self,
+        page_num: i64,
+        items_per_page: i64,
+        access: &MapAccess,
+    ) -> Result<PaginatedResponse<SvgItem>, diesel::result::Error> {
+        let trashed = || within_access(maps_svg::table.filter(maps_svg::deleted_at.is_not_null()).into_boxed(), access);
+
+        let total_items = trashed()
+            .count()
+            .get_result::<i64>(&mut self.conn)?;
+
+        let total_pages = (total_items + items_per_page - 1) / items_per_page;
+        let offset = (page_num - 1) * items_per_page;
+
+        let items = trashed()
+            .order((maps_svg::deleted_at.desc(), maps_svg::name.asc()))
             .offset(offset)
             .limit(items_per_page)
             .load::<SvgItem>(&mut self.conn)?;
@@ -53,11 +853,161 @@ impl SvgRepository {
         })
     }
 
-    pub fn delete_svg(&mut self, svg_id: &str) -> Result<bool, diesel::result::Error> {
+    /// Ids of the maps that have been in the trash for more than `retention_days`. The cutoff
+    /// is computed by MySQL, in the same time zone `deleted_at` was written in.
+    pub fn get_expired_trash(&mut self, retention_days: i64) -> Result<Vec<String>, diesel::result::Error> {
+        maps_svg::table
+            .filter(maps_svg::deleted_at.is_not_null())
+            .filter(
+                sql::<Bool>("deleted_at < NOW() - INTERVAL ")
+                    .bind::<BigInt, _>(retention_days)
+                    .sql(" DAY"),
+            )
+            .select(maps_svg::id)
+            .load(&mut self.conn)
+    }
+
+    /// Permanently deletes a map in the trash with its revisions and layers, then removes
+    /// their content from the storage backend. Returns `false` when the map is not in the trash.
+    pub fn purge_svg(&mut self, svg_id: &str) -> Result<bool, SvgRepositoryError> {
+        let mut storage_keys: Vec<String> = maps_svg_versions::table
+            .filter(maps_svg_versions::map_id.eq(svg_id))
+            .filter(maps_svg_versions::storage_key.is_not_null())
+            .select(maps_svg_versions::storage_key.assume_not_null())
+            .load(&mut self.conn)?;
+        storage_keys.extend(
+            maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::storage_key.is_not_null())
+                .select(maps_svg::storage_key.assume_not_null())
+                .load::<String>(&mut self.conn)?,
+        );
+        storage_keys.extend(
+            map_svg_originals::table
+                .filter(map_svg_originals::map_id.eq(svg_id))
+                .filter(map_svg_originals::storage_key.is_not_null())
+                .select(map_svg_originals::storage_key.assume_not_null())
+                .load::<String>(&mut self.conn)?,
+        );
+        storage_keys.extend(
+            map_layers::table
+                .filter(map_layers::map_id.eq(svg_id))
+                .filter(map_layers::storage_key.is_not_null())
+                .select(map_layers::storage_key.assume_not_null())
+                .load::<String>(&mut self.conn)?,
+        );
+        storage_keys.extend(
+            map_layer_versions::table
+                .inner_join(map_layers::table)
+                .filter(map_layers::map_id.eq(svg_id))
+                .filter(map_layer_versions::storage_key.is_not_null())
+                .select(map_layer_versions::storage_key.assume_not_null())
+                .load::<String>(&mut self.conn)?,
+        );
+
         let deleted_count = diesel::delete(
-            maps_svg::table.filter(maps_svg::id.eq(svg_id))
+            maps_svg::table
+                .filter(maps_svg::id.eq(svg_id))
+                .filter(maps_svg::deleted_at.is_not_null())
         ).execute(&mut self.conn)?;
-        
-        Ok(deleted_count > 0)
+
+        if deleted_count == 0 {
+            return Ok(false);
+        }
+
+        for key in &storage_keys {
+            self.discard_stored(Some(key));
+        }
+
+        Ok(true)
+    }
+
+    fn store_original(&self, name: &str, original: Option<&str>) -> Result<Option<(String, Option<String>)>, StorageError> {
+        original
+            .map(|original| {
+                self.storage
+                    .store(&new_storage_key(&format!("{}-original", name)), original)
+                    .map(|stored| stored.into_columns())
+            })
+            .transpose()
+    }
+
+    /// Best-effort removal of content that no row references anymore.
+    fn discard_stored(&self, storage_key: Option<&str>) {
+        if let Some(key) = storage_key {
+            if let Err(e) = self.storage.remove(key) {
+                eprintln!("No se pudo eliminar {} del almacenamiento: {}", key, e);
+            }
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Development of the maps with `prefix`, registered with the prefix as display name the first
+/// time a map uses it. Maps without a valid prefix (a name without `-` gives the whole name)
+/// don't belong to any development.
+fn development_for_prefix(conn: &mut MysqlConnection, prefix: &str) -> QueryResult<Option<i32>> {
+    if !is_valid_prefix(prefix) {
+        return Ok(None);
+    }
+
+    let find = |conn: &mut MysqlConnection| {
+        developments::table
+            .filter(developments::prefix.eq(prefix))
+            .select(developments::id)
+            .first::<i32>(conn)
+            .optional()
+    };
+
+    if let Some(id) = find(conn)? {
+        return Ok(Some(id));
+    }
+
+    // A concurrent save may register the same prefix first; the row is then just read back.
+    diesel::insert_or_ignore_into(developments::table)
+        .values((developments::prefix.eq(prefix), developments::display_name.eq(prefix)))
+        .execute(conn)?;
+    find(conn)
+}
+
+fn filtered_svgs(filter: &SvgListFilter, access: &MapAccess) -> maps_svg::BoxedQuery<'static, Mysql> {
+    let query = maps_svg::table
+        .filter(maps_svg::deleted_at.is_null())
+        .into_boxed();
+    let mut query = within_access(query, access);
+
+    if let Some(prefix) = &filter.prefix {
+        query = query.filter(maps_svg::prefix.eq(prefix.clone()));
+    }
+    if let Some(development_id) = filter.development_id {
+        query = query.filter(maps_svg::development_id.eq(development_id));
+    }
+    if let Some(search) = &filter.search {
+        query = query.filter(maps_svg::name.like(format!("%{}%", escape_like(search))));
+    }
+    if let Some(from) = filter.created_from {
+        query = query.filter(maps_svg::created_at.ge(from));
+    }
+    if let Some(to) = filter.created_to {
+        query = query.filter(maps_svg::created_at.le(to));
+    }
+    if let Some(from) = filter.updated_from {
+        query = query.filter(maps_svg::updated_at.ge(from));
+    }
+    if let Some(to) = filter.updated_to {
+        query = query.filter(maps_svg::updated_at.le(to));
+    }
+
+    query
+}
+
+/// Restricts a map query to the maps in `access`.
+fn within_access<'a>(query: maps_svg::BoxedQuery<'a, Mysql>, access: &MapAccess) -> maps_svg::BoxedQuery<'a, Mysql> {
+    match access {
+        MapAccess::All => query,
+        MapAccess::Scoped { development_ids, prefixes } => query.filter(
+            maps_svg::prefix
+                .eq_any(prefixes.clone())
+                .or(maps_svg::development_id.assume_not_null().eq_any(development_ids.clone())),
+        ),
+    }
+}
@@ -1,10 +1,38 @@
+use std::collections::HashSet;
+use std::io::{Seek, Write};
+use std::sync::Arc;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 use futures::TryStreamExt;
 use actix_web::web;
+use crate::auth::entities::auth_entities::MapAccess;
 use crate::common::types::PaginatedResponse;
-use super::interactive_maps_repository::SvgRepository;
-use crate::interactive_maps::entities::maps_entity::NewSvgItem;
-use crate::interactive_maps::dto::svg_dto::{SvgRequest, SvgInfo};
+use super::interactive_maps_repository::{SvgRepository, SvgRepositoryError};
+use crate::interactive_maps::entities::maps_entity::{NewSvgItem, SvgItem};
+use crate::interactive_maps::entities::map_lot_entity::{MapLot, NewMapLot};
+use crate::interactive_maps::entities::map_thumbnail_entity::NewMapThumbnail;
+use crate::interactive_maps::dto::svg_dto::{
+    SvgRequest, SvgInfo, MapLotInfo, SvgSaveResponse, SvgVersionInfo, SvgVersionsResponse, SvgVersionContent,
+    SvgRollbackResponse, SvgUpdateRequest, SvgUpdateResponse, SvgListFilter, MapLayerRequest, MapLayerInfo,
+    SvgDuplicateResponse, SvgsByHashResponse, TrashedSvgInfo, MapImportReport, MapImportResult, MapImportStatus,
+    MapManifestEntry, AnnotationVisibility, CreateMapAnnotationRequest, MapAnnotationInfo, UpdateMapAnnotationRequest,
+    MapLayersResponse, MapLayerSaveResponse, MapLayerVersionInfo, MapLayerVersionsResponse, MapLayerVersionContent,
+    CreateShareLinkRequest, ShareLinkResponse, shared_map_url, MapDiffQuery, MapDiffResponse, MapDiffSide,
+};
+use crate::interactive_maps::entities::map_layer_entity::{MapLayer, MapLayerChanges};
+use crate::interactive_maps::entities::map_annotation_entity::{MapAnnotationChanges, NewMapAnnotation};
+use crate::common::errors::ApiError;
+use chrono::{Duration, NaiveDateTime};
+use crate::interactive_maps::map_archive::{ArchiveFile, MapArchiveReader, MapArchiveWriter, MAX_ARCHIVE_BYTES};
+use crate::interactive_maps::map_share::{mint_share_token, DEFAULT_SHARE_HOURS, MAX_SHARE_HOURS};
+use crate::interactive_maps::storage::map_storage::MapStorage;
+use crate::interactive_maps::svg::svg_diff::{diff_lots, render_lot_diff, DEFAULT_DIFF_TOLERANCE};
+use crate::interactive_maps::svg::svg_geometry::measure_lots;
+use crate::interactive_maps::svg::svg_layers::{compose_layers, LayerSource, BASE_LAYER};
+use crate::interactive_maps::svg::svg_lots::extract_lots;
+use crate::interactive_maps::svg::svg_optimizer::optimize_svg;
+use crate::interactive_maps::svg::svg_sanitizer::sanitize_svg;
+use crate::interactive_maps::svg::svg_thumbnails::render_thumbnails;
 use diesel::mysql::MysqlConnection;
 use diesel::r2d2::{self, ConnectionManager};
 
@@ -12,32 +40,141 @@ pub type PooledConn = r2d2::PooledConnection<ConnectionManager<MysqlConnection>>
 
 pub struct SvgService {
     repository: SvgRepository,
+    access: MapAccess,
+}
+
+/// A side of a map comparison with its content.
+struct DiffSource {
+    side: MapDiffSide,
+    content: String,
+}
+
+pub enum SvgSaveOutcome {
+    Saved(SvgSaveResponse),
+    /// Not saved: another map has the same content.
+    Duplicate(SvgDuplicateResponse),
 }
 
 impl SvgService {
-    pub fn new(conn: PooledConn) -> Self {
+    pub fn new(conn: PooledConn, storage: Arc<dyn MapStorage>) -> Self {
         Self {
-            repository: SvgRepository::new(conn),
+            repository: SvgRepository::new(conn, storage),
+            access: MapAccess::All,
+        }
+    }
+
+    /// Limits listings and new or renamed maps to the maps in `access`. Uploads are checked for
+    /// duplicates against every map, but only maps in `access` are named. Requests for a single
+    /// map are checked with `ensure_map_access`.
+    pub fn with_access(mut self, access: MapAccess) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Fails with not found when the map doesn't exist or is outside the caller's scopes, so
+    /// the maps of other developments can't be told apart from missing ones.
+    pub fn ensure_map_access(&mut self, svg_id: &str) -> Result<(), ApiError> {
+        if self.access == MapAccess::All {
+            return Ok(());
+        }
+
+        let scope = self.repository
+            .get_svg_scope(svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener SVG: {}", e)))?;
+        match scope {
+            Some((prefix, development_id)) if self.access.allows(&prefix, development_id) => Ok(()),
+            _ => Err(ApiError::NotFound("SVG no encontrado".to_string())),
         }
     }
 
-    pub fn save_svg(&mut self, data: SvgRequest) -> Result<String, String> {
-        let svg_name = data.name.clone();
-        let prefix = svg_name.split('-')
-            .next()
-            .unwrap_or("")
-            .to_string();
+    /// Fails with forbidden when maps with `prefix` are outside the caller's scopes.
+    pub fn ensure_prefix_access(&mut self, prefix: &str) -> Result<(), ApiError> {
+        if self.access == MapAccess::All {
+            return Ok(());
+        }
+
+        let development_id = self.repository
+            .get_development_id_by_prefix(prefix)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener el desarrollo: {}", e)))?;
+        if !self.access.allows(prefix, development_id) {
+            return Err(ApiError::Forbidden(format!("Sin acceso a los mapas con prefijo {}", prefix)));
+        }
+        Ok(())
+    }
+
+    pub fn save_svg(&mut self, data: SvgRequest) -> Result<SvgSaveOutcome, String> {
+        self.insert_svg(data.name, None, data.content, data.allow_duplicate)
+    }
+
+    /// Saves a new map. `prefix` overrides the one taken from the name. An upload with the same
+    /// content as an existing map is rejected unless `allow_duplicate` is set, in which case it
+    /// is saved and flagged with `duplicate_of`. Maps outside the caller's scopes also count as
+    /// duplicates, without their id or name.
+    fn insert_svg(
+        &mut self,
+        svg_name: String,
+        prefix: Option<String>,
+        content: String,
+        allow_duplicate: bool,
+    ) -> Result<SvgSaveOutcome, String> {
+        let (content, sanitization) = sanitize_svg(&content)?;
+        if !sanitization.is_clean() {
+            eprintln!(
+                "Se eliminaron {} elementos y {} atributos no permitidos del SVG {}",
+                sanitization.removed_elements.values().sum::<usize>(),
+                sanitization.removed_attributes.values().sum::<usize>(),
+                svg_name
+            );
+        }
+        let (optimized, optimization) = optimize_svg(&content)?;
+        let original = (optimized != content).then_some(content);
+        let hash = content_hash(&optimized);
+
+        let duplicates = self.repository
+            .get_svgs_by_hash(&hash, None, &MapAccess::All)
+            .map_err(|e| format!("Error al buscar mapas duplicados: {}", e))?;
+        let duplicate = duplicates
+            .iter()
+            .find(|existing| self.access.allows(&existing.prefix, existing.development_id));
+        if !duplicates.is_empty() && !allow_duplicate {
+            return Ok(SvgSaveOutcome::Duplicate(match duplicate {
+                Some(existing) => SvgDuplicateResponse {
+                    error: format!("El contenido es igual al del mapa {}", existing.name),
+                    existing_id: Some(existing.id.clone()),
+                    existing: Some(SvgInfo::from_item(existing)),
+                },
+                None => SvgDuplicateResponse {
+                    error: "El contenido es igual al de un mapa de otro desarrollo".to_string(),
+                    existing_id: None,
+                    existing: None,
+                },
+            }));
+        }
+
+        let prefix = prefix.unwrap_or_else(|| prefix_from_name(&svg_name));
 
         let new_svg = NewSvgItem {
             id: Uuid::new_v4().to_string(),
             name: svg_name,
             prefix,
-            content: data.content,
+            content: optimized,
+            content_hash: hash,
         };
 
-        self.repository
-            .create_svg(&new_svg)
-            .map_err(|e| format!("Error al guardar SVG: {}", e))
+        let lots = index_lots(&new_svg.id, &new_svg.content)?;
+        let thumbnails = thumbnails_or_empty(&new_svg.id, &new_svg.content);
+
+        let id = self.repository
+            .create_svg(&new_svg, original.as_deref(), &lots, &thumbnails)
+            .map_err(|e| format!("Error al guardar SVG: {}", e))?;
+
+        Ok(SvgSaveOutcome::Saved(SvgSaveResponse {
+            id,
+            content_hash: new_svg.content_hash,
+            sanitization,
+            optimization,
+            duplicate_of: duplicate.map(|existing| existing.id.clone()),
+        }))
     }
 
     pub fn get_svg_by_id(&mut self, svg_id: String) -> Result<(SvgInfo, String), String> {
@@ -45,31 +182,30 @@ impl SvgService {
             .get_svg_by_id(&svg_id)
             .map_err(|e| format!("Error al obtener SVG: {}", e))?;
 
-        let svg_info = SvgInfo {
-            prefix: svg_item.prefix,
-            name: svg_item.name,
-            id: svg_item.id,
-        };
+        Ok((SvgInfo::from_item(&svg_item), svg_item.content))
+    }
 
-        Ok((svg_info, svg_item.content))
+    /// Last modification of a map, read without loading its content so conditional
+    /// requests can be answered cheaply.
+    pub fn get_svg_updated_at(&mut self, svg_id: String) -> Result<NaiveDateTime, String> {
+        self.repository
+            .get_svg_updated_at(&svg_id)
+            .map_err(|e| format!("Error al obtener SVG: {}", e))
     }
 
     pub fn get_paginated_svgs(
         &mut self,
         page: i64,
         per_page: i64,
+        filter: &SvgListFilter,
     ) -> Result<PaginatedResponse<SvgInfo>, String> {
         let response = self.repository
-            .get_paginated_svgs(page, per_page)
+            .get_paginated_svgs(page, per_page, filter, &self.access)
             .map_err(|e| format!("Error al obtener SVGs paginados: {}", e))?;
 
         let items: Vec<SvgInfo> = response.items
             .into_iter()
-            .map(|item| SvgInfo {
-                prefix: item.prefix,
-                name: item.name,
-                id: item.id,
-            })
+            .map(|item| SvgInfo::from_item(&item))
             .collect();
 
         Ok(PaginatedResponse {
@@ -81,44 +217,1172 @@ impl SvgService {
         })
     }
 
-    pub async fn save_svg_streaming(
+    /// Saves every SVG of a ZIP through the same path as `save_svg`, with the names, prefixes
+    /// and developments given in its `manifest.json`. A file that fails doesn't stop the rest.
+    /// Decompresses and saves synchronously, so callers run it on a blocking thread.
+    pub fn import_maps(&mut self, archive: &[u8], allow_duplicate: bool) -> Result<MapImportReport, ApiError> {
+        // Each file is imported as it is decompressed, so only one is held in memory.
+        let mut archive = MapArchiveReader::new(archive).map_err(ApiError::BadRequest)?;
+        let entries = archive.manifest.take().map(|manifest| manifest.maps).unwrap_or_default();
+
+        let mut results = Vec::new();
+        let mut imported_paths = HashSet::new();
+        while let Some(file) = archive.next_file().map_err(ApiError::BadRequest)? {
+            let entry = entries.iter().find(|entry| entry.file == file.path);
+            imported_paths.insert(file.path.clone());
+            results.push(self.import_file(file, entry, allow_duplicate));
+        }
+
+        results.extend(
+            entries
+                .iter()
+                .filter(|entry| !imported_paths.contains(&entry.file))
+                .map(|entry| import_result(&entry.file, MapImportStatus::Failed, None, None, Some("No está en el ZIP".to_string()))),
+        );
+
+        let count = |status| results.iter().filter(|result| result.status == status).count();
+        Ok(MapImportReport {
+            created: count(MapImportStatus::Created),
+            duplicates: count(MapImportStatus::Duplicate),
+            failed: count(MapImportStatus::Failed),
+            results,
+        })
+    }
+
+    /// Writes the maps matching `filter` into a ZIP with a manifest the import accepts.
+    /// Returns the writer and the number of maps exported.
+    pub fn export_maps<W: Write + Seek>(&mut self, filter: &SvgListFilter, writer: W) -> Result<(W, usize), ApiError> {
+        let svg_ids = self.repository
+            .get_svg_ids(filter, &self.access)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener SVGs: {}", e)))?;
+
+        let mut archive = MapArchiveWriter::new(writer);
+        let mut exported = 0;
+        for svg_id in svg_ids {
+            let svg_item = match self.repository.get_svg_by_id(&svg_id) {
+                Ok(svg_item) => svg_item,
+                // Moved to the trash while exporting.
+                Err(e) if e.is_not_found() => continue,
+                Err(e) => return Err(ApiError::InternalError(format!("Error al obtener SVG {}: {}", svg_id, e))),
+            };
+
+            let entry = MapManifestEntry {
+                file: String::new(),
+                id: Some(svg_item.id),
+                name: Some(svg_item.name),
+                prefix: Some(svg_item.prefix),
+                development_id: None,
+                version: Some(svg_item.version),
+                created_at: Some(svg_item.created_at),
+                updated_at: Some(svg_item.updated_at),
+                content_hash: svg_item.content_hash,
+            };
+            archive
+                .add_map(entry, &svg_item.content)
+                .map_err(ApiError::InternalError)?;
+            exported += 1;
+        }
+
+        let writer = archive.finish().map_err(ApiError::InternalError)?;
+        Ok((writer, exported))
+    }
+
+    fn import_file(&mut self, file: ArchiveFile, entry: Option<&MapManifestEntry>, allow_duplicate: bool) -> MapImportResult {
+        let failed = |error: String| import_result(&file.path, MapImportStatus::Failed, None, None, Some(error));
+
+        let content = match file.content {
+            Ok(content) => content,
+            Err(error) => return failed(error),
+        };
+
+        let name = entry
+            .and_then(|entry| entry.name.clone())
+            .unwrap_or_else(|| file.path.rsplit('/').next().unwrap_or(&file.path).to_string());
+
+        let prefix = match entry.and_then(|entry| entry.development_id) {
+            Some(development_id) => match self.repository.get_development_prefix(development_id) {
+                Ok(Some(prefix)) => Some(prefix),
+                Ok(None) => return failed(format!("El desarrollo {} no existe", development_id)),
+                Err(e) => return failed(format!("Error al obtener el desarrollo: {}", e)),
+            },
+            None => entry.and_then(|entry| entry.prefix.clone()),
+        };
+
+        let prefix = prefix.unwrap_or_else(|| prefix_from_name(&name));
+        if let Err(error) = self.ensure_prefix_access(&prefix) {
+            return failed(error.to_string());
+        }
+
+        match self.insert_svg(name, Some(prefix), content, allow_duplicate) {
+            Ok(SvgSaveOutcome::Saved(response)) => {
+                import_result(&file.path, MapImportStatus::Created, Some(response.id), response.duplicate_of, None)
+            }
+            Ok(SvgSaveOutcome::Duplicate(duplicate)) => import_result(
+                &file.path,
+                MapImportStatus::Duplicate,
+                None,
+                duplicate.existing_id,
+                Some(duplicate.error),
+            ),
+            Err(error) => failed(error),
+        }
+    }
+
+    /// Maps whose current content has the given SHA-256, oldest first.
+    pub fn get_svgs_by_hash(&mut self, hash: &str) -> Result<SvgsByHashResponse, ApiError> {
+        let hash = hash.trim().to_ascii_lowercase();
+        if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(ApiError::BadRequest("El hash debe ser un SHA-256 en hexadecimal".to_string()));
+        }
+
+        let maps: Vec<SvgInfo> = self.repository
+            .get_svgs_by_hash(&hash, None, &self.access)
+            .map_err(|e| ApiError::InternalError(format!("Error al buscar mapas: {}", e)))?
+            .iter()
+            .map(SvgInfo::from_item)
+            .collect();
+
+        if maps.is_empty() {
+            return Err(ApiError::NotFound("Ningún mapa tiene ese contenido".to_string()));
+        }
+
+        Ok(SvgsByHashResponse { content_hash: hash, maps })
+    }
+
+    /// Hashes the maps saved before content hashes existed, normalizing their content the way
+    /// it is on save. Maps that fail are logged and left for the next start.
+    pub fn backfill_content_hashes(&mut self) -> Result<usize, String> {
+        let svg_ids = self.repository
+            .get_unhashed_svg_ids()
+            .map_err(|e| format!("Error al obtener mapas sin hash: {}", e))?;
+
+        let mut hashed = 0;
+        for svg_id in svg_ids {
+            let result = self.repository
+                .get_svg_by_id(&svg_id)
+                .map_err(|e| e.to_string())
+                .and_then(|svg_item| {
+                    let (sanitized, _) = sanitize_svg(&svg_item.content)?;
+                    let (optimized, _) = optimize_svg(&sanitized)?;
+                    self.repository
+                        .set_content_hash(&svg_id, &content_hash(&optimized))
+                        .map_err(|e| e.to_string())
+                });
+
+            match result {
+                Ok(()) => hashed += 1,
+                Err(error) => eprintln!("No se pudo calcular el hash del mapa {}: {}", svg_id, error),
+            }
+        }
+
+        Ok(hashed)
+    }
+
+    /// Indexes the lots of the maps saved before lots were indexed. Maps without lots are
+    /// parsed again on every start; maps that fail are logged and left for the next one.
+    pub fn backfill_lots(&mut self) -> Result<usize, String> {
+        let svg_ids = self.repository
+            .get_unindexed_svg_ids()
+            .map_err(|e| format!("Error al obtener mapas sin lotes: {}", e))?;
+
+        let mut indexed = 0;
+        for svg_id in svg_ids {
+            let result = self.repository
+                .get_svg_by_id(&svg_id)
+                .map_err(|e| e.to_string())
+                .and_then(|svg_item| index_lots(&svg_id, &svg_item.content))
+                .and_then(|lots| {
+                    if lots.is_empty() {
+                        return Ok(false);
+                    }
+                    self.repository
+                        .replace_lots(&svg_id, &lots)
+                        .map(|_| true)
+                        .map_err(|e| e.to_string())
+                });
+
+            match result {
+                Ok(true) => indexed += 1,
+                Ok(false) => {}
+                Err(error) => eprintln!("No se pudieron indexar los lotes del mapa {}: {}", svg_id, error),
+            }
+        }
+
+        Ok(indexed)
+    }
+
+    /// Measures the lots indexed without geometry, keeping their ids. Lots that aren't shapes
+    /// stay unmeasured, so their maps are checked again on every start.
+    pub fn backfill_lot_geometry(&mut self) -> Result<usize, String> {
+        let svg_ids = self.repository
+            .get_unmeasured_svg_ids()
+            .map_err(|e| format!("Error al obtener mapas sin geometría: {}", e))?;
+
+        let mut measured = 0;
+        for svg_id in svg_ids {
+            let result = self.repository
+                .get_svg_by_id(&svg_id)
+                .map_err(|e| e.to_string())
+                .and_then(|svg_item| index_lots(&svg_id, &svg_item.content))
+                .and_then(|lots| {
+                    self.repository
+                        .update_lot_geometry(&svg_id, &lots)
+                        .map_err(|e| e.to_string())
+                });
+
+            match result {
+                Ok(0) => {}
+                Ok(_) => measured += 1,
+                Err(error) => eprintln!("No se pudo medir la geometría de los lotes del mapa {}: {}", svg_id, error),
+            }
+        }
+
+        Ok(measured)
+    }
+
+    pub fn get_svg_lots(&mut self, svg_id: String) -> Result<Vec<MapLotInfo>, ApiError> {
+        let svg_item = self.find_svg(&svg_id)?;
+
+        let lots = self.repository
+            .get_lots_by_map_id(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener lotes: {}", e)))?;
+
+        Ok(lots
+            .into_iter()
+            .map(|lot| MapLotInfo::from_lot(lot, svg_item.units_per_meter))
+            .collect())
+    }
+
+    /// Sets how many SVG units make a meter, so lot areas can be reported in m².
+    pub fn set_svg_scale(&mut self, svg_id: String, units_per_meter: Option<f64>) -> Result<SvgInfo, ApiError> {
+        if units_per_meter.is_some_and(|value| !value.is_finite() || value <= 0.0) {
+            return Err(ApiError::BadRequest("units_per_meter must be a positive number".to_string()));
+        }
+
+        let updated = self.repository
+            .set_units_per_meter(&svg_id, units_per_meter)
+            .map_err(|e| ApiError::InternalError(format!("Error al actualizar escala: {}", e)))?;
+        if !updated {
+            return Err(ApiError::NotFound("SVG no encontrado".to_string()));
+        }
+
+        let svg_item = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener SVG: {}", e)))?;
+
+        Ok(SvgInfo::from_item(&svg_item))
+    }
+
+    pub fn get_svg_with_lots(&mut self, svg_id: String) -> Result<(SvgItem, Vec<MapLot>), String> {
+        let svg_item = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| format!("Error al obtener SVG: {}", e))?;
+
+        let lots = self.repository
+            .get_lots_by_map_id(&svg_id)
+            .map_err(|e| format!("Error al obtener lotes: {}", e))?;
+
+        Ok((svg_item, lots))
+    }
+
+    /// Renames a map and/or replaces its content. `if_match` is the value of the `If-Match`
+    /// header and must match the current ETag of the map, or be `*`.
+    pub fn update_svg(
         &mut self,
-        mut payload: web::Payload,
-        svg_name: String,
-    ) -> Result<String, String> {
-        let mut content = Vec::new();
-        
-        while let Some(chunk) = payload
-            .try_next()
-            .await
-            .map_err(|e| format!("Error al leer payload: {}", e))? 
+        svg_id: String,
+        if_match: Option<&str>,
+        data: SvgUpdateRequest,
+    ) -> Result<(SvgUpdateResponse, String), ApiError> {
+        if data.name.is_none() && data.content.is_none() {
+            return Err(ApiError::BadRequest("name or content is required".to_string()));
+        }
+
+        let if_match = if_match
+            .ok_or_else(|| ApiError::PreconditionRequired("If-Match header is required".to_string()))?;
+
+        let current = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| if e.is_not_found() {
+                ApiError::NotFound("SVG no encontrado".to_string())
+            } else {
+                ApiError::InternalError(format!("Error al obtener SVG: {}", e))
+            })?;
+
+        let expected_updated_at = if if_match.trim() == "*" {
+            None
+        } else if etag_matches_strong(if_match, &svg_etag(current.updated_at)) {
+            Some(current.updated_at)
+        } else {
+            return Err(ApiError::PreconditionFailed("El mapa fue modificado por otro usuario".to_string()));
+        };
+
+        let name = data.name.unwrap_or_else(|| current.name.clone());
+        let prefix = prefix_from_name(&name);
+        if prefix != current.prefix {
+            self.ensure_prefix_access(&prefix)?;
+        }
+
+        let (applied, sanitization, optimization, hash) = match data.content {
+            Some(content) => {
+                let (content, sanitization) = sanitize_svg(&content).map_err(ApiError::BadRequest)?;
+                let (optimized, optimization) = optimize_svg(&content).map_err(ApiError::BadRequest)?;
+                let original = (optimized != content).then_some(content);
+                let hash = content_hash(&optimized);
+                let lots = index_lots(&svg_id, &optimized).map_err(ApiError::BadRequest)?;
+                let thumbnails = thumbnails_or_empty(&svg_id, &optimized);
+                let revision = NewSvgItem {
+                    id: svg_id.clone(),
+                    name,
+                    prefix,
+                    content: optimized,
+                    content_hash: hash,
+                };
+                let applied = self.repository
+                    .replace_svg_content(&revision, original.as_deref(), &lots, &thumbnails, expected_updated_at)
+                    .map_err(|e| ApiError::InternalError(format!("Error al actualizar SVG: {}", e)))?
+                    .is_some();
+                (applied, Some(sanitization), Some(optimization), Some(revision.content_hash))
+            }
+            None => {
+                let applied = self.repository
+                    .rename_svg(&svg_id, &name, &prefix, expected_updated_at)
+                    .map_err(|e| ApiError::InternalError(format!("Error al actualizar SVG: {}", e)))?;
+                (applied, None, None, None)
+            }
+        };
+
+        if !applied {
+            return Err(ApiError::PreconditionFailed("El mapa fue modificado por otro usuario".to_string()));
+        }
+
+        let updated = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener SVG: {}", e)))?;
+
+        let duplicate_of = match hash {
+            Some(hash) => self.repository
+                .get_svgs_by_hash(&hash, Some(&svg_id), &self.access)
+                .map_err(|e| ApiError::InternalError(format!("Error al buscar mapas duplicados: {}", e)))?
+                .into_iter()
+                .next()
+                .map(|existing| existing.id),
+            None => None,
+        };
+
+        let etag = svg_etag(updated.updated_at);
+        Ok((
+            SvgUpdateResponse {
+                svg: SvgInfo::from_item(&updated),
+                sanitization,
+                optimization,
+                duplicate_of,
+            },
+            etag,
+        ))
+    }
+
+    /// PNG thumbnail of the map. Maps saved before thumbnails existed, or whose rasterization
+    /// failed on save, are rendered on first request.
+    pub fn get_thumbnail(&mut self, svg_id: String, size: i32) -> Result<Vec<u8>, String> {
+        if let Some(png) = self.repository
+            .get_thumbnail(&svg_id, size)
+            .map_err(|e| format!("Error al obtener miniatura: {}", e))?
         {
-            content.extend_from_slice(&chunk);
+            return Ok(png);
         }
 
-        let prefix = svg_name.split('-')
-            .next()
-            .unwrap_or("")
-            .to_string();
+        let svg_item = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| format!("Error al obtener SVG: {}", e))?;
 
-        let new_svg = NewSvgItem {
-            id: Uuid::new_v4().to_string(),
-            name: svg_name,
-            prefix,
-            content: String::from_utf8(content)
-                .map_err(|e| format!("Error al convertir contenido a UTF-8: {}", e))?,
+        let thumbnails = new_thumbnails(&svg_id, &svg_item.content)?;
+        self.repository
+            .replace_thumbnails(&svg_id, &thumbnails)
+            .map_err(|e| format!("Error al guardar miniaturas: {}", e))?;
+
+        thumbnails
+            .into_iter()
+            .find(|thumbnail| thumbnail.size == size)
+            .map(|thumbnail| thumbnail.png)
+            .ok_or_else(|| format!("Tamaño de miniatura no soportado: {}", size))
+    }
+
+    pub fn get_svg_versions(&mut self, svg_id: String) -> Result<SvgVersionsResponse, String> {
+        let svg_item = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| format!("Error al obtener SVG: {}", e))?;
+
+        let versions = self.repository
+            .get_svg_versions(&svg_id)
+            .map_err(|e| format!("Error al obtener versiones: {}", e))?
+            .into_iter()
+            .map(|(version, name, prefix, created_at)| SvgVersionInfo {
+                version,
+                name,
+                prefix,
+                created_at,
+            })
+            .collect();
+
+        Ok(SvgVersionsResponse {
+            id: svg_item.id,
+            current_version: svg_item.version,
+            versions,
+        })
+    }
+
+    pub fn get_svg_version(&mut self, svg_id: String, version: i32) -> Result<SvgVersionContent, String> {
+        let svg_item = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| format!("Error al obtener SVG: {}", e))?;
+
+        if svg_item.version == version {
+            return Ok(SvgVersionContent {
+                version,
+                name: svg_item.name,
+                prefix: svg_item.prefix,
+                content: svg_item.content,
+                created_at: svg_item.updated_at,
+            });
+        }
+
+        let svg_version = self.repository
+            .get_svg_version(&svg_id, version)
+            .map_err(|e| format!("Error al obtener la versión {}: {}", version, e))?;
+
+        Ok(SvgVersionContent {
+            version: svg_version.version,
+            name: svg_version.name,
+            prefix: svg_version.prefix,
+            content: svg_version.content,
+            created_at: svg_version.created_at,
+        })
+    }
+
+    /// Content of a version as it was uploaded, before optimization. Defaults to the current
+    /// version; versions the optimizer didn't change, or saved before it existed, return the
+    /// stored content. Returns the name of the map with it, for the download.
+    pub fn get_svg_original(&mut self, svg_id: String, version: Option<i32>) -> Result<(String, String), ApiError> {
+        let svg_item = self.find_svg(&svg_id)?;
+
+        let (version, name, content) = match version {
+            Some(version) if version != svg_item.version => {
+                let svg_version = self.repository
+                    .get_svg_version(&svg_id, version)
+                    .map_err(|e| if e.is_not_found() {
+                        ApiError::NotFound(format!("Versión {} no encontrada", version))
+                    } else {
+                        ApiError::InternalError(format!("Error al obtener la versión {}: {}", version, e))
+                    })?;
+                (svg_version.version, svg_version.name, svg_version.content)
+            }
+            _ => (svg_item.version, svg_item.name, svg_item.content),
+        };
+
+        let original = self.repository
+            .get_svg_original(&svg_id, version)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener el SVG original: {}", e)))?;
+
+        Ok((name, original.unwrap_or(content)))
+    }
+
+    /// Compares the lots of two versions of a map, or of two maps.
+    pub fn diff_svgs(&mut self, svg_id: String, query: &MapDiffQuery) -> Result<MapDiffResponse, ApiError> {
+        let (from, to, tolerance) = self.diff_sides(svg_id, query)?;
+        let diff = diff_lots(&from.content, &to.content, tolerance).map_err(ApiError::UnprocessableEntity)?;
+
+        Ok(MapDiffResponse::from_diff(from.side, to.side, tolerance, &diff))
+    }
+
+    /// The compared map with its lots colored by change and the removed lots outlined.
+    pub fn render_svg_diff(&mut self, svg_id: String, query: &MapDiffQuery) -> Result<String, ApiError> {
+        let (from, to, tolerance) = self.diff_sides(svg_id, query)?;
+        diff_lots(&from.content, &to.content, tolerance)
+            .and_then(|diff| render_lot_diff(&to.content, &diff))
+            .map_err(ApiError::UnprocessableEntity)
+    }
+
+    fn diff_sides(&mut self, svg_id: String, query: &MapDiffQuery) -> Result<(DiffSource, DiffSource, f64), ApiError> {
+        let tolerance = query.tolerance.unwrap_or(DEFAULT_DIFF_TOLERANCE);
+        if !tolerance.is_finite() || tolerance < 0.0 {
+            return Err(ApiError::BadRequest("tolerance must be a non-negative number".to_string()));
+        }
+
+        let to_id = query.to.clone().unwrap_or_else(|| svg_id.clone());
+        if to_id != svg_id {
+            self.ensure_map_access(&to_id)?;
+        }
+
+        let from = self.find_version(&svg_id, query.from_version)?;
+        let to = self.find_version(&to_id, query.to_version)?;
+        if from.side.id == to.side.id && from.side.version == to.side.version {
+            return Err(ApiError::BadRequest("Compare two different versions or maps".to_string()));
+        }
+
+        Ok((from, to, tolerance))
+    }
+
+    /// Content of a version of a map; `None` is the current version.
+    fn find_version(&mut self, svg_id: &str, version: Option<i32>) -> Result<DiffSource, ApiError> {
+        let svg_item = self.find_svg(svg_id)?;
+
+        let (version, content) = match version {
+            Some(version) if version != svg_item.version => {
+                let svg_version = self.repository
+                    .get_svg_version(svg_id, version)
+                    .map_err(|e| if e.is_not_found() {
+                        ApiError::NotFound(format!("Versión {} no encontrada", version))
+                    } else {
+                        ApiError::InternalError(format!("Error al obtener la versión {}: {}", version, e))
+                    })?;
+                (svg_version.version, svg_version.content)
+            }
+            _ => (svg_item.version, svg_item.content),
+        };
+
+        Ok(DiffSource {
+            side: MapDiffSide { id: svg_item.id, name: svg_item.name, version },
+            content,
+        })
+    }
+
+    /// Restores a previous revision as a new version, so the rollback itself can be undone.
+    /// The restored content goes through the same sanitizer and optimizer as an update, since
+    /// old versions may predate them, and `if_match` follows the same protocol.
+    pub fn rollback_svg(
+        &mut self,
+        svg_id: String,
+        version: i32,
+        if_match: Option<&str>,
+    ) -> Result<(SvgRollbackResponse, String), ApiError> {
+        let if_match = if_match
+            .ok_or_else(|| ApiError::PreconditionRequired("If-Match header is required".to_string()))?;
+
+        let current = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| if e.is_not_found() {
+                ApiError::NotFound("SVG no encontrado".to_string())
+            } else {
+                ApiError::InternalError(format!("Error al obtener SVG: {}", e))
+            })?;
+
+        let expected_updated_at = if if_match.trim() == "*" {
+            None
+        } else if etag_matches_strong(if_match, &svg_etag(current.updated_at)) {
+            Some(current.updated_at)
+        } else {
+            return Err(ApiError::PreconditionFailed("El mapa fue modificado por otro usuario".to_string()));
+        };
+
+        let version_error = |e: SvgRepositoryError| if e.is_not_found() {
+            ApiError::NotFound(format!("La versión {} no existe", version))
+        } else {
+            ApiError::InternalError(format!("Error al obtener la versión {}: {}", version, e))
         };
+        let svg_version = self.repository
+            .get_svg_version(&svg_id, version)
+            .map_err(version_error)?;
+        self.ensure_prefix_access(&svg_version.prefix)?;
+
+        let uploaded = self.repository
+            .get_svg_original(&svg_id, version)
+            .map_err(version_error)?
+            .unwrap_or(svg_version.content);
+        let (content, _) = sanitize_svg(&uploaded).map_err(ApiError::BadRequest)?;
+        let (optimized, _) = optimize_svg(&content).map_err(ApiError::BadRequest)?;
+        let original = (optimized != content).then_some(content);
+        let lots = index_lots(&svg_id, &optimized).map_err(ApiError::BadRequest)?;
+        let thumbnails = thumbnails_or_empty(&svg_id, &optimized);
+
+        let revision = NewSvgItem {
+            id: svg_id.clone(),
+            name: svg_version.name,
+            prefix: svg_version.prefix,
+            content_hash: content_hash(&optimized),
+            content: optimized,
+        };
+
+        let new_version = self.repository
+            .replace_svg_content(&revision, original.as_deref(), &lots, &thumbnails, expected_updated_at)
+            .map_err(|e| ApiError::InternalError(format!("Error al restaurar la versión {}: {}", version, e)))?
+            .ok_or_else(|| ApiError::PreconditionFailed("El mapa fue modificado por otro usuario".to_string()))?;
+
+        let updated = self.repository
+            .get_svg_by_id(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener SVG: {}", e)))?;
+
+        Ok((
+            SvgRollbackResponse {
+                id: svg_id,
+                version: new_version,
+                restored_from: version,
+            },
+            svg_etag(updated.updated_at),
+        ))
+    }
+
+    /// The lots layer followed by the overlay layers, bottom first.
+    pub fn get_layers(&mut self, svg_id: String) -> Result<MapLayersResponse, ApiError> {
+        let svg_item = self.find_svg(&svg_id)?;
+        let layers = self.repository
+            .get_layers(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener capas: {}", e)))?;
+
+        Ok(MapLayersResponse {
+            layers: layers_with_base(&svg_item, &layers),
+            id: svg_id,
+        })
+    }
+
+    /// Creates or replaces an overlay layer, or only moves/hides it when no content is sent.
+    pub fn save_layer(&mut self, svg_id: String, name: String, data: MapLayerRequest) -> Result<MapLayerSaveResponse, ApiError> {
+        let name = validate_layer_name(&name)?;
+        let changes = MapLayerChanges {
+            position: data.position,
+            visible_by_default: data.visible_by_default,
+        };
+        if changes.position == Some(0) {
+            return Err(ApiError::BadRequest(format!("position 0 is reserved for the {} layer", BASE_LAYER)));
+        }
+
+        let content = match data.content {
+            Some(content) => content,
+            None => {
+                if changes.position.is_none() && changes.visible_by_default.is_none() {
+                    return Err(ApiError::BadRequest("content, position or visible_by_default is required".to_string()));
+                }
+                let layer = self.find_layer(&svg_id, &name)?;
+                let layer = self.repository
+                    .update_layer_settings(layer.id, &changes)
+                    .map_err(|e| ApiError::InternalError(format!("Error al actualizar capa: {}", e)))?;
+                return Ok(MapLayerSaveResponse {
+                    layer: MapLayerInfo::from_layer(&layer),
+                    sanitization: None,
+                });
+            }
+        };
+
+        let (content, sanitization) = sanitize_svg(&content).map_err(ApiError::BadRequest)?;
+        // Layers are drawn as nested <svg> elements, so each one must compose on its own.
+        compose_layers(&content, &[LayerSource::Base]).map_err(ApiError::BadRequest)?;
+
+        let layer = self.repository
+            .save_layer(&svg_id, &name, &content, &changes)
+            .map_err(|e| ApiError::InternalError(format!("Error al guardar capa: {}", e)))?
+            .ok_or_else(|| ApiError::NotFound("SVG no encontrado".to_string()))?;
+
+        Ok(MapLayerSaveResponse {
+            layer: MapLayerInfo::from_layer(&layer),
+            sanitization: Some(sanitization),
+        })
+    }
+
+    /// SVG of a single layer; for the lots layer, the content of the map.
+    pub fn get_layer_content(&mut self, svg_id: String, name: String) -> Result<String, ApiError> {
+        if name == BASE_LAYER {
+            return Ok(self.find_svg(&svg_id)?.content);
+        }
+
+        let layer = self.find_layer(&svg_id, &name)?;
+        self.repository
+            .get_layer_content(layer.id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener capa: {}", e)))
+    }
+
+    pub fn get_layer_versions(&mut self, svg_id: String, name: String) -> Result<MapLayerVersionsResponse, ApiError> {
+        let layer = self.find_layer(&svg_id, &name)?;
+        let versions = self.repository
+            .get_layer_versions(layer.id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener versiones: {}", e)))?
+            .into_iter()
+            .map(|(version, created_at)| MapLayerVersionInfo { version, created_at })
+            .collect();
+
+        Ok(MapLayerVersionsResponse {
+            id: svg_id,
+            layer: layer.name,
+            current_version: layer.version,
+            versions,
+        })
+    }
+
+    pub fn get_layer_version(&mut self, svg_id: String, name: String, version: i32) -> Result<MapLayerVersionContent, ApiError> {
+        let layer = self.find_layer(&svg_id, &name)?;
+        if layer.version == version {
+            return Ok(MapLayerVersionContent {
+                version,
+                content: self.repository
+                    .get_layer_content(layer.id)
+                    .map_err(|e| ApiError::InternalError(format!("Error al obtener capa: {}", e)))?,
+                created_at: layer.updated_at,
+            });
+        }
+
+        let (content, created_at) = self.repository
+            .get_layer_version(layer.id, version)
+            .map_err(|e| if e.is_not_found() {
+                ApiError::NotFound(format!("La capa {} no tiene la versión {}", name, version))
+            } else {
+                ApiError::InternalError(format!("Error al obtener la versión {}: {}", version, e))
+            })?;
+
+        Ok(MapLayerVersionContent { version, content, created_at })
+    }
+
+    pub fn delete_layer(&mut self, svg_id: String, name: String) -> Result<(), ApiError> {
+        let layer = self.find_layer(&svg_id, &name)?;
+        self.repository
+            .delete_layer(layer.id)
+            .map_err(|e| ApiError::InternalError(format!("Error al eliminar capa: {}", e)))
+    }
+
+    /// Composes `base`, the content of the map (possibly already colored), with the selected
+    /// layers. Without a selection, the lots and the layers visible by default are used.
+    pub fn compose_map(&mut self, svg_id: &str, base: &str, selection: Option<&[String]>) -> Result<String, ApiError> {
+        let layers = self.repository
+            .get_layers(svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener capas: {}", e)))?;
+
+        if let Some(unknown) = selection
+            .into_iter()
+            .flatten()
+            .find(|name| name.as_str() != BASE_LAYER && !layers.iter().any(|layer| &layer.name == *name))
+        {
+            return Err(ApiError::BadRequest(format!("El mapa no tiene la capa {}", unknown)));
+        }
+
+        let selected = |name: &str, visible_by_default: bool| match selection {
+            Some(names) => names.iter().any(|selected| selected == name),
+            None => visible_by_default,
+        };
+        let include_base = selected(BASE_LAYER, true);
+
+        let mut contents = Vec::new();
+        for layer in layers.iter().filter(|layer| selected(&layer.name, layer.visible_by_default)) {
+            let content = self.repository
+                .get_layer_content(layer.id)
+                .map_err(|e| ApiError::InternalError(format!("Error al obtener capa: {}", e)))?;
+            contents.push((layer, content));
+        }
+
+        let mut stack = Vec::with_capacity(contents.len() + 1);
+        let mut base_added = !include_base;
+        for (layer, content) in &contents {
+            if !base_added && layer.position > 0 {
+                stack.push(LayerSource::Base);
+                base_added = true;
+            }
+            stack.push(LayerSource::Overlay { name: &layer.name, content });
+        }
+        if !base_added {
+            stack.push(LayerSource::Base);
+        }
+
+        compose_layers(base, &stack).map_err(ApiError::InternalError)
+    }
+
+    fn find_svg(&mut self, svg_id: &str) -> Result<SvgItem, ApiError> {
+        self.repository
+            .get_svg_by_id(svg_id)
+            .map_err(|e| if e.is_not_found() {
+                ApiError::NotFound("SVG no encontrado".to_string())
+            } else {
+                ApiError::InternalError(format!("Error al obtener SVG: {}", e))
+            })
+    }
+
+    fn find_layer(&mut self, svg_id: &str, name: &str) -> Result<MapLayer, ApiError> {
+        self.repository
+            .get_layer(svg_id, name)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener capa: {}", e)))?
+            .ok_or_else(|| ApiError::NotFound(format!("El mapa no tiene la capa {}", name)))
+    }
+
+    pub fn get_annotations(
+        &mut self,
+        svg_id: String,
+        visibility: Option<AnnotationVisibility>,
+    ) -> Result<Vec<MapAnnotationInfo>, ApiError> {
+        self.ensure_svg_exists(&svg_id)?;
+
+        let annotations = self.repository
+            .get_annotations(&svg_id, visibility.map(|visibility| visibility.as_str()))
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener anotaciones: {}", e)))?;
+
+        Ok(annotations.iter().map(MapAnnotationInfo::from_annotation).collect())
+    }
+
+    pub fn create_annotation(&mut self, svg_id: String, data: CreateMapAnnotationRequest) -> Result<MapAnnotationInfo, ApiError> {
+        validate_coordinates(Some(data.x), Some(data.y))?;
+        let label = validate_annotation_label(&data.label)?;
+        let icon = validate_annotation_icon(data.icon.as_deref())?;
+        self.ensure_svg_exists(&svg_id)?;
+
+        let annotation = self.repository
+            .create_annotation(&NewMapAnnotation {
+                map_id: svg_id,
+                annotation_type: data.annotation_type.as_str().to_string(),
+                x: data.x,
+                y: data.y,
+                label,
+                icon,
+                visibility: data.visibility.as_str().to_string(),
+            })
+            .map_err(|e| ApiError::InternalError(format!("Error al guardar anotación: {}", e)))?;
+
+        Ok(MapAnnotationInfo::from_annotation(&annotation))
+    }
+
+    pub fn update_annotation(
+        &mut self,
+        svg_id: String,
+        annotation_id: i32,
+        data: UpdateMapAnnotationRequest,
+    ) -> Result<MapAnnotationInfo, ApiError> {
+        validate_coordinates(data.x, data.y)?;
+        let changes = MapAnnotationChanges {
+            annotation_type: data.annotation_type.map(|annotation_type| annotation_type.as_str().to_string()),
+            x: data.x,
+            y: data.y,
+            label: data.label.as_deref().map(validate_annotation_label).transpose()?,
+            icon: data.icon.as_deref().map(|icon| validate_annotation_icon(Some(icon))).transpose()?,
+            visibility: data.visibility.map(|visibility| visibility.as_str().to_string()),
+        };
+        if changes.annotation_type.is_none()
+            && changes.x.is_none()
+            && changes.y.is_none()
+            && changes.label.is_none()
+            && changes.icon.is_none()
+            && changes.visibility.is_none()
+        {
+            return Err(ApiError::BadRequest("at least one field is required".to_string()));
+        }
+        self.ensure_svg_exists(&svg_id)?;
+
+        let annotation = self.repository
+            .update_annotation(&svg_id, annotation_id, &changes)
+            .map_err(|e| ApiError::InternalError(format!("Error al actualizar anotación: {}", e)))?
+            .ok_or_else(|| ApiError::NotFound("Anotación no encontrada".to_string()))?;
+
+        Ok(MapAnnotationInfo::from_annotation(&annotation))
+    }
+
+    pub fn delete_annotation(&mut self, svg_id: String, annotation_id: i32) -> Result<(), ApiError> {
+        self.ensure_svg_exists(&svg_id)?;
+
+        let deleted = self.repository
+            .delete_annotation(&svg_id, annotation_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al eliminar anotación: {}", e)))?;
+        if !deleted {
+            return Err(ApiError::NotFound("Anotación no encontrada".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Signs a link that opens the map without a session until it expires.
+    pub fn create_share_link(
+        &mut self,
+        svg_id: String,
+        secret: &str,
+        data: CreateShareLinkRequest,
+    ) -> Result<ShareLinkResponse, ApiError> {
+        let hours = data.expires_in_hours.unwrap_or(DEFAULT_SHARE_HOURS);
+        if !(1..=MAX_SHARE_HOURS).contains(&hours) {
+            return Err(ApiError::BadRequest(format!("expires_in_hours must be between 1 and {}", MAX_SHARE_HOURS)));
+        }
+        if data.visible_statuses.as_ref().is_some_and(|statuses| statuses.iter().any(|status| status.trim().is_empty())) {
+            return Err(ApiError::BadRequest("visible_statuses can't contain empty statuses".to_string()));
+        }
+        self.ensure_svg_exists(&svg_id)?;
 
+        let (token, expires_at) = mint_share_token(secret, &svg_id, hours, data.visible_statuses)?;
+        Ok(ShareLinkResponse { url: shared_map_url(&token), token, expires_at })
+    }
+
+    /// Checks that a map exists and is not in the trash, without loading its content.
+    fn ensure_svg_exists(&mut self, svg_id: &str) -> Result<(), ApiError> {
         self.repository
-            .create_svg(&new_svg)
-            .map_err(|e| format!("Error al guardar SVG: {}", e))
+            .get_svg_updated_at(svg_id)
+            .map(|_| ())
+            .map_err(|e| match e {
+                diesel::result::Error::NotFound => ApiError::NotFound("SVG no encontrado".to_string()),
+                e => ApiError::InternalError(format!("Error al obtener SVG: {}", e)),
+            })
     }
 
+    /// Moves a map to the trash; it can be restored until it is purged.
     pub fn delete_svg_by_id(&mut self, svg_id: String) -> Result<(), String> {
-        match self.repository.delete_svg(&svg_id) {
+        match self.repository.trash_svg(&svg_id) {
             Ok(true) => Ok(()),
             Ok(false) => Err("SVG no encontrado".to_string()),
             Err(e) => Err(format!("Error al eliminar SVG: {}", e)),
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_trash(&mut self, page: i64, per_page: i64, retention_days: i64) -> Result<PaginatedResponse<TrashedSvgInfo>, ApiError> {
+        let response = self.repository
+            .get_trashed_svgs(page, per_page, &self.access)
+            .map_err(|e| ApiError::InternalError(format!("Error al obtener la papelera: {}", e)))?;
+
+        let items = response.items
+            .iter()
+            .filter_map(|item| {
+                let deleted_at = item.deleted_at?;
+                Some(TrashedSvgInfo {
+                    svg: SvgInfo::from_item(item),
+                    deleted_at,
+                    purge_at: deleted_at + Duration::days(retention_days),
+                })
+            })
+            .collect();
+
+        Ok(PaginatedResponse {
+            items,
+            total_items: response.total_items,
+            per_page: response.per_page,
+            current_page: response.current_page,
+            total_pages: response.total_pages,
+        })
+    }
+
+    pub fn restore_svg(&mut self, svg_id: String) -> Result<SvgInfo, ApiError> {
+        let restored = self.repository
+            .restore_svg(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al restaurar SVG: {}", e)))?;
+        if !restored {
+            return Err(ApiError::NotFound("El SVG no está en la papelera".to_string()));
+        }
+
+        Ok(SvgInfo::from_item(&self.find_svg(&svg_id)?))
+    }
+
+    /// Permanently deletes a map in the trash.
+    pub fn purge_svg(&mut self, svg_id: String) -> Result<(), ApiError> {
+        let purged = self.repository
+            .purge_svg(&svg_id)
+            .map_err(|e| ApiError::InternalError(format!("Error al purgar SVG: {}", e)))?;
+        if !purged {
+            return Err(ApiError::NotFound("El SVG no está en la papelera".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Purges the maps that have been in the trash for more than `retention_days`. Returns how
+    /// many were purged; maps that fail are logged and retried on the next run.
+    pub fn purge_expired_trash(&mut self, retention_days: i64) -> Result<usize, String> {
+        let svg_ids = self.repository
+            .get_expired_trash(retention_days)
+            .map_err(|e| format!("Error al obtener la papelera: {}", e))?;
+
+        let mut purged = 0;
+        for svg_id in svg_ids {
+            match self.repository.purge_svg(&svg_id) {
+                Ok(true) => purged += 1,
+                Ok(false) => {}
+                Err(e) => eprintln!("No se pudo purgar el mapa {}: {}", svg_id, e),
+            }
+        }
+
+        Ok(purged)
+    }
+}
+
+/// Reads an uploaded SVG into memory.
+pub async fn read_svg_payload(mut payload: web::Payload) -> Result<String, String> {
+    let mut content = Vec::new();
+
+    while let Some(chunk) = payload
+        .try_next()
+        .await
+        .map_err(|e| format!("Error al leer payload: {}", e))?
+    {
+        content.extend_from_slice(&chunk);
+    }
+
+    String::from_utf8(content).map_err(|e| format!("Error al convertir contenido a UTF-8: {}", e))
+}
+
+/// Reads an uploaded archive into memory, up to [`MAX_ARCHIVE_BYTES`].
+pub async fn read_archive_payload(mut payload: web::Payload) -> Result<Vec<u8>, ApiError> {
+    let mut archive = Vec::new();
+
+    while let Some(chunk) = payload
+        .try_next()
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Error al leer payload: {}", e)))?
+    {
+        if archive.len() + chunk.len() > MAX_ARCHIVE_BYTES {
+            return Err(ApiError::BadRequest(format!(
+                "El archivo supera {} MB",
+                MAX_ARCHIVE_BYTES / (1024 * 1024)
+            )));
+        }
+        archive.extend_from_slice(&chunk);
+    }
+
+    Ok(archive)
+}
+
+fn import_result(
+    file: &str,
+    status: MapImportStatus,
+    id: Option<String>,
+    existing_id: Option<String>,
+    error: Option<String>,
+) -> MapImportResult {
+    MapImportResult {
+        file: file.to_string(),
+        status,
+        id,
+        existing_id,
+        error,
+    }
+}
+
+fn index_lots(svg_id: &str, content: &str) -> Result<Vec<NewMapLot>, String> {
+    let lots = extract_lots(content)?;
+    let element_ids: Vec<&str> = lots.iter().map(|lot| lot.element_id.as_str()).collect();
+    let geometries = measure_lots(content, &element_ids)?;
+
+    Ok(lots
+        .into_iter()
+        .map(|lot| {
+            let geometry = geometries.get(&lot.element_id);
+            NewMapLot {
+                map_id: svg_id.to_string(),
+                bbox_x: geometry.map(|g| g.bbox_x),
+                bbox_y: geometry.map(|g| g.bbox_y),
+                bbox_width: geometry.map(|g| g.bbox_width),
+                bbox_height: geometry.map(|g| g.bbox_height),
+                centroid_x: geometry.map(|g| g.centroid_x),
+                centroid_y: geometry.map(|g| g.centroid_y),
+                area: geometry.map(|g| g.area),
+                element_id: lot.element_id,
+                lot_number: lot.lot_number,
+            }
+        })
+        .collect())
+}
+
+/// The lots layer, built from the map itself, in its place among the overlay layers.
+fn layers_with_base(svg_item: &SvgItem, layers: &[MapLayer]) -> Vec<MapLayerInfo> {
+    let base = MapLayerInfo {
+        name: BASE_LAYER.to_string(),
+        position: 0,
+        visible_by_default: true,
+        version: svg_item.version,
+        updated_at: svg_item.updated_at,
+    };
+
+    let (below, above): (Vec<&MapLayer>, Vec<&MapLayer>) = layers.iter().partition(|layer| layer.position < 0);
+    below
+        .into_iter()
+        .map(MapLayerInfo::from_layer)
+        .chain(std::iter::once(base))
+        .chain(above.into_iter().map(MapLayerInfo::from_layer))
+        .collect()
+}
+
+/// Annotation positions must be finite; `None` is a coordinate an update leaves as is.
+fn validate_coordinates(x: Option<f64>, y: Option<f64>) -> Result<(), ApiError> {
+    if [x, y].into_iter().flatten().any(|value| !value.is_finite()) {
+        return Err(ApiError::BadRequest("x and y must be finite numbers".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_annotation_label(label: &str) -> Result<String, ApiError> {
+    let label = label.trim();
+    if label.is_empty() || label.chars().count() > 255 {
+        return Err(ApiError::BadRequest("label must have 1 to 255 characters".to_string()));
+    }
+    Ok(label.to_string())
+}
+
+/// An empty icon means no icon.
+fn validate_annotation_icon(icon: Option<&str>) -> Result<Option<String>, ApiError> {
+    let icon = icon.map(str::trim).filter(|icon| !icon.is_empty());
+    if icon.is_some_and(|icon| icon.chars().count() > 255) {
+        return Err(ApiError::BadRequest("icon must have at most 255 characters".to_string()));
+    }
+    Ok(icon.map(str::to_string))
+}
+
+/// Layer names are used in URLs and element ids: lowercase letters, digits, `-` and `_`.
+fn validate_layer_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+
+    if !valid {
+        return Err(ApiError::BadRequest(
+            "layer name must have 1 to 100 lowercase letters, digits, '-' or '_'".to_string(),
+        ));
+    }
+    if name == BASE_LAYER {
+        return Err(ApiError::BadRequest(format!(
+            "the {} layer is the content of the map; update the map instead",
+            BASE_LAYER
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn new_thumbnails(svg_id: &str, content: &str) -> Result<Vec<NewMapThumbnail>, String> {
+    Ok(render_thumbnails(content)?
+        .into_iter()
+        .map(|(size, png)| NewMapThumbnail {
+            map_id: svg_id.to_string(),
+            size,
+            png,
+        })
+        .collect())
+}
+
+/// A map that can't be rasterized is still saved; its thumbnails are retried on request.
+fn thumbnails_or_empty(svg_id: &str, content: &str) -> Vec<NewMapThumbnail> {
+    new_thumbnails(svg_id, content).unwrap_or_else(|e| {
+        eprintln!("No se generaron miniaturas para {}: {}", svg_id, e);
+        Vec::new()
+    })
+}
+
+/// Development prefix of a map, taken from its name up to the first `-` (`TC-LOT` gives `TC`).
+/// The map is linked to the development with that prefix when it is saved.
+pub fn prefix_from_name(name: &str) -> String {
+    name.split('-')
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+/// SHA-256 (hex) of the content of a map as it is stored, sanitized and optimized, so editor
+/// metadata, indentation and path precision don't tell two uploads of the same plan apart.
+pub fn content_hash(content: &str) -> String {
+    let normalized = content.replace("\r\n", "\n");
+    format!("{:x}", Sha256::digest(normalized.trim().as_bytes()))
+}
+
+/// Strong ETag of a map, derived from `updated_at`.
+pub fn svg_etag(updated_at: NaiveDateTime) -> String {
+    format!("\"{}\"", updated_at.and_utc().timestamp_micros())
+}
+
+/// Whether an `If-None-Match` header value lists `etag`. Weak validators are compared by
+/// their opaque value.
+pub fn etag_matches(header_value: &str, etag: &str) -> bool {
+    header_value
+        .split(',')
+        .map(|candidate| candidate.trim().trim_start_matches("W/"))
+        .any(|candidate| candidate == etag || candidate == "*")
+}
+
+/// Whether an `If-Match` header value lists `etag`. `If-Match` uses the strong comparison of
+/// RFC 9110, so weak validators never match.
+pub fn etag_matches_strong(header_value: &str, etag: &str) -> bool {
+    header_value
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == etag || candidate == "*")
+}
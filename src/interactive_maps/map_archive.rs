@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Seek, Write};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::interactive_maps::dto::svg_dto::{MapManifest, MapManifestEntry};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Largest archive accepted by the import.
+pub const MAX_ARCHIVE_BYTES: usize = 200 * 1024 * 1024;
+
+/// Largest uncompressed SVG read from an archive. Compressed sizes can't be trusted, so
+/// entries are read up to this limit and rejected past it.
+pub const MAX_ARCHIVE_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Most entries an archive may list, directories and skipped files included.
+pub const MAX_ARCHIVE_ENTRIES: usize = 2000;
+
+/// Most bytes decompressed from a single archive, manifest included. Past it the import
+/// stops, so a small archive can't expand without bound.
+pub const MAX_ARCHIVE_TOTAL_BYTES: u64 = 500 * 1024 * 1024;
+
+/// An SVG entry of an archive, or why it could not be read.
+pub struct ArchiveFile {
+    pub path: String,
+    pub content: Result<String, String>,
+}
+
+/// Reads the `.svg` entries of a ZIP one at a time, after its `manifest.json`, if any.
+/// Directories, hidden files and the `__MACOSX` folder are skipped.
+pub struct MapArchiveReader<'a> {
+    archive: ZipArchive<Cursor<&'a [u8]>>,
+    pub manifest: Option<MapManifest>,
+    next_index: usize,
+    remaining_bytes: u64,
+}
+
+impl<'a> MapArchiveReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| format!("El archivo no es un ZIP válido: {}", e))?;
+        if archive.len() > MAX_ARCHIVE_ENTRIES {
+            return Err(format!("El ZIP tiene más de {} archivos", MAX_ARCHIVE_ENTRIES));
+        }
+
+        let mut reader = Self {
+            archive,
+            manifest: None,
+            next_index: 0,
+            remaining_bytes: MAX_ARCHIVE_TOTAL_BYTES,
+        };
+
+        // The manifest is written last, but the entries need it as they are read.
+        if reader.archive.index_for_name(MANIFEST_FILE).is_some() {
+            let mut entry = reader
+                .archive
+                .by_name(MANIFEST_FILE)
+                .map_err(|e| format!("Error al leer el ZIP: {}", e))?;
+            let content = read_entry(&mut entry, &mut reader.remaining_bytes)??;
+            drop(entry);
+            reader.manifest = Some(
+                serde_json::from_str(&content).map_err(|e| format!("{} no es válido: {}", MANIFEST_FILE, e))?,
+            );
+        }
+
+        Ok(reader)
+    }
+
+    /// The next SVG entry, or `None` at the end of the archive. Fails when the archive is
+    /// corrupt or decompresses past [`MAX_ARCHIVE_TOTAL_BYTES`].
+    pub fn next_file(&mut self) -> Result<Option<ArchiveFile>, String> {
+        while self.next_index < self.archive.len() {
+            let mut entry = self
+                .archive
+                .by_index(self.next_index)
+                .map_err(|e| format!("Error al leer el ZIP: {}", e))?;
+            self.next_index += 1;
+            if entry.is_dir() {
+                continue;
+            }
+
+            let path = entry.name().to_string();
+            let file_name = path.rsplit('/').next().unwrap_or(&path);
+            if path.starts_with("__MACOSX/") || file_name.starts_with('.') || path == MANIFEST_FILE {
+                continue;
+            }
+
+            if file_name.to_ascii_lowercase().ends_with(".svg") {
+                let content = read_entry(&mut entry, &mut self.remaining_bytes)?;
+                return Ok(Some(ArchiveFile { path, content }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Decompresses an entry, counting it against `remaining_bytes`. The outer error stops the
+/// whole archive; the inner one only this entry.
+fn read_entry(entry: &mut impl Read, remaining_bytes: &mut u64) -> Result<Result<String, String>, String> {
+    let mut content = Vec::new();
+    entry
+        .take((MAX_ARCHIVE_FILE_BYTES + 1).min(*remaining_bytes + 1))
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Error al descomprimir: {}", e))?;
+
+    if content.len() as u64 > *remaining_bytes {
+        return Err(format!(
+            "El ZIP descomprimido supera {} MB",
+            MAX_ARCHIVE_TOTAL_BYTES / (1024 * 1024)
+        ));
+    }
+    *remaining_bytes -= content.len() as u64;
+
+    if content.len() as u64 > MAX_ARCHIVE_FILE_BYTES {
+        return Ok(Err(format!("El archivo supera {} MB", MAX_ARCHIVE_FILE_BYTES / (1024 * 1024))));
+    }
+
+    Ok(String::from_utf8(content).map_err(|e| format!("Error al convertir contenido a UTF-8: {}", e)))
+}
+
+/// Writes maps into a ZIP as `<name>.svg`, followed by the `manifest.json` that
+/// `MapArchiveReader` consumes.
+pub struct MapArchiveWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    manifest: MapManifest,
+    file_names: HashSet<String>,
+}
+
+impl<W: Write + Seek> MapArchiveWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            zip: ZipWriter::new(writer),
+            manifest: MapManifest::default(),
+            file_names: HashSet::new(),
+        }
+    }
+
+    /// Adds a map; `entry.file` is set to a file name unique within the archive.
+    pub fn add_map(&mut self, mut entry: MapManifestEntry, content: &str) -> Result<(), String> {
+        let name = entry.name.as_deref().unwrap_or_default();
+        entry.file = unique_file_name(name, &mut self.file_names);
+
+        self.zip
+            .start_file(entry.file.as_str(), SimpleFileOptions::default())
+            .and_then(|_| Ok(self.zip.write_all(content.as_bytes())?))
+            .map_err(|e| format!("Error al escribir {} en el ZIP: {}", entry.file, e))?;
+
+        self.manifest.maps.push(entry);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, String> {
+        let manifest = serde_json::to_vec_pretty(&self.manifest)
+            .map_err(|e| format!("Error al generar {}: {}", MANIFEST_FILE, e))?;
+
+        self.zip
+            .start_file(MANIFEST_FILE, SimpleFileOptions::default())
+            .and_then(|_| Ok(self.zip.write_all(&manifest)?))
+            .and_then(|_| self.zip.finish())
+            .map_err(|e| format!("Error al generar el ZIP: {}", e))
+    }
+}
+
+/// `<name>.svg`, without path separators, numbered when another map already took it. Names
+/// that already end in `.svg` keep a single extension.
+fn unique_file_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let stem = name.strip_suffix(".svg").or_else(|| name.strip_suffix(".SVG")).unwrap_or(name);
+    let stem: String = stem
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\') || c.is_control() { '_' } else { c })
+        .collect();
+    let stem = if stem.trim().is_empty() { "map".to_string() } else { stem };
+
+    let mut file_name = format!("{}.svg", stem);
+    let mut copy = 1;
+    while !taken.insert(file_name.to_ascii_lowercase()) {
+        copy += 1;
+        file_name = format!("{} ({}).svg", stem, copy);
+    }
+    file_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zip_with(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn read_all(bytes: &[u8]) -> Result<(Option<MapManifest>, Vec<ArchiveFile>), String> {
+        let mut reader = MapArchiveReader::new(bytes)?;
+        let mut files = Vec::new();
+        while let Some(file) = reader.next_file()? {
+            files.push(file);
+        }
+        Ok((reader.manifest, files))
+    }
+
+    #[test]
+    fn reads_what_the_writer_wrote() {
+        let mut writer = MapArchiveWriter::new(Cursor::new(Vec::new()));
+        for name in ["TC-Etapa1", "TC-Etapa1", "a/b.svg"] {
+            let entry = MapManifestEntry { name: Some(name.to_string()), ..Default::default() };
+            writer.add_map(entry, "<svg/>").unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let (manifest, files) = read_all(&bytes).unwrap();
+        let manifest = manifest.expect("the manifest should be read");
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["TC-Etapa1.svg", "TC-Etapa1 (2).svg", "a_b.svg"]);
+        assert_eq!(manifest.maps.iter().map(|entry| entry.file.as_str()).collect::<Vec<_>>(), paths);
+        assert!(files.iter().all(|file| file.content.as_deref() == Ok("<svg/>")));
+    }
+
+    #[test]
+    fn skips_directories_hidden_files_and_other_entries() {
+        let bytes = zip_with(&[
+            ("maps/a.SVG", b"<svg/>"),
+            ("maps/.b.svg", b"<svg/>"),
+            ("__MACOSX/maps/a.svg", b"<svg/>"),
+            ("notes.txt", b"hola"),
+        ]);
+
+        let (manifest, files) = read_all(&bytes).unwrap();
+        assert!(manifest.is_none());
+        assert_eq!(files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), ["maps/a.SVG"]);
+    }
+
+    #[test]
+    fn reports_files_that_are_not_utf8_without_stopping() {
+        let bytes = zip_with(&[("a.svg", &[0xff, 0xfe]), ("b.svg", b"<svg/>")]);
+
+        let (_, files) = read_all(&bytes).unwrap();
+        assert!(files[0].content.is_err());
+        assert!(files[1].content.is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_manifests_and_archives() {
+        assert!(MapArchiveReader::new(&zip_with(&[(MANIFEST_FILE, b"{")])).is_err());
+        assert!(MapArchiveReader::new(b"no es un zip").is_err());
+    }
+
+    #[test]
+    fn rejects_archives_with_too_many_entries() {
+        let names: Vec<String> = (0..=MAX_ARCHIVE_ENTRIES).map(|index| format!("{}.svg", index)).collect();
+        let files: Vec<(&str, &[u8])> = names.iter().map(|name| (name.as_str(), &b""[..])).collect();
+
+        assert!(MapArchiveReader::new(&zip_with(&files)).is_err());
+    }
+
+    #[test]
+    fn rejects_files_past_the_file_limit() {
+        let mut remaining = MAX_ARCHIVE_TOTAL_BYTES;
+        let content = vec![b' '; MAX_ARCHIVE_FILE_BYTES as usize + 10];
+
+        let read = read_entry(&mut content.as_slice(), &mut remaining).unwrap();
+        assert!(read.is_err());
+        assert_eq!(remaining, MAX_ARCHIVE_TOTAL_BYTES - MAX_ARCHIVE_FILE_BYTES - 1);
+    }
+
+    #[test]
+    fn stops_past_the_total_limit() {
+        let mut remaining = 10;
+        assert_eq!(read_entry(&mut &b"<svg/>"[..], &mut remaining).unwrap(), Ok("<svg/>".to_string()));
+        assert_eq!(remaining, 4);
+        assert!(read_entry(&mut &b"<svg/>"[..], &mut remaining).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use quick_xml::escape::escape;
+use serde::Serialize;
+use crate::interactive_maps::dto::svg_dto::{LotStatus, StatusLegendEntry};
+use crate::interactive_maps::map_status_service::UNKNOWN_STATUS_LABEL;
+
+/// Tooltips of the embedded viewer, served from [`VIEWER_SCRIPT_PATH`].
+pub const VIEWER_SCRIPT: &str = include_str!("map_embed_viewer.js");
+pub const VIEWER_SCRIPT_PATH: &str = "/api/public/embed/viewer.js";
+
+const VIEWER_STYLE: &str = "html,body{margin:0;height:100%;font-family:system-ui,sans-serif;font-size:13px;color:#222}\
+body{display:flex;flex-direction:column}\
+#map{flex:1;min-height:0}\
+#map svg{display:block;width:100%;height:100%}\
+.legend{display:flex;flex-wrap:wrap;gap:4px 12px;margin:0;padding:6px 8px;list-style:none}\
+.swatch{display:inline-block;width:10px;height:10px;margin-right:4px;border-radius:2px}\
+#tooltip{position:fixed;display:flex;flex-direction:column;gap:2px;padding:6px 8px;background:#fff;\
+border-radius:4px;box-shadow:0 1px 4px rgba(0,0,0,.3);pointer-events:none}\
+#tooltip[hidden]{display:none}";
+
+/// An embedded map ready to be served, with the headers that depend on its development.
+pub struct EmbeddedMap {
+    pub html: String,
+    pub content_security_policy: String,
+    pub etag: String,
+}
+
+/// Embedded maps by map id. The marketing site can load a map on every page view, so the
+/// page is rebuilt at most once per `ttl`; status changes show up when the entry expires.
+pub struct EmbedCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Arc<EmbeddedMap>)>>,
+}
+
+impl EmbedCache {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self {
+            ttl: Duration::from_secs(ttl_seconds),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl_seconds(&self) -> u64 {
+        self.ttl.as_secs()
+    }
+
+    pub fn get(&self, map_id: &str) -> Option<Arc<EmbeddedMap>> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(map_id)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, embedded)| embedded.clone())
+    }
+
+    /// Stores a page and drops the expired ones.
+    pub fn insert(&self, map_id: String, embedded: EmbeddedMap) -> Arc<EmbeddedMap> {
+        let embedded = Arc::new(embedded);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+            entries.insert(map_id, (Instant::now(), embedded.clone()));
+        }
+        embedded
+    }
+}
+
+/// Scripts only come from this server and the page can only be framed by `origins`.
+pub fn content_security_policy(origins: &[String]) -> String {
+    format!(
+        "default-src 'none'; script-src 'self'; style-src 'unsafe-inline'; img-src data:; frame-ancestors {}",
+        origins.join(" ")
+    )
+}
+
+#[derive(Serialize)]
+struct TooltipLot<'a> {
+    lot_number: &'a str,
+    status: &'a str,
+    color: &'a str,
+}
+
+/// Standalone HTML page with the colored map, its legend and the lot data of the tooltips.
+/// `rendered` is the map content with the status fills already applied.
+pub fn render_embedded_map(
+    title: &str,
+    rendered: &str,
+    statuses: &[LotStatus],
+    legend: &[StatusLegendEntry],
+) -> Result<String, String> {
+    // The XML declaration and doctype of the stored file are not valid inside HTML.
+    let svg = rendered
+        .find("<svg")
+        .map(|start| &rendered[start..])
+        .ok_or_else(|| "El mapa no contiene un elemento <svg>".to_string())?;
+
+    let lots: HashMap<&str, TooltipLot> = statuses
+        .iter()
+        .map(|status| {
+            let lot = TooltipLot {
+                lot_number: &status.lot_number,
+                status: status.estatus_venta.as_deref().unwrap_or(UNKNOWN_STATUS_LABEL),
+                color: &status.color,
+            };
+            (status.element_id.as_str(), lot)
+        })
+        .collect();
+    // `<` is escaped so a lot value can't close the data block.
+    let lots = serde_json::to_string(&lots)
+        .map_err(|e| format!("Error al generar el mapa: {}", e))?
+        .replace('<', "\\u003c");
+
+    let legend: String = legend
+        .iter()
+        .map(|entry| {
+            format!(
+                "<li><span class=\"swatch\" style=\"background:{}\"></span>{} ({})</li>",
+                escape(entry.color.as_str()),
+                escape(entry.name.as_str()),
+                entry.count
+            )
+        })
+        .collect();
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <div id=\"map\">{svg}</div>\n<ul class=\"legend\">{legend}</ul>\n<div id=\"tooltip\" hidden></div>\n\
+         <script type=\"application/json\" id=\"lots\">{lots}</script>\n\
+         <script src=\"{script}\" defer></script>\n</body>\n</html>\n",
+        title = escape(title),
+        style = VIEWER_STYLE,
+        svg = svg,
+        legend = legend,
+        lots = lots,
+        script = VIEWER_SCRIPT_PATH,
+    ))
+}
@@ -0,0 +1,48 @@
+// Tooltips of the embedded map viewer. Served as a file so the page CSP doesn't allow inline scripts.
+(function () {
+    var data = document.getElementById("lots");
+    var tooltip = document.getElementById("tooltip");
+    var map = document.getElementById("map");
+    if (!data || !tooltip || !map) {
+        return;
+    }
+
+    var lots = JSON.parse(data.textContent);
+
+    function lotAt(target) {
+        while (target && target !== map) {
+            if (target.id && Object.prototype.hasOwnProperty.call(lots, target.id)) {
+                return lots[target.id];
+            }
+            target = target.parentNode;
+        }
+        return null;
+    }
+
+    map.addEventListener("mousemove", function (event) {
+        var lot = lotAt(event.target);
+        if (!lot) {
+            tooltip.hidden = true;
+            return;
+        }
+
+        tooltip.textContent = "";
+        var title = document.createElement("strong");
+        title.textContent = "Lote " + lot.lot_number;
+        var status = document.createElement("span");
+        status.textContent = lot.status;
+        status.style.color = lot.color;
+        tooltip.appendChild(title);
+        tooltip.appendChild(status);
+
+        tooltip.hidden = false;
+        var x = Math.min(event.clientX + 12, window.innerWidth - tooltip.offsetWidth - 4);
+        var y = Math.min(event.clientY + 12, window.innerHeight - tooltip.offsetHeight - 4);
+        tooltip.style.left = Math.max(x, 4) + "px";
+        tooltip.style.top = Math.max(y, 4) + "px";
+    });
+
+    map.addEventListener("mouseleave", function () {
+        tooltip.hidden = true;
+    });
+})();
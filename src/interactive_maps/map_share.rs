@@ -0,0 +1,184 @@
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use jsonwebtoken::{decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use crate::common::errors::ApiError;
+use crate::interactive_maps::dto::svg_dto::LotStatus;
+use crate::status_colors::status_color_service::{normalize_status, UNKNOWN_STATUS_COLOR};
+
+/// Lifetime of a share link when none is requested.
+pub const DEFAULT_SHARE_HOURS: i64 = 72;
+pub const MAX_SHARE_HOURS: i64 = 30 * 24;
+
+/// Claims of a share link. They are signed with their own secret, so a share token is never
+/// accepted as a session and a session token never opens a shared map.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShareClaims {
+    pub map_id: String,
+    pub exp: usize,
+    /// Sales statuses the viewer can see; lots in any other status are shown without one.
+    /// `None` shows every status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_statuses: Option<Vec<String>>,
+}
+
+impl ShareClaims {
+    pub fn expires_at(&self) -> NaiveDateTime {
+        DateTime::from_timestamp(self.exp as i64, 0)
+            .unwrap_or_default()
+            .naive_utc()
+    }
+
+    /// Hides the statuses the link doesn't show, as if the lots had no product.
+    pub fn apply_status_policy(&self, statuses: &mut [LotStatus]) {
+        let visible: Vec<String> = match &self.visible_statuses {
+            Some(visible) => visible.iter().map(|status| normalize_status(status)).collect(),
+            None => return,
+        };
+
+        for status in statuses {
+            let shown = status
+                .estatus_venta
+                .as_deref()
+                .is_some_and(|estatus_venta| visible.contains(&normalize_status(estatus_venta)));
+            if !shown {
+                status.estatus_venta = None;
+                status.color = UNKNOWN_STATUS_COLOR.to_string();
+            }
+        }
+    }
+}
+
+/// Signs a share token for a map that expires `hours` from now.
+pub fn mint_share_token(
+    secret: &str,
+    map_id: &str,
+    hours: i64,
+    visible_statuses: Option<Vec<String>>,
+) -> Result<(String, NaiveDateTime), ApiError> {
+    let expires_at = Utc::now() + Duration::hours(hours);
+    let claims = ShareClaims {
+        map_id: map_id.to_string(),
+        exp: expires_at.timestamp() as usize,
+        visible_statuses,
+    };
+
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref()))
+        .map_err(|e| ApiError::InternalError(format!("Error al firmar el enlace: {}", e)))?;
+
+    Ok((token, claims.expires_at()))
+}
+
+pub fn verify_share_token(secret: &str, token: &str) -> Result<ShareClaims, ApiError> {
+    decode::<ShareClaims>(token, &DecodingKey::from_secret(secret.as_ref()), &Validation::new(Algorithm::HS256))
+        .map(|token_data| token_data.claims)
+        .map_err(|e| match e.kind() {
+            ErrorKind::ExpiredSignature => ApiError::TokenExpired("El enlace expiró".to_string()),
+            _ => ApiError::InvalidToken("Enlace inválido".to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "share-secret";
+    const MAP_ID: &str = "550e8400-e29b-41d4-a716-446655440000";
+
+    fn status(element_id: &str, estatus_venta: Option<&str>) -> LotStatus {
+        LotStatus {
+            element_id: element_id.to_string(),
+            lot_number: element_id.trim_start_matches("lote").to_string(),
+            product_name: format!("TC-{}", element_id),
+            estatus_venta: estatus_venta.map(str::to_string),
+            color: "#2E7D32".to_string(),
+        }
+    }
+
+    #[test]
+    fn verifies_the_tokens_it_mints() {
+        let (token, expires_at) = mint_share_token(SECRET, MAP_ID, 24, Some(vec!["Disponible".to_string()]))
+            .expect("the token should be signed");
+
+        let claims = verify_share_token(SECRET, &token).expect("the token should verify");
+        assert_eq!(claims.map_id, MAP_ID);
+        assert_eq!(claims.expires_at(), expires_at);
+        assert_eq!(claims.visible_statuses, Some(vec!["Disponible".to_string()]));
+
+        let remaining = expires_at - Utc::now().naive_utc();
+        assert!(remaining > Duration::hours(23) && remaining <= Duration::hours(24));
+    }
+
+    #[test]
+    fn rejects_tokens_signed_with_another_secret() {
+        let (token, _) = mint_share_token("session-secret", MAP_ID, 1, None).expect("the token should be signed");
+
+        assert!(matches!(verify_share_token(SECRET, &token), Err(ApiError::InvalidToken(_))));
+        assert!(matches!(verify_share_token(SECRET, "not-a-token"), Err(ApiError::InvalidToken(_))));
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let claims = ShareClaims {
+            map_id: MAP_ID.to_string(),
+            exp: (Utc::now() - Duration::hours(1)).timestamp() as usize,
+            visible_statuses: None,
+        };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_ref()))
+            .expect("the token should be signed");
+
+        assert!(matches!(verify_share_token(SECRET, &token), Err(ApiError::TokenExpired(_))));
+    }
+
+    #[test]
+    fn omits_the_status_filter_when_every_status_is_shown() {
+        let (token, _) = mint_share_token(SECRET, MAP_ID, 1, None).expect("the token should be signed");
+        let payload = decode::<serde_json::Value>(
+            &token,
+            &DecodingKey::from_secret(SECRET.as_ref()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .expect("the token should decode")
+        .claims;
+
+        assert!(payload.get("visible_statuses").is_none());
+        assert_eq!(verify_share_token(SECRET, &token).unwrap().visible_statuses, None);
+    }
+
+    #[test]
+    fn hides_the_statuses_the_link_does_not_show() {
+        let claims = ShareClaims {
+            map_id: MAP_ID.to_string(),
+            exp: 0,
+            visible_statuses: Some(vec![" disponible ".to_string()]),
+        };
+        let mut statuses = vec![
+            status("lote1", Some("Disponible")),
+            status("lote2", Some("Vendido")),
+            status("lote3", None),
+        ];
+
+        claims.apply_status_policy(&mut statuses);
+
+        assert_eq!(statuses[0].estatus_venta.as_deref(), Some("Disponible"));
+        assert_eq!(statuses[0].color, "#2E7D32");
+        assert_eq!(statuses[1].estatus_venta, None);
+        assert_eq!(statuses[1].color, UNKNOWN_STATUS_COLOR);
+        assert_eq!(statuses[2].estatus_venta, None);
+        assert_eq!(statuses[2].color, UNKNOWN_STATUS_COLOR);
+    }
+
+    #[test]
+    fn shows_every_status_without_a_filter() {
+        let claims = ShareClaims {
+            map_id: MAP_ID.to_string(),
+            exp: 0,
+            visible_statuses: None,
+        };
+        let mut statuses = vec![status("lote1", Some("Vendido"))];
+
+        claims.apply_status_policy(&mut statuses);
+
+        assert_eq!(statuses[0].estatus_venta.as_deref(), Some("Vendido"));
+        assert_eq!(statuses[0].color, "#2E7D32");
+    }
+}
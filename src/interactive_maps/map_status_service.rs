@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::common::errors::ApiError;
+use crate::interactive_maps::dto::svg_dto::{
+    LotStatus, ReconciliationReport, StatusLegendEntry, UnmatchedLot, UnmatchedProduct,
+};
+use crate::interactive_maps::entities::map_lot_entity::MapLot;
+use crate::products::products_service::ProductService;
+use crate::status_colors::status_color_service::{normalize_status, StatusColorService, UNKNOWN_STATUS_COLOR};
+
+/// Legend label for lots without a product or whose status has no color.
+pub const UNKNOWN_STATUS_LABEL: &str = "Sin estatus";
+
+/// Name of the Zoho product behind a lot, e.g. prefix `TC` and `lote6` give `TC6`.
+pub fn product_name_for_lot(prefix: &str, lot_number: &str) -> String {
+    format!("{}{}", prefix, lot_number)
+}
+
+/// Resolves the sales status and display color of the lots of a map from the products cache
+/// and the `status_colors` table.
+pub struct MapStatusService {
+    product_service: Arc<Mutex<ProductService>>,
+    status_color_service: Arc<StatusColorService>,
+}
+
+impl MapStatusService {
+    pub fn new(product_service: Arc<Mutex<ProductService>>, status_color_service: Arc<StatusColorService>) -> Self {
+        Self {
+            product_service,
+            status_color_service,
+        }
+    }
+
+    pub async fn resolve_lot_statuses(&self, prefix: &str, lots: &[MapLot]) -> Result<Vec<LotStatus>, ApiError> {
+        let product_names: Vec<String> = lots
+            .iter()
+            .map(|lot| product_name_for_lot(prefix, &lot.lot_number))
+            .collect();
+
+        let products = if product_names.is_empty() {
+            Vec::new()
+        } else {
+            let mut service = self.product_service.lock().await;
+            service.get_many_by_ids(product_names.iter().map(String::as_str).collect())?
+        };
+
+        let status_by_product: HashMap<String, Option<String>> = products
+            .into_iter()
+            .filter_map(|product| product.product_name.map(|name| (name, product.estatus_venta)))
+            .collect();
+
+        let colors = self.status_color_service.get_colors_by_status()?;
+
+        Ok(lots
+            .iter()
+            .zip(product_names)
+            .map(|(lot, product_name)| {
+                let estatus_venta = status_by_product.get(&product_name).cloned().flatten();
+                let color = estatus_venta
+                    .as_deref()
+                    .and_then(|status| colors.get(&normalize_status(status)))
+                    .map(|color| color.hexadecimal.clone())
+                    .unwrap_or_else(|| UNKNOWN_STATUS_COLOR.to_string());
+
+                LotStatus {
+                    element_id: lot.element_id.clone(),
+                    lot_number: lot.lot_number.clone(),
+                    product_name,
+                    estatus_venta,
+                    color,
+                }
+            })
+            .collect())
+    }
+
+    /// Legend of a map: every status of `status_colors` with its number of lots, plus an
+    /// "Sin estatus" entry when some lots have no colored status.
+    pub fn status_legend(&self, statuses: &[LotStatus]) -> Result<Vec<StatusLegendEntry>, ApiError> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for status in statuses {
+            if let Some(estatus_venta) = &status.estatus_venta {
+                *counts.entry(normalize_status(estatus_venta)).or_default() += 1;
+            }
+        }
+
+        let mut legend: Vec<StatusLegendEntry> = self.status_color_service
+            .get_all_colors()?
+            .colors
+            .into_iter()
+            .map(|color| StatusLegendEntry {
+                count: counts.remove(&normalize_status(&color.status)).unwrap_or(0),
+                name: color.name,
+                color: color.hexadecimal,
+            })
+            .collect();
+
+        let unknown = statuses.len() - legend.iter().map(|entry| entry.count).sum::<usize>();
+        if unknown > 0 {
+            legend.push(StatusLegendEntry {
+                name: UNKNOWN_STATUS_LABEL.to_string(),
+                color: UNKNOWN_STATUS_COLOR.to_string(),
+                count: unknown,
+            });
+        }
+
+        Ok(legend)
+    }
+
+    /// Compares the lots of a map with the products of its prefix. Only products named
+    /// prefix + digits count as lots of the map, so `TCX1` is not reported for prefix `TC`.
+    pub async fn reconcile(&self, svg_id: &str, prefix: &str, lots: &[MapLot]) -> Result<ReconciliationReport, ApiError> {
+        let products: Vec<_> = {
+            let mut service = self.product_service.lock().await;
+            service.get_by_name_prefix(prefix)?
+        }
+        .into_iter()
+        .filter(|product| {
+            product
+                .product_name
+                .as_deref()
+                .and_then(|name| name.strip_prefix(prefix))
+                .is_some_and(|lot_number| !lot_number.is_empty() && lot_number.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .collect();
+
+        let status_by_product: HashMap<&str, Option<&str>> = products
+            .iter()
+            .filter_map(|product| {
+                product
+                    .product_name
+                    .as_deref()
+                    .map(|name| (name, product.estatus_venta.as_deref()))
+            })
+            .collect();
+
+        let colors = self.status_color_service.get_colors_by_status()?;
+
+        let mut map_product_names = HashSet::new();
+        let mut lots_without_product = Vec::new();
+        let mut statuses_without_color = Vec::new();
+
+        for lot in lots {
+            let product_name = product_name_for_lot(prefix, &lot.lot_number);
+
+            match status_by_product.get(product_name.as_str()) {
+                None => lots_without_product.push(UnmatchedLot {
+                    element_id: lot.element_id.clone(),
+                    lot_number: lot.lot_number.clone(),
+                    product_name: product_name.clone(),
+                }),
+                Some(Some(status)) if !colors.contains_key(&normalize_status(status)) => {
+                    statuses_without_color.push(LotStatus {
+                        element_id: lot.element_id.clone(),
+                        lot_number: lot.lot_number.clone(),
+                        product_name: product_name.clone(),
+                        estatus_venta: Some(status.to_string()),
+                        color: UNKNOWN_STATUS_COLOR.to_string(),
+                    })
+                }
+                Some(_) => {}
+            }
+
+            map_product_names.insert(product_name);
+        }
+
+        let products_without_lot = products
+            .iter()
+            .filter_map(|product| {
+                let name = product.product_name.as_ref()?;
+                (!map_product_names.contains(name)).then(|| UnmatchedProduct {
+                    product_id: product.id.clone(),
+                    product_name: name.clone(),
+                    estatus_venta: product.estatus_venta.clone(),
+                })
+            })
+            .collect();
+
+        Ok(ReconciliationReport {
+            id: svg_id.to_string(),
+            prefix: prefix.to_string(),
+            total_lots: lots.len(),
+            total_products: products.len(),
+            lots_without_product,
+            products_without_lot,
+            statuses_without_color,
+        })
+    }
+}
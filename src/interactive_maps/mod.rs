@@ -2,4 +2,10 @@ pub mod interactive_maps_handler;
 pub mod dto;
 pub mod entities;
 pub mod interactive_maps_service;
-pub mod interactive_maps_repository;
\ No newline at end of file
+pub mod interactive_maps_repository;
+pub mod storage;
+pub mod map_status_service;
+pub mod map_archive;
+pub mod map_embed;
+pub mod map_share;
+pub mod svg;
\ No newline at end of file
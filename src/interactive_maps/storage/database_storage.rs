@@ -0,0 +1,21 @@
+use super::map_storage::{MapStorage, StorageError, StoredContent};
+
+/// Keeps the content in the `content` column of `maps_svg`, as before storage backends existed.
+pub struct DatabaseStorage;
+
+impl MapStorage for DatabaseStorage {
+    fn store(&self, _key: &str, content: &str) -> Result<StoredContent, StorageError> {
+        Ok(StoredContent::Inline(content.to_string()))
+    }
+
+    fn load(&self, key: &str) -> Result<String, StorageError> {
+        Err(StorageError(format!(
+            "El mapa está en un almacenamiento externo ({}) y MAP_STORAGE_BACKEND es database",
+            key
+        )))
+    }
+
+    fn remove(&self, _key: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use super::map_storage::{MapStorage, StorageError, StoredContent};
+
+/// Stores each revision as a file in a local directory (`svg_storage` by default).
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: &str) -> Self {
+        Self { root: PathBuf::from(root) }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(StorageError(format!("Clave de almacenamiento inválida: {}", key)));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl MapStorage for FilesystemStorage {
+    fn store(&self, key: &str, content: &str) -> Result<StoredContent, StorageError> {
+        let path = self.path(key)?;
+        fs::create_dir_all(&self.root)
+            .map_err(|e| StorageError(format!("Error al crear {}: {}", self.root.display(), e)))?;
+
+        // Written under a temporary name and renamed, so readers never see a partial file.
+        let temporary = path.with_extension("svg.tmp");
+        fs::write(&temporary, content)
+            .and_then(|_| fs::rename(&temporary, &path))
+            .map_err(|e| StorageError(format!("Error al escribir {}: {}", path.display(), e)))?;
+
+        Ok(StoredContent::External(key.to_string()))
+    }
+
+    fn load(&self, key: &str) -> Result<String, StorageError> {
+        let path = self.path(key)?;
+        fs::read_to_string(&path)
+            .map_err(|e| StorageError(format!("Error al leer {}: {}", path.display(), e)))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(StorageError(format!("Error al eliminar {}: {}", path.display(), e)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::interactive_maps::storage::map_storage::new_storage_key;
+
+    fn temporary_storage() -> (FilesystemStorage, PathBuf) {
+        let root = std::env::temp_dir().join(format!("map-storage-{}", Uuid::new_v4()));
+        (FilesystemStorage::new(root.to_str().unwrap()), root)
+    }
+
+    #[test]
+    fn stores_loads_and_removes() {
+        let (storage, root) = temporary_storage();
+        let key = new_storage_key(".TC-Etapa 1");
+        let content = "<svg xmlns=\"http://www.w3.org/2000/svg\"><g id=\"lote1\"/></svg>";
+
+        assert_eq!(storage.store(&key, content).unwrap(), StoredContent::External(key.clone()));
+        assert_eq!(storage.load(&key).unwrap(), content);
+
+        storage.store(&key, "<svg/>").unwrap();
+        assert_eq!(storage.load(&key).unwrap(), "<svg/>");
+
+        storage.remove(&key).unwrap();
+        assert!(storage.load(&key).is_err());
+        // Removing twice is not an error, so a failed save can always be cleaned up.
+        storage.remove(&key).unwrap();
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_keys_outside_the_directory() {
+        let (storage, _) = temporary_storage();
+        for key in ["", "../a.svg", "a/b.svg", "a\\b.svg", ".a.svg"] {
+            assert!(storage.store(key, "<svg/>").is_err(), "{}", key);
+            assert!(storage.load(key).is_err(), "{}", key);
+        }
+    }
+}
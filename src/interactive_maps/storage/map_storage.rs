@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+use crate::common::config::MapStorageConfig;
+use super::database_storage::DatabaseStorage;
+use super::filesystem_storage::FilesystemStorage;
+use super::s3_storage::S3Storage;
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct StorageError(pub String);
+
+/// Where the content of a map revision ended up. `Inline` content goes into the `content`
+/// column; `External` content is referenced by its key in `storage_key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoredContent {
+    Inline(String),
+    External(String),
+}
+
+impl StoredContent {
+    /// Values for the `content` and `storage_key` columns.
+    pub fn into_columns(self) -> (String, Option<String>) {
+        match self {
+            StoredContent::Inline(content) => (content, None),
+            StoredContent::External(key) => (String::new(), Some(key)),
+        }
+    }
+}
+
+/// Backend holding the SVG content of maps and their revisions. Metadata always stays in
+/// MySQL. Rows written by another backend keep working: inline rows are read from the
+/// `content` column whatever the current backend is.
+pub trait MapStorage: Send + Sync {
+    fn store(&self, key: &str, content: &str) -> Result<StoredContent, StorageError>;
+    fn load(&self, key: &str) -> Result<String, StorageError>;
+    fn remove(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Builds the backend selected by `MAP_STORAGE_BACKEND`.
+pub fn build_map_storage(config: &MapStorageConfig) -> Result<Arc<dyn MapStorage>, StorageError> {
+    match config.backend.as_str() {
+        "database" => Ok(Arc::new(DatabaseStorage)),
+        "filesystem" => Ok(Arc::new(FilesystemStorage::new(&config.directory))),
+        "s3" => Ok(Arc::new(S3Storage::new(config)?)),
+        other => Err(StorageError(format!(
+            "MAP_STORAGE_BACKEND desconocido: {} (database, filesystem o s3)",
+            other
+        ))),
+    }
+}
+
+/// Longest part of a key taken from the map name, so keys stay valid file names.
+const MAX_KEY_NAME_LENGTH: usize = 100;
+
+/// Key of a new revision, `<name>-<uuid>.svg` as in the `svg_storage` directory. Every
+/// revision gets its own key, so archived versions keep pointing at their content. The name
+/// is escaped to ASCII letters, digits, `-` and `_`, so a key never starts with `.` nor
+/// leaves the storage directory.
+pub fn new_storage_key(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .take(MAX_KEY_NAME_LENGTH)
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') { c } else { '_' })
+        .collect();
+
+    format!("{}-{}.svg", name, Uuid::new_v4())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_keys_are_plain_file_names() {
+        for name in ["TC-Etapa 1", ".oculto", "../../etc/passwd", "a\\b", "", "Ñandú.svg"] {
+            let key = new_storage_key(name);
+            assert!(!key.starts_with('.'), "{}", key);
+            assert!(!key.contains(['/', '\\']), "{}", key);
+            assert!(key.ends_with(".svg"));
+            assert_eq!(key.matches('.').count(), 1, "{}", key);
+        }
+        assert!(new_storage_key("TC-Etapa 1").starts_with("TC-Etapa_1-"));
+    }
+
+    #[test]
+    fn storage_keys_are_unique() {
+        assert_ne!(new_storage_key("TC"), new_storage_key("TC"));
+    }
+
+    #[test]
+    fn storage_keys_have_a_bounded_length() {
+        let key = new_storage_key(&"a".repeat(1000));
+        assert!(key.len() <= MAX_KEY_NAME_LENGTH + 42);
+    }
+}
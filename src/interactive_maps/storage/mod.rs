@@ -0,0 +1,4 @@
+pub mod map_storage;
+pub mod database_storage;
+pub mod filesystem_storage;
+pub mod s3_storage;
@@ -0,0 +1,122 @@
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use crate::common::config::MapStorageConfig;
+use super::map_storage::{MapStorage, StorageError, StoredContent};
+
+/// Stores each revision as an object of an S3-compatible bucket (AWS, MinIO, ...).
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+    prefix: String,
+}
+
+impl S3Storage {
+    pub fn new(config: &MapStorageConfig) -> Result<Self, StorageError> {
+        let bucket_name = config
+            .s3_bucket
+            .as_deref()
+            .ok_or_else(|| StorageError("MAP_STORAGE_S3_BUCKET es requerido".to_string()))?;
+
+        let region = match &config.s3_endpoint {
+            Some(endpoint) => Region::Custom {
+                region: config.s3_region.clone(),
+                endpoint: endpoint.clone(),
+            },
+            None => config
+                .s3_region
+                .parse()
+                .map_err(|e| StorageError(format!("Región S3 inválida: {}", e)))?,
+        };
+
+        let credentials = Credentials::new(
+            config.s3_access_key.as_deref(),
+            config.s3_secret_key.as_deref(),
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| StorageError(format!("Credenciales S3 inválidas: {}", e)))?;
+
+        let bucket = Bucket::new(bucket_name, region, credentials)
+            .map_err(|e| StorageError(format!("Error al configurar el bucket S3: {}", e)))?;
+
+        // MinIO and most S3-compatible servers only support path-style URLs.
+        let bucket = if config.s3_endpoint.is_some() {
+            bucket.with_path_style()
+        } else {
+            bucket
+        };
+
+        Ok(Self {
+            bucket,
+            prefix: config.s3_prefix.clone(),
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("{}/{}", self.prefix.trim_end_matches('/'), key)
+    }
+}
+
+impl MapStorage for S3Storage {
+    fn store(&self, key: &str, content: &str) -> Result<StoredContent, StorageError> {
+        self.bucket
+            .put_object_with_content_type(self.object_path(key), content.as_bytes(), "image/svg+xml")
+            .map_err(|e| StorageError(format!("Error al guardar {} en S3: {}", key, e)))?;
+
+        Ok(StoredContent::External(key.to_string()))
+    }
+
+    fn load(&self, key: &str) -> Result<String, StorageError> {
+        let response = self.bucket
+            .get_object(self.object_path(key))
+            .map_err(|e| StorageError(format!("Error al leer {} de S3: {}", key, e)))?;
+
+        response
+            .to_string()
+            .map_err(|e| StorageError(format!("Contenido inválido en {}: {}", key, e)))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        self.bucket
+            .delete_object(self.object_path(key))
+            .map_err(|e| StorageError(format!("Error al eliminar {} de S3: {}", key, e)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive_maps::storage::map_storage::new_storage_key;
+
+    /// The bucket of the `minio` service of docker-compose, or the one in `MAP_STORAGE_S3_*`.
+    fn minio_storage() -> S3Storage {
+        let env = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let config = MapStorageConfig {
+            backend: "s3".to_string(),
+            directory: String::new(),
+            s3_bucket: Some(env("MAP_STORAGE_S3_BUCKET", "urvic-maps")),
+            s3_region: env("MAP_STORAGE_S3_REGION", "us-east-1"),
+            s3_endpoint: Some(env("MAP_STORAGE_S3_ENDPOINT", "http://localhost:9000")),
+            s3_access_key: Some(env("MAP_STORAGE_S3_ACCESS_KEY", "minioadmin")),
+            s3_secret_key: Some(env("MAP_STORAGE_S3_SECRET_KEY", "minioadmin")),
+            s3_prefix: "maps-test".to_string(),
+        };
+        S3Storage::new(&config).expect("the S3 configuration should be valid")
+    }
+
+    #[test]
+    #[ignore = "needs MinIO: docker compose up minio minio-init"]
+    fn stores_loads_and_removes() {
+        let storage = minio_storage();
+        let key = new_storage_key("TC-Etapa 1");
+        let content = "<svg xmlns=\"http://www.w3.org/2000/svg\"><g id=\"lote1\"/></svg>";
+
+        assert_eq!(storage.store(&key, content).unwrap(), StoredContent::External(key.clone()));
+        assert_eq!(storage.load(&key).unwrap(), content);
+
+        storage.remove(&key).unwrap();
+        assert!(storage.load(&key).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod svg_diff;
+pub mod svg_geometry;
+pub mod svg_layers;
+pub mod svg_lots;
+pub mod svg_optimizer;
+pub mod svg_pdf;
+pub mod svg_renderer;
+pub mod svg_sanitizer;
+pub mod svg_thumbnails;
+pub mod svg_xml;
@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet};
+use quick_xml::escape::escape;
+use super::svg_geometry::{measure_lots, user_space, LotGeometry};
+use super::svg_layers::{compose_layers, LayerSource};
+use super::svg_lots::extract_lots;
+use super::svg_renderer::apply_fills;
+
+/// Distance in SVG units under which two lot outlines are considered the same.
+pub const DEFAULT_DIFF_TOLERANCE: f64 = 1.0;
+
+const ADDED_COLOR: &str = "#2E7D32";
+const RENAMED_COLOR: &str = "#1565C0";
+const CHANGED_COLOR: &str = "#EF6C00";
+const REMOVED_COLOR: &str = "#C62828";
+const UNCHANGED_COLOR: &str = "#E0E0E0";
+/// Layer of the visual diff with the outlines of the removed lots and the previous outlines
+/// of the lots that changed.
+const DIFF_LAYER: &str = "diff";
+
+#[derive(Debug, Clone)]
+pub struct LotGeometryChange {
+    pub element_id: String,
+    pub before: LotGeometry,
+    pub after: LotGeometry,
+}
+
+impl LotGeometryChange {
+    pub fn centroid_shift(&self) -> f64 {
+        (self.after.centroid_x - self.before.centroid_x).hypot(self.after.centroid_y - self.before.centroid_y)
+    }
+}
+
+/// Lot elements of two maps compared by id and by outline, in document order.
+#[derive(Debug, Clone)]
+pub struct LotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Lots whose element id changed but whose outline didn't, as `(before, after)`.
+    pub renamed: Vec<(String, String)>,
+    /// Lots with the same element id whose outline moved or changed size beyond the tolerance.
+    pub geometry_changed: Vec<LotGeometryChange>,
+    pub unchanged: usize,
+    removed_geometry: Vec<LotGeometry>,
+}
+
+/// Compares the lots of `before` and `after`. Both maps must be drawn on the same canvas for
+/// their outlines to be comparable; lots that can't be measured only count by their id.
+pub fn diff_lots(before: &str, after: &str, tolerance: f64) -> Result<LotDiff, String> {
+    let before_ids: Vec<String> = extract_lots(before)?.into_iter().map(|lot| lot.element_id).collect();
+    let after_ids: Vec<String> = extract_lots(after)?.into_iter().map(|lot| lot.element_id).collect();
+    let before_geometry = measure_lots(before, &before_ids.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let after_geometry = measure_lots(after, &after_ids.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+    let before_set: HashSet<&str> = before_ids.iter().map(String::as_str).collect();
+    let after_set: HashSet<&str> = after_ids.iter().map(String::as_str).collect();
+
+    let mut geometry_changed = Vec::new();
+    let mut unchanged = 0;
+    for element_id in after_ids.iter().filter(|id| before_set.contains(id.as_str())) {
+        match (before_geometry.get(element_id), after_geometry.get(element_id)) {
+            (Some(&before), Some(&after)) if !same_outline(&before, &after, tolerance) => {
+                geometry_changed.push(LotGeometryChange { element_id: element_id.clone(), before, after });
+            }
+            _ => unchanged += 1,
+        }
+    }
+
+    let mut added: Vec<&String> = after_ids.iter().filter(|id| !before_set.contains(id.as_str())).collect();
+    let mut removed = Vec::new();
+    let mut renamed = Vec::new();
+    for element_id in before_ids.iter().filter(|id| !after_set.contains(id.as_str())) {
+        let match_index = before_geometry.get(element_id).and_then(|before| {
+            added
+                .iter()
+                .enumerate()
+                .filter_map(|(index, candidate)| {
+                    let after = after_geometry.get(candidate.as_str())?;
+                    same_outline(before, after, tolerance).then(|| (index, centroid_distance(before, after)))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(index, _)| index)
+        });
+
+        match match_index {
+            Some(index) => renamed.push((element_id.clone(), added.remove(index).clone())),
+            None => removed.push(element_id.clone()),
+        }
+    }
+
+    Ok(LotDiff {
+        added: added.into_iter().cloned().collect(),
+        removed_geometry: removed.iter().filter_map(|id| before_geometry.get(id).copied()).collect(),
+        removed,
+        renamed,
+        geometry_changed,
+        unchanged,
+    })
+}
+
+/// `after` with added lots in green, renamed lots in blue, changed lots in orange and the
+/// rest in grey, plus a `diff` layer with the outlines of the removed lots in red and the
+/// previous outlines of the changed lots in dashed orange.
+pub fn render_lot_diff(after: &str, diff: &LotDiff) -> Result<String, String> {
+    let fills: HashMap<String, String> = extract_lots(after)?
+        .into_iter()
+        .map(|lot| (lot.element_id, UNCHANGED_COLOR.to_string()))
+        .chain(diff.added.iter().map(|id| (id.clone(), ADDED_COLOR.to_string())))
+        .chain(diff.renamed.iter().map(|(_, id)| (id.clone(), RENAMED_COLOR.to_string())))
+        .chain(diff.geometry_changed.iter().map(|change| (change.element_id.clone(), CHANGED_COLOR.to_string())))
+        .collect();
+    let filled = apply_fills(after, &fills)?;
+
+    // The outlines are in the map's user units, so the layer shares its viewBox.
+    let (x, y, width, height) = user_space(after)?;
+    let mut overlay = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" preserveAspectRatio=\"none\">",
+        x, y, width, height
+    );
+    for (element_id, geometry) in diff.removed.iter().zip(&diff.removed_geometry) {
+        overlay.push_str(&outline(element_id, geometry, REMOVED_COLOR, false));
+    }
+    for change in &diff.geometry_changed {
+        overlay.push_str(&outline(&change.element_id, &change.before, CHANGED_COLOR, true));
+    }
+    overlay.push_str("</svg>");
+
+    compose_layers(&filled, &[LayerSource::Base, LayerSource::Overlay { name: DIFF_LAYER, content: &overlay }])
+}
+
+fn outline(element_id: &str, geometry: &LotGeometry, color: &str, dashed: bool) -> String {
+    format!(
+        "<rect data-lot=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\" \
+         stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"{}/>",
+        escape(element_id),
+        geometry.bbox_x,
+        geometry.bbox_y,
+        geometry.bbox_width,
+        geometry.bbox_height,
+        color,
+        if dashed { " stroke-dasharray=\"6 4\"" } else { "" }
+    )
+}
+
+fn centroid_distance(a: &LotGeometry, b: &LotGeometry) -> f64 {
+    (a.centroid_x - b.centroid_x).hypot(a.centroid_y - b.centroid_y)
+}
+
+fn same_outline(a: &LotGeometry, b: &LotGeometry, tolerance: f64) -> bool {
+    centroid_distance(a, b) <= tolerance
+        && (a.bbox_width - b.bbox_width).abs() <= tolerance
+        && (a.bbox_height - b.bbox_height).abs() <= tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(lots: &str) -> String {
+        format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">{}</svg>"#, lots)
+    }
+
+    #[test]
+    fn classifies_lots_by_id_and_outline() {
+        let before = map(concat!(
+            r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote2" x="20" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote3" x="40" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote4" x="60" y="0" width="10" height="10"/>"#,
+        ));
+        let after = map(concat!(
+            r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote2" x="20" y="30" width="10" height="10"/>"#,
+            r#"<rect id="lote30" x="40" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote5" x="80" y="0" width="10" height="10"/>"#,
+        ));
+
+        let diff = diff_lots(&before, &after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added, vec!["lote5"]);
+        assert_eq!(diff.removed, vec!["lote4"]);
+        assert_eq!(diff.renamed, vec![("lote3".to_string(), "lote30".to_string())]);
+        assert_eq!(diff.geometry_changed.len(), 1);
+        assert_eq!(diff.geometry_changed[0].element_id, "lote2");
+        assert!((diff.geometry_changed[0].centroid_shift() - 30.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ignores_changes_within_the_tolerance() {
+        let before = map(r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#);
+        let after = map(r#"<rect id="lote1" x="0.5" y="0" width="10.5" height="10"/>"#);
+
+        let diff = diff_lots(&before, &after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+        assert_eq!(diff.unchanged, 1);
+        assert!(diff.geometry_changed.is_empty());
+
+        let diff = diff_lots(&before, &after, 0.1).expect("the maps should parse");
+        assert_eq!(diff.unchanged, 0);
+        assert_eq!(diff.geometry_changed.len(), 1);
+    }
+
+    #[test]
+    fn renames_to_the_closest_matching_outline() {
+        let before = map(r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#);
+        let after = map(concat!(
+            r#"<rect id="lote7" x="0.8" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote8" x="0.2" y="0" width="10" height="10"/>"#,
+        ));
+
+        let diff = diff_lots(&before, &after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+
+        assert_eq!(diff.renamed, vec![("lote1".to_string(), "lote8".to_string())]);
+        assert_eq!(diff.added, vec!["lote7"]);
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn compares_unmeasurable_lots_by_id() {
+        let before = map(r#"<text id="lote1">1</text><text id="lote2">2</text>"#);
+        let after = map(r#"<text id="lote1">1</text><text id="lote3">3</text>"#);
+
+        let diff = diff_lots(&before, &after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added, vec!["lote3"]);
+        assert_eq!(diff.removed, vec!["lote2"]);
+        assert!(diff.renamed.is_empty());
+    }
+
+    #[test]
+    fn colors_the_lots_of_the_new_map() {
+        let before = map(concat!(
+            r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote2" x="20" y="0" width="10" height="10"/>"#,
+        ));
+        let after = map(concat!(
+            r#"<rect id="lote1" x="0" y="0" width="10" height="10"/>"#,
+            r#"<rect id="lote2" x="20" y="50" width="10" height="10"/>"#,
+            r#"<rect id="lote3" x="40" y="0" width="10" height="10"/>"#,
+        ));
+
+        let diff = diff_lots(&before, &after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+        let rendered = render_lot_diff(&after, &diff).expect("the diff should render");
+        let fill_of = |element_id: &str| {
+            let start = rendered.find(&format!("id=\"{}\"", element_id)).unwrap();
+            let element = &rendered[start..start + rendered[start..].find('>').unwrap()];
+            [UNCHANGED_COLOR, ADDED_COLOR, CHANGED_COLOR].into_iter().find(|color| element.contains(color))
+        };
+
+        assert_eq!(fill_of("lote1"), Some(UNCHANGED_COLOR));
+        assert_eq!(fill_of("lote2"), Some(CHANGED_COLOR));
+        assert_eq!(fill_of("lote3"), Some(ADDED_COLOR));
+        assert!(rendered.contains(r#"data-lot="lote2" x="20" y="0" width="10" height="10""#));
+        assert!(rendered.contains("stroke-dasharray"));
+    }
+
+    #[test]
+    fn draws_the_outlines_in_the_user_space_of_the_map() {
+        let before = r#"<svg xmlns="http://www.w3.org/2000/svg" width="1000" height="500" viewBox="100 0 2000 1000"><rect id="lote1" x="200" y="100" width="100" height="50"/><rect id="lote2" x="400" y="100" width="100" height="50"/></svg>"#;
+        let after = r#"<svg xmlns="http://www.w3.org/2000/svg" width="1000" height="500" viewBox="100 0 2000 1000"><rect id="lote2" x="400" y="300" width="100" height="50"/></svg>"#;
+
+        let diff = diff_lots(before, after, DEFAULT_DIFF_TOLERANCE).expect("the maps should parse");
+        let rendered = render_lot_diff(after, &diff).expect("the diff should render");
+
+        let layer = &rendered[rendered.find("<svg id=\"layer-diff\"").expect("the diff layer should be added")..];
+        let layer_root = &layer[..layer.find('>').unwrap()];
+        assert!(layer_root.contains(r#"viewBox="100 0 2000 1000""#));
+        assert!(layer_root.contains(r#"preserveAspectRatio="none""#));
+        assert!(layer.contains(r#"data-lot="lote1" x="200" y="100" width="100" height="50""#));
+        assert!(layer.contains(r#"data-lot="lote2" x="400" y="100" width="100" height="50""#));
+    }
+}
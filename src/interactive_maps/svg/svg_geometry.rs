@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use resvg::usvg::{self, tiny_skia_path::{PathSegment, Point}};
+use super::svg_xml::{invalid_svg, read_attributes};
+
+/// Segments used to approximate each Bézier curve of a lot outline.
+const CURVE_STEPS: usize = 16;
+
+/// Geometry of a lot in SVG user units, in the coordinate system of the root `<svg>` (element
+/// and group transforms applied).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LotGeometry {
+    pub bbox_x: f64,
+    pub bbox_y: f64,
+    pub bbox_width: f64,
+    pub bbox_height: f64,
+    pub centroid_x: f64,
+    pub centroid_y: f64,
+    pub area: f64,
+}
+
+/// Measures the elements listed in `element_ids`. Elements that usvg drops (invisible or
+/// empty) or that aren't shapes are left out of the result.
+pub fn measure_lots(content: &str, element_ids: &[&str]) -> Result<HashMap<String, LotGeometry>, String> {
+    let tree = parse_tree(content)?;
+    // usvg folds the root viewBox into every absolute transform; undoing it gives user units.
+    let to_user = view_box_transform(content, tree.size())?
+        .invert()
+        .unwrap_or_default();
+
+    Ok(element_ids
+        .iter()
+        .filter_map(|&element_id| {
+            let node = tree.node_by_id(element_id)?;
+            measure_node(node, to_user).map(|geometry| (element_id.to_string(), geometry))
+        })
+        .collect())
+}
+
+/// `x`, `y`, width and height of the area of user space the map shows: its viewBox, or the
+/// canvas when it has none. The geometry of [`measure_lots`] is in this coordinate system.
+pub fn user_space(content: &str) -> Result<(f64, f64, f64, f64), String> {
+    match root_viewport(content)?.0 {
+        Some([x, y, width, height]) => Ok((x as f64, y as f64, width as f64, height as f64)),
+        None => {
+            let size = parse_tree(content)?.size();
+            Ok((0.0, 0.0, size.width() as f64, size.height() as f64))
+        }
+    }
+}
+
+fn parse_tree(content: &str) -> Result<usvg::Tree, String> {
+    usvg::Tree::from_str(content, &usvg::Options::default())
+        .map_err(|e| format!("Error al analizar la geometría del SVG: {}", e))
+}
+
+/// Transform from user units to the canvas of `size`, as the root `viewBox` and
+/// `preserveAspectRatio` define it.
+fn view_box_transform(content: &str, size: usvg::Size) -> Result<usvg::Transform, String> {
+    let (view_box, aspect) = root_viewport(content)?;
+    let Some([x, y, width, height]) = view_box else {
+        return Ok(usvg::Transform::identity());
+    };
+
+    let scale_x = size.width() / width;
+    let scale_y = size.height() / height;
+    let mut parts = aspect.split_whitespace().filter(|part| *part != "defer");
+    let align = parts.next().unwrap_or("xMidYMid");
+    if align == "none" {
+        return Ok(usvg::Transform::from_row(scale_x, 0.0, 0.0, scale_y, -x * scale_x, -y * scale_y));
+    }
+
+    let scale = match parts.next() {
+        Some("slice") => scale_x.max(scale_y),
+        _ => scale_x.min(scale_y),
+    };
+    let offset = |axis: &str, free: f32| {
+        if align.contains(&format!("{}Min", axis)) {
+            0.0
+        } else if align.contains(&format!("{}Max", axis)) {
+            free
+        } else {
+            free / 2.0
+        }
+    };
+    let translate_x = offset("x", size.width() - width * scale) - x * scale;
+    let translate_y = offset("Y", size.height() - height * scale) - y * scale;
+
+    Ok(usvg::Transform::from_row(scale, 0.0, 0.0, scale, translate_x, translate_y))
+}
+
+/// `viewBox` (when valid) and `preserveAspectRatio` of the root `<svg>`.
+fn root_viewport(content: &str) -> Result<(Option<[f32; 4]>, String), String> {
+    let mut reader = Reader::from_str(content);
+    let root = loop {
+        match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) | Event::Empty(element) => break element,
+            Event::Eof => return Err("El SVG no tiene un elemento <svg> raíz".to_string()),
+            _ => {}
+        }
+    };
+
+    let attributes = read_attributes(&root, &reader)?;
+    let value = |name: &str| attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+
+    let view_box = value("viewBox").and_then(|view_box| {
+        let numbers = view_box
+            .split([' ', ',', '\t', '\n', '\r'])
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match numbers[..] {
+            [x, y, width, height] if width > 0.0 && height > 0.0 => Some([x, y, width, height]),
+            _ => None,
+        }
+    });
+
+    Ok((view_box, value("preserveAspectRatio").unwrap_or_default().to_string()))
+}
+
+fn measure_node(node: &usvg::Node, to_user: usvg::Transform) -> Option<LotGeometry> {
+    let mut polygons = Vec::new();
+    collect_polygons(node, to_user, &mut polygons);
+    if polygons.is_empty() {
+        return None;
+    }
+
+    // Signed areas, so holes drawn in the opposite direction are subtracted.
+    let mut signed_area = 0.0;
+    let mut moment_x = 0.0;
+    let mut moment_y = 0.0;
+    for polygon in &polygons {
+        let (area, cx, cy) = polygon_area_centroid(polygon);
+        signed_area += area;
+        moment_x += area * cx;
+        moment_y += area * cy;
+    }
+
+    let canvas_bbox = node.abs_bounding_box();
+    let bbox = canvas_bbox.transform(to_user).unwrap_or(canvas_bbox);
+    let (centroid_x, centroid_y) = if signed_area.abs() > f64::EPSILON {
+        (moment_x / signed_area, moment_y / signed_area)
+    } else {
+        (
+            bbox.x() as f64 + bbox.width() as f64 / 2.0,
+            bbox.y() as f64 + bbox.height() as f64 / 2.0,
+        )
+    };
+
+    Some(LotGeometry {
+        bbox_x: bbox.x() as f64,
+        bbox_y: bbox.y() as f64,
+        bbox_width: bbox.width() as f64,
+        bbox_height: bbox.height() as f64,
+        centroid_x,
+        centroid_y,
+        area: signed_area.abs(),
+    })
+}
+
+/// Flattens every path under `node` into closed polygons, mapped from the canvas by `to_user`.
+fn collect_polygons(node: &usvg::Node, to_user: usvg::Transform, polygons: &mut Vec<Vec<(f64, f64)>>) {
+    match node {
+        usvg::Node::Group(group) => {
+            for child in group.children() {
+                collect_polygons(child, to_user, polygons);
+            }
+        }
+        usvg::Node::Path(path) => {
+            let transform = to_user.pre_concat(path.abs_transform());
+            let mut current: Vec<Point> = Vec::new();
+            let mut last = Point::zero();
+
+            for segment in path.data().segments() {
+                match segment {
+                    PathSegment::MoveTo(point) => {
+                        flush_polygon(&mut current, transform, polygons);
+                        current.push(point);
+                        last = point;
+                    }
+                    PathSegment::LineTo(point) => {
+                        current.push(point);
+                        last = point;
+                    }
+                    PathSegment::QuadTo(control, point) => {
+                        for step in 1..=CURVE_STEPS {
+                            let t = step as f32 / CURVE_STEPS as f32;
+                            let mt = 1.0 - t;
+                            current.push(Point::from_xy(
+                                mt * mt * last.x + 2.0 * mt * t * control.x + t * t * point.x,
+                                mt * mt * last.y + 2.0 * mt * t * control.y + t * t * point.y,
+                            ));
+                        }
+                        last = point;
+                    }
+                    PathSegment::CubicTo(control1, control2, point) => {
+                        for step in 1..=CURVE_STEPS {
+                            let t = step as f32 / CURVE_STEPS as f32;
+                            let mt = 1.0 - t;
+                            current.push(Point::from_xy(
+                                mt * mt * mt * last.x
+                                    + 3.0 * mt * mt * t * control1.x
+                                    + 3.0 * mt * t * t * control2.x
+                                    + t * t * t * point.x,
+                                mt * mt * mt * last.y
+                                    + 3.0 * mt * mt * t * control1.y
+                                    + 3.0 * mt * t * t * control2.y
+                                    + t * t * t * point.y,
+                            ));
+                        }
+                        last = point;
+                    }
+                    PathSegment::Close => {
+                        flush_polygon(&mut current, transform, polygons);
+                    }
+                }
+            }
+
+            flush_polygon(&mut current, transform, polygons);
+        }
+        _ => {}
+    }
+}
+
+fn flush_polygon(current: &mut Vec<Point>, transform: usvg::Transform, polygons: &mut Vec<Vec<(f64, f64)>>) {
+    if current.len() >= 3 {
+        transform.map_points(current);
+        polygons.push(current.iter().map(|point| (point.x as f64, point.y as f64)).collect());
+    }
+    current.clear();
+}
+
+/// Shoelace formula: signed area and centroid of a polygon, closing it implicitly.
+fn polygon_area_centroid(points: &[(f64, f64)]) -> (f64, f64, f64) {
+    let mut area = 0.0;
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+
+    for (index, &(x0, y0)) in points.iter().enumerate() {
+        let (x1, y1) = points[(index + 1) % points.len()];
+        let cross = x0 * y1 - x1 * y0;
+        area += cross;
+        cx += (x0 + x1) * cross;
+        cy += (y0 + y1) * cross;
+    }
+
+    area /= 2.0;
+    if area.abs() <= f64::EPSILON {
+        return (0.0, 0.0, 0.0);
+    }
+
+    (area, cx / (6.0 * area), cy / (6.0 * area))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure(content: &str, element_id: &str) -> LotGeometry {
+        measure_lots(content, &[element_id])
+            .expect("the SVG should parse")
+            .remove(element_id)
+            .expect("the lot should be measured")
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn measures_without_a_view_box() {
+        let geometry = measure(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"><rect id="L1" x="10" y="20" width="30" height="40"/></svg>"#,
+            "L1",
+        );
+
+        assert_close(geometry.bbox_x, 10.0);
+        assert_close(geometry.bbox_y, 20.0);
+        assert_close(geometry.bbox_width, 30.0);
+        assert_close(geometry.bbox_height, 40.0);
+        assert_close(geometry.centroid_x, 25.0);
+        assert_close(geometry.centroid_y, 40.0);
+        assert_close(geometry.area, 1200.0);
+    }
+
+    #[test]
+    fn measures_in_user_units_when_the_view_box_scales() {
+        let geometry = measure(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1000" height="500" viewBox="100 50 2000 1000"><rect id="L1" x="200" y="150" width="100" height="50"/></svg>"#,
+            "L1",
+        );
+
+        assert_close(geometry.bbox_x, 200.0);
+        assert_close(geometry.bbox_y, 150.0);
+        assert_close(geometry.bbox_width, 100.0);
+        assert_close(geometry.bbox_height, 50.0);
+        assert_close(geometry.centroid_x, 250.0);
+        assert_close(geometry.centroid_y, 175.0);
+        assert_close(geometry.area, 5000.0);
+    }
+
+    #[test]
+    fn undoes_the_aspect_ratio_alignment() {
+        let content = |aspect: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="100" viewBox="0 0 100 100" preserveAspectRatio="{}"><rect id="L1" x="10" y="10" width="20" height="20"/></svg>"#,
+                aspect
+            )
+        };
+
+        for aspect in ["xMinYMin meet", "xMidYMid", "xMaxYMax slice", "none"] {
+            let geometry = measure(&content(aspect), "L1");
+            assert_close(geometry.bbox_x, 10.0);
+            assert_close(geometry.bbox_y, 10.0);
+            assert_close(geometry.bbox_width, 20.0);
+            assert_close(geometry.area, 400.0);
+        }
+    }
+
+    #[test]
+    fn applies_nested_transforms() {
+        let geometry = measure(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500" viewBox="0 0 1000 1000">
+                <g transform="translate(100 200)">
+                    <g transform="scale(2)">
+                        <path id="L1" transform="translate(5 5)" d="M0 0H10V20H0Z"/>
+                    </g>
+                </g>
+            </svg>"#,
+            "L1",
+        );
+
+        assert_close(geometry.bbox_x, 110.0);
+        assert_close(geometry.bbox_y, 210.0);
+        assert_close(geometry.bbox_width, 20.0);
+        assert_close(geometry.bbox_height, 40.0);
+        assert_close(geometry.centroid_x, 120.0);
+        assert_close(geometry.centroid_y, 230.0);
+        assert_close(geometry.area, 800.0);
+    }
+
+    #[test]
+    fn measures_groups_as_one_lot() {
+        let geometry = measure(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 200 200"><g id="L1"><rect x="0" y="0" width="10" height="10"/><rect x="20" y="0" width="10" height="10"/></g></svg>"#,
+            "L1",
+        );
+
+        assert_close(geometry.bbox_x, 0.0);
+        assert_close(geometry.bbox_width, 30.0);
+        assert_close(geometry.area, 200.0);
+        assert_close(geometry.centroid_x, 15.0);
+    }
+
+    #[test]
+    fn skips_missing_and_invisible_elements() {
+        let geometry = measure_lots(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"><rect id="L1" width="0" height="10"/><text id="L2">1</text></svg>"#,
+            &["L1", "L2", "L3"],
+        )
+        .expect("the SVG should parse");
+
+        assert!(geometry.is_empty());
+    }
+
+    #[test]
+    fn reports_the_user_space_of_the_map() {
+        assert_eq!(
+            user_space(r#"<svg xmlns="http://www.w3.org/2000/svg" width="1000" height="500" viewBox="-10 5 2000 1000"/>"#),
+            Ok((-10.0, 5.0, 2000.0, 1000.0))
+        );
+        assert_eq!(
+            user_space(r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="200" viewBox="0 0 0 10"/>"#),
+            Ok((0.0, 0.0, 300.0, 200.0))
+        );
+    }
+}
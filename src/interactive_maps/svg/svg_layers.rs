@@ -0,0 +1,331 @@
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use super::svg_xml::{build_element, element_name, invalid_svg, read_attributes};
+
+/// Name under which the content of `maps_svg`, the lots, is listed and selected among the
+/// layers of a map. It sits at position 0: overlays with a negative position are drawn below it.
+pub const BASE_LAYER: &str = "lots";
+
+/// A layer of the composed map, bottom first.
+pub enum LayerSource<'a> {
+    /// The children of the map's own `<svg>` root.
+    Base,
+    Overlay { name: &'a str, content: &'a str },
+}
+
+/// Builds a single SVG from the map and the selected layers. The root element of `base` is
+/// kept as the canvas; each layer becomes a group (`Base`) or a nested `<svg>` (`Overlay`)
+/// with `id="layer-<name>"` and `data-layer="<name>"`, in the order of `stack`.
+///
+/// Overlays are scaled from their own viewBox onto the viewBox of the map, so they must be
+/// drawn on the same canvas. Their ids, and the `#id` and `url(#id)` references to them, get
+/// a `<name>__` prefix so they can't collide with the ids of the lots or of other layers.
+pub fn compose_layers(base: &str, stack: &[LayerSource]) -> Result<String, String> {
+    let mut reader = Reader::from_str(base);
+    let mut writer = Writer::new(Vec::with_capacity(base.len()));
+
+    let (root, base_children) = loop {
+        match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) if element_name(&element) == "svg" => {
+                let children = read_children(&mut reader)?;
+                break (element.into_owned(), children);
+            }
+            Event::Empty(element) if element_name(&element) == "svg" => break (element.into_owned(), Vec::new()),
+            Event::Eof => return Err("El SVG no tiene un elemento <svg> raíz".to_string()),
+            event => write(&mut writer, event)?,
+        }
+    };
+
+    let root_attributes = read_attributes(&root, &reader)?;
+    let viewport = viewport_attributes(&root_attributes);
+    let root_name = element_name(&root);
+    write(&mut writer, Event::Start(root))?;
+
+    for source in stack {
+        match source {
+            LayerSource::Base => {
+                write(&mut writer, Event::Start(layer_group(BASE_LAYER)))?;
+                for event in &base_children {
+                    write(&mut writer, event.clone())?;
+                }
+                write(&mut writer, Event::End(BytesEnd::new("g")))?;
+            }
+            LayerSource::Overlay { name, content } => write_overlay(&mut writer, name, content, &viewport)?,
+        }
+    }
+
+    write(&mut writer, Event::End(BytesEnd::new(root_name)))?;
+    String::from_utf8(writer.into_inner()).map_err(|e| format!("Error al generar SVG: {}", e))
+}
+
+fn write_overlay(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    content: &str,
+    viewport: &[(String, String)],
+) -> Result<(), String> {
+    let mut reader = Reader::from_str(content);
+    let id_prefix = format!("{}__", name);
+
+    let root = loop {
+        match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) if element_name(&element) == "svg" => break element,
+            // A layer without children draws nothing.
+            Event::Empty(element) if element_name(&element) == "svg" => return Ok(()),
+            Event::Eof => return Err(format!("La capa {} no tiene un elemento <svg> raíz", name)),
+            _ => {}
+        }
+    };
+
+    let mut attributes = vec![
+        ("id".to_string(), format!("layer-{}", name)),
+        ("data-layer".to_string(), name.to_string()),
+    ];
+    attributes.extend(viewport.iter().cloned());
+    attributes.extend(overlay_root_attributes(&read_attributes(&root, &reader)?));
+    write(writer, Event::Start(build_element("svg", &attributes)))?;
+
+    let mut depth = 0usize;
+    let mut style_depth = None;
+    loop {
+        let event = match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) => {
+                if element_name(&element) == "style" {
+                    style_depth.get_or_insert(depth);
+                }
+                depth += 1;
+                Event::Start(prefix_ids(&element, &reader, &id_prefix)?)
+            }
+            Event::Empty(element) => Event::Empty(prefix_ids(&element, &reader, &id_prefix)?),
+            Event::End(element) => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+                if style_depth == Some(depth) {
+                    style_depth = None;
+                }
+                Event::End(element)
+            }
+            Event::Text(text) if style_depth.is_some() => {
+                let css = text.unescape().map_err(|e| invalid_svg(&reader, e))?;
+                Event::Text(BytesText::new(&prefix_url_references(&css, &id_prefix)).into_owned())
+            }
+            Event::Eof => return Err(format!("La capa {} está incompleta", name)),
+            event => event,
+        };
+        write(writer, event)?;
+    }
+
+    write(writer, Event::End(BytesEnd::new("svg")))
+}
+
+/// Events between the root start tag and its end tag, which is consumed.
+fn read_children(reader: &mut Reader<&[u8]>) -> Result<Vec<Event<'static>>, String> {
+    let mut children = Vec::new();
+    let mut depth = 0usize;
+
+    loop {
+        let event = reader.read_event().map_err(|e| invalid_svg(reader, e))?;
+        match &event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) if depth == 0 => return Ok(children),
+            Event::End(_) => depth -= 1,
+            Event::Eof => return Err("El SVG está incompleto".to_string()),
+            _ => {}
+        }
+        children.push(event.into_owned());
+    }
+}
+
+/// Position and size that make a nested `<svg>` cover the whole viewBox of the map.
+fn viewport_attributes(root_attributes: &[(String, String)]) -> Vec<(String, String)> {
+    let view_box = root_attributes
+        .iter()
+        .find(|(key, _)| key == "viewBox")
+        .map(|(_, value)| value.split([' ', ',']).filter(|part| !part.is_empty()).collect::<Vec<_>>())
+        .filter(|parts| parts.len() == 4);
+
+    let values = match view_box {
+        Some(parts) => [parts[0], parts[1], parts[2], parts[3]].map(str::to_string),
+        None => ["0", "0", "100%", "100%"].map(str::to_string),
+    };
+
+    ["x", "y", "width", "height"]
+        .into_iter()
+        .map(str::to_string)
+        .zip(values)
+        .collect()
+}
+
+/// Attributes of the root of a layer carried over to its nested `<svg>`: the viewBox (or one
+/// built from a unitless width and height), the aspect ratio and the namespace declarations
+/// its children may use.
+fn overlay_root_attributes(attributes: &[(String, String)]) -> Vec<(String, String)> {
+    let value = |name: &str| {
+        attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim())
+    };
+
+    let mut kept: Vec<(String, String)> = attributes
+        .iter()
+        .filter(|(key, _)| key.starts_with("xmlns:") || key == "preserveAspectRatio")
+        .cloned()
+        .collect();
+
+    let view_box = value("viewBox").map(str::to_string).or_else(|| {
+        let width = value("width")?.trim_end_matches("px").parse::<f64>().ok()?;
+        let height = value("height")?.trim_end_matches("px").parse::<f64>().ok()?;
+        Some(format!("0 0 {} {}", width, height))
+    });
+    if let Some(view_box) = view_box {
+        kept.push(("viewBox".to_string(), view_box));
+    }
+
+    kept
+}
+
+fn prefix_ids(element: &BytesStart, reader: &Reader<&[u8]>, id_prefix: &str) -> Result<BytesStart<'static>, String> {
+    let mut attributes = read_attributes(element, reader)?;
+
+    for (key, value) in attributes.iter_mut() {
+        if key == "id" {
+            *value = format!("{}{}", id_prefix, value);
+        } else if (key == "href" || key == "xlink:href") && value.starts_with('#') {
+            *value = format!("#{}{}", id_prefix, &value[1..]);
+        } else if value.contains("url(") {
+            *value = prefix_url_references(value, id_prefix);
+        }
+    }
+
+    Ok(build_element(&element_name(element), &attributes))
+}
+
+/// Prefixes the ids in `url(#id)`, `url('#id')` and `url("#id")` references.
+fn prefix_url_references(value: &str, id_prefix: &str) -> String {
+    ["url(#", "url('#", "url(\"#"]
+        .into_iter()
+        .fold(value.to_string(), |value, reference| {
+            value.replace(reference, &format!("{}{}", reference, id_prefix))
+        })
+}
+
+fn layer_group(name: &str) -> BytesStart<'static> {
+    build_element(
+        "g",
+        &[
+            ("id".to_string(), format!("layer-{}", name)),
+            ("data-layer".to_string(), name.to_string()),
+        ],
+    )
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<(), String> {
+    writer
+        .write_event(event)
+        .map_err(|e| format!("Error al generar SVG: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100"><rect id="lote1" width="10" height="10"/></svg>"#;
+
+    #[test]
+    fn wraps_the_map_in_the_base_layer() {
+        let composed = compose_layers(MAP, &[LayerSource::Base]).expect("the map should compose");
+
+        assert_eq!(
+            composed,
+            r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100"><g id="layer-lots" data-layer="lots"><rect id="lote1" width="10" height="10"/></g></svg>"#
+        );
+    }
+
+    #[test]
+    fn stacks_layers_in_order() {
+        let overlay = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10"><circle r="1"/></svg>"#;
+        let composed = compose_layers(
+            MAP,
+            &[
+                LayerSource::Overlay { name: "below", content: overlay },
+                LayerSource::Base,
+                LayerSource::Overlay { name: "above", content: overlay },
+            ],
+        )
+        .expect("the map should compose");
+
+        let below = composed.find("layer-below").unwrap();
+        let base = composed.find("layer-lots").unwrap();
+        let above = composed.find("layer-above").unwrap();
+        assert!(below < base && base < above);
+    }
+
+    #[test]
+    fn fits_overlays_to_the_view_box_of_the_map() {
+        let composed = compose_layers(
+            MAP,
+            &[LayerSource::Overlay {
+                name: "streets",
+                content: r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="20px" height="10" preserveAspectRatio="none"><path d="M0 0h20"/></svg>"#,
+            }],
+        )
+        .expect("the map should compose");
+
+        assert!(composed.contains(
+            r#"<svg id="layer-streets" data-layer="streets" x="0" y="0" width="200" height="100" xmlns:xlink="http://www.w3.org/1999/xlink" preserveAspectRatio="none" viewBox="0 0 20 10"><path d="M0 0h20"/></svg>"#
+        ));
+    }
+
+    #[test]
+    fn fills_the_canvas_when_the_map_has_no_view_box() {
+        let composed = compose_layers(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"/>"#,
+            &[LayerSource::Base, LayerSource::Overlay { name: "notes", content: r#"<svg viewBox="0 0 2 1"><rect/></svg>"# }],
+        )
+        .expect("the map should compose");
+
+        assert!(composed.contains(r#"x="0" y="0" width="100%" height="100%" viewBox="0 0 2 1""#));
+        assert!(composed.contains(r#"<g id="layer-lots" data-layer="lots"></g>"#));
+    }
+
+    #[test]
+    fn prefixes_the_ids_of_overlays_and_their_references() {
+        let composed = compose_layers(
+            MAP,
+            &[LayerSource::Overlay {
+                name: "trees",
+                content: r##"<svg viewBox="0 0 200 100">
+                    <style>.tree { fill: url(#leaves); }</style>
+                    <defs><linearGradient id="leaves"/><circle id="tree" r="2"/></defs>
+                    <use href="#tree" x="5"/>
+                    <use xlink:href="#tree" x="10" fill="url('#leaves')"/>
+                </svg>"##,
+            }],
+        )
+        .expect("the map should compose");
+
+        assert!(composed.contains(".tree { fill: url(#trees__leaves); }"));
+        assert!(composed.contains(r#"<linearGradient id="trees__leaves"/>"#));
+        assert!(composed.contains(r#"<circle id="trees__tree" r="2"/>"#));
+        assert!(composed.contains(r##"<use href="#trees__tree" x="5"/>"##));
+        assert!(composed.contains(r##"<use xlink:href="#trees__tree" x="10" fill="url(&apos;#trees__leaves&apos;)"/>"##));
+    }
+
+    #[test]
+    fn skips_empty_overlays() {
+        let composed = compose_layers(MAP, &[LayerSource::Overlay { name: "empty", content: "<svg/>" }])
+            .expect("the map should compose");
+
+        assert!(!composed.contains("layer-empty"));
+    }
+
+    #[test]
+    fn rejects_layers_without_an_svg_root() {
+        assert!(compose_layers("<g/>", &[LayerSource::Base]).is_err());
+        assert!(compose_layers(MAP, &[LayerSource::Overlay { name: "broken", content: "<g/>" }]).is_err());
+        assert!(compose_layers(MAP, &[LayerSource::Overlay { name: "broken", content: "<svg><g>" }]).is_err());
+    }
+}
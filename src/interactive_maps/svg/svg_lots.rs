@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use super::svg_xml::invalid_svg;
+
+/// Prefix of the `id` attribute that marks a lot element in our maps (`lote1`, `lote2`, ...).
+pub const LOT_ID_PREFIX: &str = "lote";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LotElement {
+    pub element_id: String,
+    pub lot_number: String,
+}
+
+/// Returns the lot number of an element id following the `loteN` convention.
+pub fn lot_number_from_id(element_id: &str) -> Option<&str> {
+    let number = element_id.strip_prefix(LOT_ID_PREFIX)?;
+    if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+        Some(number)
+    } else {
+        None
+    }
+}
+
+/// Walks the SVG markup and collects every lot element in document order.
+pub fn extract_lots(content: &str) -> Result<Vec<LotElement>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut seen = HashSet::new();
+    let mut lots = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) | Ok(Event::Empty(element)) => {
+                let id = element
+                    .try_get_attribute("id")
+                    .map_err(|e| invalid_svg(&reader, e))?;
+
+                if let Some(id) = id {
+                    let element_id = id
+                        .unescape_value()
+                        .map_err(|e| invalid_svg(&reader, e))?
+                        .into_owned();
+
+                    if let Some(lot_number) = lot_number_from_id(&element_id) {
+                        if seen.insert(element_id.clone()) {
+                            lots.push(LotElement {
+                                lot_number: lot_number.to_string(),
+                                element_id,
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("SVG inválido en la posición {}: {}", reader.error_position(), e)),
+            _ => {}
+        }
+    }
+
+    Ok(lots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(element_id: &str, lot_number: &str) -> LotElement {
+        LotElement {
+            element_id: element_id.to_string(),
+            lot_number: lot_number.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_the_lot_number_of_lot_ids() {
+        assert_eq!(lot_number_from_id("lote1"), Some("1"));
+        assert_eq!(lot_number_from_id("lote042"), Some("042"));
+        assert_eq!(lot_number_from_id("lote"), None);
+        assert_eq!(lot_number_from_id("lote1a"), None);
+        assert_eq!(lot_number_from_id("lote-1"), None);
+        assert_eq!(lot_number_from_id("Lote1"), None);
+        assert_eq!(lot_number_from_id("manzana1"), None);
+    }
+
+    #[test]
+    fn extracts_lots_in_document_order() {
+        let lots = extract_lots(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <g id="manzana1">
+                    <path id="lote2" d="M0 0h10v10H0z"/>
+                    <rect id="lote1" width="10" height="10"/>
+                </g>
+                <g id="lote10"><rect width="10" height="10"/></g>
+                <text id="label1">1</text>
+            </svg>"#,
+        )
+        .expect("the SVG should parse");
+
+        assert_eq!(lots, vec![lot("lote2", "2"), lot("lote1", "1"), lot("lote10", "10")]);
+    }
+
+    #[test]
+    fn keeps_the_first_element_of_a_repeated_id() {
+        let lots = extract_lots(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="lote1"/><rect id="lote2"/><rect id="lote1"/></svg>"#,
+        )
+        .expect("the SVG should parse");
+
+        assert_eq!(lots, vec![lot("lote1", "1"), lot("lote2", "2")]);
+    }
+
+    #[test]
+    fn unescapes_ids() {
+        let lots = extract_lots(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="&#108;ote3"/></svg>"#)
+            .expect("the SVG should parse");
+
+        assert_eq!(lots, vec![lot("lote3", "3")]);
+    }
+
+    #[test]
+    fn rejects_malformed_markup() {
+        assert!(extract_lots(r#"<svg><rect id="lote1"></svg>"#).is_err());
+        assert!(extract_lots(r#"<svg><rect id="lote1 /></svg>"#).is_err());
+    }
+}
@@ -0,0 +1,559 @@
+use std::collections::{BTreeMap, HashSet};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use super::svg_sanitizer::EDITOR_NAMESPACE_PREFIXES;
+use super::svg_xml::{build_element, element_name, invalid_svg, read_attributes};
+
+/// Decimals kept in path data. Our maps use coordinates in the thousands, so a thousandth of
+/// a unit is far below what can be seen.
+pub const PATH_PRECISION: usize = 3;
+
+/// Elements that only render when something references them by id.
+const DEFINITION_ELEMENTS: &[&str] = &[
+    "marker", "linearGradient", "radialGradient", "pattern", "clipPath", "mask", "filter", "symbol",
+];
+
+/// Elements whose whitespace-only text is content rather than indentation.
+const TEXT_ELEMENTS: &[&str] = &["text", "tspan", "textPath", "title", "desc", "style"];
+
+const PATH_COMMANDS: &[u8] = b"MmZzLlHhVvCcSsQqTtAa";
+
+/// What the optimizer removed from a map and how much smaller it got.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    pub original_bytes: usize,
+    pub optimized_bytes: usize,
+    pub removed_elements: BTreeMap<String, usize>,
+    pub removed_attributes: usize,
+}
+
+impl OptimizationReport {
+    fn element_removed(&mut self, name: &str) {
+        *self.removed_elements.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Shrinks a sanitized map: drops `<metadata>`, editor namespaces, comments, definitions no
+/// one references and `<defs>` left empty, rounds path data to `PATH_PRECISION` decimals and
+/// removes indentation. Rendered elements keep their ids, so the lots and anything else the
+/// frontend looks up keep working; ids are never renamed. Unreferenced definitions and
+/// `<metadata>` are dropped with whatever ids they carry.
+pub fn optimize_svg(content: &str) -> Result<(String, OptimizationReport), String> {
+    let referenced = referenced_ids(content)?;
+    let mut reader = Reader::from_str(content);
+    let mut writer = Writer::new(Vec::with_capacity(content.len()));
+    let mut report = OptimizationReport {
+        original_bytes: content.len(),
+        ..OptimizationReport::default()
+    };
+
+    // Depth inside a removed element; its whole subtree is skipped.
+    let mut skipped_depth = 0usize;
+    // Depth inside text content, where whitespace is kept.
+    let mut text_depth = 0usize;
+    // An outermost `<defs>` is held back until we know it still has children.
+    let mut defs: Option<DefsBuffer> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| invalid_svg(&reader, e))?;
+
+        if skipped_depth > 0 {
+            match event {
+                Event::Start(_) => skipped_depth += 1,
+                Event::End(_) => skipped_depth -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        let event = match event {
+            Event::Start(element) => {
+                let name = element_name(&element);
+                if is_removable(&name, &element, &reader, &referenced)? {
+                    report.element_removed(&name);
+                    skipped_depth = 1;
+                    continue;
+                }
+                if TEXT_ELEMENTS.contains(&name.as_str()) {
+                    text_depth += 1;
+                }
+                let element = optimize_attributes(&element, &reader, &mut report)?;
+                if name == "defs" && defs.is_none() {
+                    defs = Some(DefsBuffer::new(element));
+                    continue;
+                }
+                Event::Start(element)
+            }
+            Event::Empty(element) => {
+                let name = element_name(&element);
+                if is_removable(&name, &element, &reader, &referenced)? {
+                    report.element_removed(&name);
+                    continue;
+                }
+                if name == "defs" && defs.is_none() {
+                    report.element_removed(&name);
+                    continue;
+                }
+                Event::Empty(optimize_attributes(&element, &reader, &mut report)?)
+            }
+            Event::End(end) => {
+                let name = String::from_utf8_lossy(end.name().as_ref()).into_owned();
+                if TEXT_ELEMENTS.contains(&name.as_str()) {
+                    text_depth = text_depth.saturating_sub(1);
+                }
+                if let Some(buffer) = defs.as_mut() {
+                    if buffer.depth == 0 {
+                        let buffer = defs.take().expect("defs buffer");
+                        if buffer.has_children {
+                            for event in buffer.events {
+                                write(&mut writer, event)?;
+                            }
+                            write(&mut writer, Event::End(end))?;
+                        } else {
+                            report.element_removed("defs");
+                        }
+                        continue;
+                    }
+                }
+                Event::End(end)
+            }
+            Event::Text(text) if text_depth == 0 && text.iter().all(u8::is_ascii_whitespace) => continue,
+            Event::Comment(_) => continue,
+            Event::Eof => break,
+            other => other,
+        };
+
+        match defs.as_mut() {
+            Some(buffer) => buffer.push(event.into_owned()),
+            None => write(&mut writer, event)?,
+        }
+    }
+
+    let optimized = String::from_utf8(writer.into_inner())
+        .map_err(|e| format!("Error al generar SVG: {}", e))?;
+    report.optimized_bytes = optimized.len();
+
+    Ok((optimized, report))
+}
+
+/// Events of an outermost `<defs>`, held until its end tag.
+struct DefsBuffer {
+    events: Vec<Event<'static>>,
+    depth: usize,
+    has_children: bool,
+}
+
+impl DefsBuffer {
+    fn new(start: BytesStart<'static>) -> Self {
+        Self {
+            events: vec![Event::Start(start)],
+            depth: 0,
+            has_children: false,
+        }
+    }
+
+    fn push(&mut self, event: Event<'static>) {
+        match &event {
+            Event::Start(_) => {
+                self.depth += 1;
+                self.has_children = true;
+            }
+            Event::Empty(_) => self.has_children = true,
+            Event::End(_) => self.depth -= 1,
+            Event::Text(_) | Event::CData(_) => self.has_children = true,
+            _ => {}
+        }
+        self.events.push(event);
+    }
+}
+
+fn is_removable(
+    name: &str,
+    element: &BytesStart,
+    reader: &Reader<&[u8]>,
+    referenced: &HashSet<String>,
+) -> Result<bool, String> {
+    if name == "metadata" || is_editor_name(name) {
+        return Ok(true);
+    }
+    if !DEFINITION_ELEMENTS.contains(&name) {
+        return Ok(false);
+    }
+
+    let id = read_attributes(element, reader)?
+        .into_iter()
+        .find(|(key, _)| key == "id")
+        .map(|(_, id)| id);
+    Ok(id.is_none_or(|id| !referenced.contains(&id)))
+}
+
+/// Elements and attributes in an editor namespace, and the declarations of those namespaces.
+fn is_editor_name(name: &str) -> bool {
+    let prefix = match name.strip_prefix("xmlns:") {
+        Some(declared) => declared,
+        None => match name.split_once(':') {
+            Some((prefix, _)) => prefix,
+            None => return false,
+        },
+    };
+    EDITOR_NAMESPACE_PREFIXES.contains(&prefix)
+}
+
+fn optimize_attributes(
+    element: &BytesStart,
+    reader: &Reader<&[u8]>,
+    report: &mut OptimizationReport,
+) -> Result<BytesStart<'static>, String> {
+    let name = element_name(element);
+    let attributes = read_attributes(element, reader)?;
+    let mut kept = Vec::with_capacity(attributes.len());
+
+    for (key, value) in attributes {
+        if is_editor_name(&key) {
+            report.removed_attributes += 1;
+            continue;
+        }
+
+        let value = match key.as_str() {
+            "d" if name == "path" => optimize_numbers(&value).unwrap_or(value),
+            "points" if !value.bytes().any(|b| b.is_ascii_alphabetic() && b != b'e' && b != b'E') => {
+                optimize_numbers(&value).unwrap_or(value)
+            }
+            "style" => compact_style(&value),
+            _ => value,
+        };
+        kept.push((key, value));
+    }
+
+    Ok(build_element(&name, &kept))
+}
+
+/// Rewrites path data (or a list of points) with rounded numbers and the fewest separators.
+/// Returns `None` for data it doesn't understand, which is then kept as is.
+fn optimize_numbers(data: &str) -> Option<String> {
+    let bytes = data.as_bytes();
+    let mut optimized = String::with_capacity(data.len());
+    let mut command = b' ';
+    let mut parameter = 0usize;
+    let mut after_number = false;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if byte.is_ascii_whitespace() || byte == b',' {
+            index += 1;
+            continue;
+        }
+
+        if byte.is_ascii_alphabetic() {
+            if !PATH_COMMANDS.contains(&byte) {
+                return None;
+            }
+            optimized.push(byte as char);
+            command = byte;
+            parameter = 0;
+            after_number = false;
+            index += 1;
+            continue;
+        }
+
+        // Arc flags may be written without separators (`a5 5 0 01 10 10`).
+        let is_arc_flag = matches!(command, b'A' | b'a') && matches!(parameter % 7, 3 | 4);
+        let (number, end) = if is_arc_flag {
+            if byte != b'0' && byte != b'1' {
+                return None;
+            }
+            ((byte as char).to_string(), index + 1)
+        } else {
+            let end = number_end(bytes, index)?;
+            (format_number(data[index..end].parse().ok()?), end)
+        };
+
+        if after_number && !number.starts_with('-') {
+            optimized.push(' ');
+        }
+        optimized.push_str(&number);
+        after_number = true;
+        parameter += 1;
+        index = end;
+    }
+
+    Some(optimized)
+}
+
+/// End of the number starting at `start`: sign, digits, fraction and exponent.
+fn number_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let digits = |mut index: usize| {
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+        index
+    };
+
+    let mut index = start;
+    if matches!(bytes.get(index), Some(b'+' | b'-')) {
+        index += 1;
+    }
+    let integer_end = digits(index);
+    let mut end = integer_end;
+    if bytes.get(end) == Some(&b'.') {
+        end = digits(end + 1);
+    }
+    if end == index || (end == integer_end + 1 && integer_end == index) {
+        return None;
+    }
+
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exponent = end + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        let exponent_end = digits(exponent);
+        if exponent_end > exponent {
+            end = exponent_end;
+        }
+    }
+
+    Some(end)
+}
+
+/// `12.50000` gives `12.5`, `0.25` gives `.25` and `-0.0001` gives `0`.
+fn format_number(value: f64) -> String {
+    let formatted = format!("{:.*}", PATH_PRECISION, value);
+    let formatted = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        formatted.as_str()
+    };
+
+    match formatted {
+        "-0" | "" => "0".to_string(),
+        _ => match formatted.strip_prefix("0.") {
+            Some(fraction) => format!(".{}", fraction),
+            None => match formatted.strip_prefix("-0.") {
+                Some(fraction) => format!("-.{}", fraction),
+                None => formatted.to_string(),
+            },
+        },
+    }
+}
+
+fn compact_style(style: &str) -> String {
+    style
+        .split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            Some(format!("{}:{}", property.trim(), value.trim()))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Ids referenced from `url(#id)` values, `href="#id"` attributes and style sheets.
+fn referenced_ids(content: &str) -> Result<HashSet<String>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut referenced = HashSet::new();
+
+    loop {
+        match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) | Event::Empty(element) => {
+                for (key, value) in read_attributes(&element, &reader)? {
+                    if key == "href" || key.ends_with(":href") {
+                        if let Some(id) = value.trim().strip_prefix('#') {
+                            referenced.insert(id.to_string());
+                        }
+                    }
+                    collect_url_references(&value, &mut referenced);
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| invalid_svg(&reader, e))?;
+                collect_url_references(&text, &mut referenced);
+            }
+            Event::CData(data) => collect_url_references(&String::from_utf8_lossy(&data), &mut referenced),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(referenced)
+}
+
+fn collect_url_references(value: &str, referenced: &mut HashSet<String>) {
+    for (index, _) in value.match_indices("url(") {
+        let target = value[index + 4..].trim_start_matches([' ', '"', '\'']);
+        if let Some(target) = target.strip_prefix('#') {
+            let id: String = target
+                .chars()
+                .take_while(|c| !matches!(c, ')' | '"' | '\'') && !c.is_whitespace())
+                .collect();
+            referenced.insert(id);
+        }
+    }
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<(), String> {
+    writer
+        .write_event(event)
+        .map_err(|e| format!("Error al generar SVG: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive_maps::svg::svg_geometry::measure_lots;
+    use crate::interactive_maps::svg::svg_lots::extract_lots;
+
+    fn optimize(content: &str) -> String {
+        optimize_svg(content).expect("the SVG should parse").0
+    }
+
+    #[test]
+    fn formats_numbers_with_the_path_precision() {
+        assert_eq!(format_number(12.5), "12.5");
+        assert_eq!(format_number(12.0), "12");
+        assert_eq!(format_number(0.25), ".25");
+        assert_eq!(format_number(-0.25), "-.25");
+        assert_eq!(format_number(1.23456), "1.235");
+        assert_eq!(format_number(-1.23449), "-1.234");
+        assert_eq!(format_number(-0.0001), "0");
+        assert_eq!(format_number(1999.9999), "2000");
+    }
+
+    #[test]
+    fn rounds_path_data_and_drops_separators() {
+        assert_eq!(
+            optimize_numbers("M 10.12345,20.00001 L -0.5 -0.25 h 3.0004 Z").as_deref(),
+            Some("M10.123 20L-.5-.25h3Z")
+        );
+    }
+
+    #[test]
+    fn reads_implicit_separators() {
+        assert_eq!(optimize_numbers("M1.5.5-2-3l.25.75").as_deref(), Some("M1.5 .5-2-3l.25 .75"));
+        assert_eq!(optimize_numbers("M0,0 10,0 10,10").as_deref(), Some("M0 0 10 0 10 10"));
+    }
+
+    #[test]
+    fn reads_exponents() {
+        assert_eq!(optimize_numbers("M1e2 2.5E-1 L-1.5e+1,3e0").as_deref(), Some("M100 .25L-15 3"));
+    }
+
+    #[test]
+    fn keeps_arc_flags() {
+        assert_eq!(optimize_numbers("a5 5 0 01 10.0004 10").as_deref(), Some("a5 5 0 0 1 10 10"));
+        assert_eq!(
+            optimize_numbers("M0 0A5,5 30 1,0 10,20 5 5 0 1 1 0 0").as_deref(),
+            Some("M0 0A5 5 30 1 0 10 20 5 5 0 1 1 0 0")
+        );
+        assert_eq!(optimize_numbers("a5 5 0 2 1 10 10"), None);
+    }
+
+    #[test]
+    fn leaves_data_it_does_not_understand() {
+        assert_eq!(optimize_numbers("M1 2 X3"), None);
+        assert_eq!(optimize_numbers("M1 . 2"), None);
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M1 2 X3"/></svg>"#;
+        assert_eq!(optimize(svg), svg);
+    }
+
+    #[test]
+    fn rounds_points_but_not_other_attributes() {
+        assert_eq!(
+            optimize(r#"<svg xmlns="http://www.w3.org/2000/svg"><polygon points="0.0001,1.23456 2,3" x="1.23456"/></svg>"#),
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><polygon points="0 1.235 2 3" x="1.23456"/></svg>"#
+        );
+    }
+
+    #[test]
+    fn removes_unreferenced_definitions_metadata_and_editor_data() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd">
+  <!-- Creado con Inkscape -->
+  <metadata><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"/></metadata>
+  <sodipodi:namedview inkscape:zoom="1"/>
+  <defs>
+    <linearGradient id="usado"><stop offset="0"/></linearGradient>
+    <linearGradient id="huerfano"><stop offset="0"/></linearGradient>
+    <clipPath id="recorte"><rect width="1" height="1"/></clipPath>
+  </defs>
+  <defs><marker id="flecha"/></defs>
+  <g id="lotes" inkscape:label="Lotes" style=" fill : url(#usado) ; stroke:none ">
+    <path id="lote1" d="M 0,0 H 10.00001 V 10 Z" clip-path="url(#recorte)"/>
+  </g>
+</svg>"##;
+
+        let (optimized, report) = optimize_svg(svg).unwrap();
+        assert_eq!(
+            optimized,
+            r##"<svg xmlns="http://www.w3.org/2000/svg"><defs><linearGradient id="usado"><stop offset="0"/></linearGradient><clipPath id="recorte"><rect width="1" height="1"/></clipPath></defs><g id="lotes" style="fill:url(#usado);stroke:none"><path id="lote1" d="M0 0H10V10Z" clip-path="url(#recorte)"/></g></svg>"##
+        );
+        assert_eq!(report.removed_elements.get("linearGradient"), Some(&1));
+        assert_eq!(report.removed_elements.get("marker"), Some(&1));
+        assert_eq!(report.removed_elements.get("defs"), Some(&1));
+        assert_eq!(report.removed_elements.get("metadata"), Some(&1));
+        assert_eq!(report.removed_elements.get("sodipodi:namedview"), Some(&1));
+        assert_eq!(report.removed_attributes, 3);
+        assert!(report.optimized_bytes < report.original_bytes);
+    }
+
+    #[test]
+    fn keeps_definitions_referenced_from_style_sheets_and_links() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg"><defs><symbol id="arbol"/><pattern id="rayas"/></defs><style>.lote{fill:url(#rayas)}</style><use href="#arbol"/></svg>"##;
+        assert_eq!(optimize(svg), svg);
+    }
+
+    #[test]
+    fn keeps_ids_of_rendered_elements_and_drops_unreferenced_definitions() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><defs id="definiciones"><linearGradient id="sin-uso"/></defs><metadata id="datos"/><g><g class="manzana"><rect id="lote7" width="10" height="10"/></g></g></svg>"#;
+
+        assert_eq!(
+            optimize(svg),
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><g><g class="manzana"><rect id="lote7" width="10" height="10"/></g></g></svg>"#
+        );
+    }
+
+    #[test]
+    fn keeps_whitespace_inside_text() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><text> <tspan>Lote</tspan> <tspan>12</tspan></text></svg>"#;
+        assert_eq!(optimize(svg), svg);
+    }
+
+    #[test]
+    fn keeps_lot_ids_and_geometry() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2000 1000" width="1000" height="500">
+  <g id="manzana1" transform="translate(100.00004 50)">
+    <path id="lote1" d="M 0.0001,0 L 300.12345,0 L 300.12345,200.98765 L 0,200.98765 Z"/>
+    <path id="lote2" d="m 400,0 c 50.55555,0 100,50 100,100 s -50,100 -100,100 z"/>
+    <path id="lote3" d="M 700,100 a 50.00049,50 0 1 0 60,0 z"/>
+    <polygon id="lote4" points="900,0 1000.0004,0 1000,80.4444"/>
+  </g>
+</svg>"#;
+        let optimized = optimize(svg);
+
+        let ids = |content: &str| extract_lots(content).unwrap().into_iter().map(|lot| lot.element_id).collect::<Vec<_>>();
+        assert_eq!(ids(svg), ["lote1", "lote2", "lote3", "lote4"]);
+        assert_eq!(ids(&optimized), ids(svg));
+
+        let lot_ids = ["lote1", "lote2", "lote3", "lote4"];
+        let before = measure_lots(svg, &lot_ids).unwrap();
+        let after = measure_lots(&optimized, &lot_ids).unwrap();
+        assert_eq!(before.len(), 4);
+        for id in lot_ids {
+            let (before, after) = (before[id], after[id]);
+            for (a, b) in [
+                (before.bbox_x, after.bbox_x),
+                (before.bbox_y, after.bbox_y),
+                (before.bbox_width, after.bbox_width),
+                (before.bbox_height, after.bbox_height),
+                (before.centroid_x, after.centroid_x),
+                (before.centroid_y, after.centroid_y),
+            ] {
+                assert!((a - b).abs() < 0.01, "{}: {} != {}", id, a, b);
+            }
+            assert!((before.area - after.area).abs() / before.area < 1e-4, "{}", id);
+        }
+    }
+}
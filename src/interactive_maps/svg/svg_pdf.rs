@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use svg2pdf::usvg::{self, fontdb};
+use crate::interactive_maps::dto::svg_dto::StatusLegendEntry;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+const HEADER_HEIGHT: f32 = 48.0;
+const LEGEND_COLUMNS: usize = 3;
+const LEGEND_ROW_HEIGHT: f32 = 16.0;
+const LEGEND_SWATCH: f32 = 10.0;
+
+/// Installed families used for generic and unknown font names. Inkscape writes `Sans`, which
+/// only fontconfig resolves; usvg falls back to the serif family for it.
+const FALLBACK_FAMILIES: [&str; 4] = ["DejaVu Sans", "Liberation Sans", "Arial", "Helvetica"];
+
+const REGULAR_FONT: Name = Name(b"F1");
+const BOLD_FONT: Name = Name(b"F2");
+const MAP_XOBJECT: Name = Name(b"M1");
+
+/// Texts printed around the map in the exported sheet.
+pub struct PdfSheet<'a> {
+    pub title: &'a str,
+    pub development: &'a str,
+    pub generated_at: &'a str,
+    pub legend: &'a [StatusLegendEntry],
+}
+
+/// Lays out a single A4 page with the map, the development, the generation timestamp
+/// and the legend with the number of lots per status. The page is landscape when the map is
+/// wider than tall. Labels use the standard Helvetica fonts, so nothing is embedded for them;
+/// text inside the map is embedded from the system fonts.
+pub fn render_pdf(content: &str, sheet: &PdfSheet) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: system_fonts(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(content, &options)
+        .map_err(|e| format!("Error al leer SVG: {}", e))?;
+    let (map_chunk, map_ref) = svg2pdf::to_chunk(&tree, svg2pdf::ConversionOptions::default())
+        .map_err(|e| format!("Error al convertir SVG a PDF: {}", e))?;
+
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let page_tree_id = alloc.bump();
+    let page_id = alloc.bump();
+    let regular_font_id = alloc.bump();
+    let bold_font_id = alloc.bump();
+    let content_id = alloc.bump();
+
+    let mut renumbered = HashMap::new();
+    let map_chunk = map_chunk.renumber(|old| *renumbered.entry(old).or_insert_with(|| alloc.bump()));
+    let map_id = renumbered[&map_ref];
+
+    let map_size = tree.size();
+    let (page_width, page_height) = if map_size.width() > map_size.height() {
+        (PAGE_HEIGHT, PAGE_WIDTH)
+    } else {
+        (PAGE_WIDTH, PAGE_HEIGHT)
+    };
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids([page_id]).count(1);
+
+    let mut page = pdf.page(page_id);
+    page.media_box(Rect::new(0.0, 0.0, page_width, page_height));
+    page.parent(page_tree_id);
+    page.contents(content_id);
+    let mut resources = page.resources();
+    resources.x_objects().pair(MAP_XOBJECT, map_id);
+    resources
+        .fonts()
+        .pair(REGULAR_FONT, regular_font_id)
+        .pair(BOLD_FONT, bold_font_id);
+    resources.finish();
+    page.finish();
+
+    pdf.type1_font(regular_font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_font_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let mut page_content = Content::new();
+    let top = page_height - MARGIN;
+
+    draw_text(&mut page_content, BOLD_FONT, 16.0, MARGIN, top - 16.0, sheet.title);
+    draw_text(
+        &mut page_content,
+        REGULAR_FONT,
+        10.0,
+        MARGIN,
+        top - 32.0,
+        &format!("Desarrollo: {}    Generado: {}", sheet.development, sheet.generated_at),
+    );
+
+    let legend_rows = sheet.legend.len().div_ceil(LEGEND_COLUMNS);
+    let legend_height = LEGEND_ROW_HEIGHT * (legend_rows as f32 + 1.0);
+    draw_legend(&mut page_content, sheet.legend, page_width, MARGIN + legend_height);
+
+    let box_width = page_width - 2.0 * MARGIN;
+    let box_bottom = MARGIN + legend_height + 12.0;
+    let box_height = top - HEADER_HEIGHT - box_bottom;
+    let scale = (box_width / map_size.width()).min(box_height / map_size.height());
+    let map_width = map_size.width() * scale;
+    let map_height = map_size.height() * scale;
+
+    page_content
+        .save_state()
+        .transform([
+            map_width,
+            0.0,
+            0.0,
+            map_height,
+            MARGIN + (box_width - map_width) / 2.0,
+            box_bottom + (box_height - map_height) / 2.0,
+        ])
+        .x_object(MAP_XOBJECT)
+        .restore_state();
+
+    pdf.stream(content_id, &page_content.finish());
+    pdf.extend(&map_chunk);
+
+    Ok(pdf.finish())
+}
+
+/// Draws the legend below the map, starting at `top`: a "Total de lotes" line and then the
+/// statuses in `LEGEND_COLUMNS` columns.
+fn draw_legend(content: &mut Content, legend: &[StatusLegendEntry], page_width: f32, top: f32) {
+    let total: usize = legend.iter().map(|entry| entry.count).sum();
+    draw_text(content, BOLD_FONT, 10.0, MARGIN, top - 10.0, &format!("Total de lotes: {}", total));
+
+    let column_width = (page_width - 2.0 * MARGIN) / LEGEND_COLUMNS as f32;
+    for (index, entry) in legend.iter().enumerate() {
+        let x = MARGIN + column_width * (index % LEGEND_COLUMNS) as f32;
+        let y = top - LEGEND_ROW_HEIGHT * (index / LEGEND_COLUMNS + 1) as f32 - 10.0;
+        let (r, g, b) = parse_hex_color(&entry.color);
+
+        content
+            .save_state()
+            .set_fill_rgb(r, g, b)
+            .set_stroke_gray(0.3)
+            .set_line_width(0.5)
+            .rect(x, y - 1.0, LEGEND_SWATCH, LEGEND_SWATCH)
+            .fill_nonzero_and_stroke()
+            .restore_state();
+
+        draw_text(
+            content,
+            REGULAR_FONT,
+            9.0,
+            x + LEGEND_SWATCH + 6.0,
+            y,
+            &format!("{} ({})", entry.name, entry.count),
+        );
+    }
+}
+
+fn draw_text(content: &mut Content, font: Name, size: f32, x: f32, y: f32, text: &str) {
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(text)))
+        .end_text();
+}
+
+/// Encodes text for the standard fonts. WinAnsi matches Latin-1 for accented letters, which
+/// covers Spanish; anything else is replaced with `?`.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// `#RRGGBB` or `#RGB` to PDF color components. Invalid values fall back to the gray used for
+/// unknown statuses.
+fn parse_hex_color(color: &str) -> (f32, f32, f32) {
+    let hex = color.trim().trim_start_matches('#');
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        _ => hex.to_string(),
+    };
+
+    match u32::from_str_radix(&hex, 16) {
+        Ok(value) if hex.len() == 6 => (
+            ((value >> 16) & 0xFF) as f32 / 255.0,
+            ((value >> 8) & 0xFF) as f32 / 255.0,
+            (value & 0xFF) as f32 / 255.0,
+        ),
+        _ => (0.8, 0.8, 0.8),
+    }
+}
+
+/// System fonts are scanned once and shared by every export.
+fn system_fonts() -> Arc<fontdb::Database> {
+    static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut database = fontdb::Database::new();
+            database.load_system_fonts();
+
+            let fallback = FALLBACK_FAMILIES.into_iter().find(|family| {
+                database
+                    .faces()
+                    .any(|face| face.families.iter().any(|(name, _)| name == family))
+            });
+            if let Some(family) = fallback {
+                database.set_serif_family(family);
+                database.set_sans_serif_family(family);
+            }
+
+            Arc::new(database)
+        })
+        .clone()
+}
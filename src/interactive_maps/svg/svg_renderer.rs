@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use super::svg_xml::{build_element, element_name, invalid_svg, read_attributes, set_style_property};
+
+/// Sets the fill of every element whose id appears in `fills` and copies the rest of the
+/// markup through untouched. The color goes into both the `fill` attribute and the inline
+/// style, since our maps declare `fill:none` in `style`, which wins over the attribute.
+pub fn apply_fills(content: &str, fills: &HashMap<String, String>) -> Result<String, String> {
+    let mut reader = Reader::from_str(content);
+    let mut writer = Writer::new(Vec::with_capacity(content.len()));
+
+    loop {
+        let event = match reader.read_event().map_err(|e| invalid_svg(&reader, e))? {
+            Event::Start(element) => Event::Start(fill_element(element, &reader, fills)?),
+            Event::Empty(element) => Event::Empty(fill_element(element, &reader, fills)?),
+            Event::Eof => break,
+            other => other,
+        };
+
+        writer
+            .write_event(event)
+            .map_err(|e| format!("Error al generar SVG: {}", e))?;
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(|e| format!("Error al generar SVG: {}", e))
+}
+
+fn fill_element<'a>(
+    element: BytesStart<'a>,
+    reader: &Reader<&[u8]>,
+    fills: &HashMap<String, String>,
+) -> Result<BytesStart<'a>, String> {
+    let mut attributes = read_attributes(&element, reader)?;
+    let color = attributes
+        .iter()
+        .find(|(key, _)| key == "id")
+        .and_then(|(_, id)| fills.get(id));
+
+    let color = match color {
+        Some(color) => color.clone(),
+        None => return Ok(element),
+    };
+
+    attributes.retain(|(key, _)| key != "fill");
+    attributes.push(("fill".to_string(), color.clone()));
+
+    match attributes.iter_mut().find(|(key, _)| key == "style") {
+        Some((_, style)) => *style = set_style_property(style, "fill", &color),
+        None => attributes.push(("style".to_string(), format!("fill:{}", color))),
+    }
+
+    Ok(build_element(&element_name(&element), &attributes))
+}
@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::name::{Namespace, QName, ResolveResult};
+use quick_xml::{NsReader, Writer};
+use serde::{Deserialize, Serialize};
+use super::svg_xml::{build_element, element_name, invalid_svg, read_attributes};
+
+/// Static SVG elements a map may contain. Anything else (`script`, `foreignObject`,
+/// animation elements, embedded HTML...) is dropped together with its children.
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "svg", "g", "defs", "symbol", "use", "title", "desc", "metadata", "switch",
+    "path", "rect", "circle", "ellipse", "line", "polyline", "polygon",
+    "text", "tspan", "textPath", "marker", "clipPath", "mask", "pattern",
+    "linearGradient", "radialGradient", "stop", "image", "style", "a",
+    "filter", "feBlend", "feColorMatrix", "feComponentTransfer", "feComposite",
+    "feConvolveMatrix", "feDiffuseLighting", "feDisplacementMap", "feDistantLight",
+    "feDropShadow", "feFlood", "feFuncA", "feFuncB", "feFuncG", "feFuncR",
+    "feGaussianBlur", "feMerge", "feMergeNode", "feMorphology", "feOffset",
+    "fePointLight", "feSpecularLighting", "feSpotLight", "feTile", "feTurbulence",
+];
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+/// Namespaces written by drawing editors (Inkscape, Adobe, RDF metadata). Their elements
+/// carry no behavior, so they are kept and left to the optimizer. Elements are matched by
+/// namespace URI: the prefix is chosen by the document and proves nothing.
+const EDITOR_NAMESPACES: &[&str] = &[
+    "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd",
+    "http://www.inkscape.org/namespaces/inkscape",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+    "http://creativecommons.org/ns#",
+    "http://web.resource.org/cc/",
+    "http://purl.org/dc/elements/1.1/",
+    "http://ns.adobe.com/AdobeIllustrator/10.0/",
+    "http://ns.adobe.com/Extensibility/1.0/",
+];
+
+/// Prefixes the editors use for [`EDITOR_NAMESPACES`], for the optimizer to strip.
+pub const EDITOR_NAMESPACE_PREFIXES: &[&str] = &["sodipodi", "inkscape", "rdf", "cc", "dc", "i", "x"];
+
+/// Embedded images are the only non-local references a map may keep.
+const ALLOWED_DATA_URIS: &[&str] = &["data:image/png", "data:image/jpeg", "data:image/gif", "data:image/webp"];
+
+/// What the sanitizer removed from an uploaded map, counted by element and attribute name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SanitizationReport {
+    pub removed_elements: BTreeMap<String, usize>,
+    pub removed_attributes: BTreeMap<String, usize>,
+    pub removed_doctype: bool,
+    pub removed_processing_instructions: usize,
+}
+
+impl SanitizationReport {
+    pub fn is_clean(&self) -> bool {
+        self.removed_elements.is_empty()
+            && self.removed_attributes.is_empty()
+            && !self.removed_doctype
+            && self.removed_processing_instructions == 0
+    }
+
+    fn element_removed(&mut self, name: &str) {
+        *self.removed_elements.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    fn attribute_removed(&mut self, name: &str) {
+        *self.removed_attributes.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Strips active content from SVG markup before it is stored and later served to browsers:
+/// disallowed elements, event handler attributes, `javascript:` values, references to
+/// external resources, DOCTYPE declarations and processing instructions.
+pub fn sanitize_svg(content: &str) -> Result<(String, SanitizationReport), String> {
+    let mut reader = NsReader::from_str(content);
+    let mut writer = Writer::new(Vec::with_capacity(content.len()));
+    let mut report = SanitizationReport::default();
+    // Depth inside a removed element; its whole subtree is skipped.
+    let mut skipped_depth = 0usize;
+    // Whether we are inside a `<style>` element whose text must be checked.
+    let mut style_buffer: Option<(BytesStart<'static>, String)> = None;
+    // Names of the open elements as written, since SVG elements lose their prefix.
+    let mut open_elements: Vec<String> = Vec::new();
+
+    loop {
+        let (namespace, event) = match reader.read_resolved_event() {
+            Ok((namespace, event)) => (ElementNamespace::of(&namespace), event),
+            Err(e) => return Err(invalid_svg(&reader, e)),
+        };
+
+        if skipped_depth > 0 {
+            match event {
+                Event::Start(_) => skipped_depth += 1,
+                Event::End(_) => skipped_depth -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some((start, css)) = style_buffer.as_mut() {
+            match event {
+                Event::Text(text) => {
+                    css.push_str(&text.unescape().map_err(|e| invalid_svg(&reader, e))?);
+                    continue;
+                }
+                Event::CData(data) => {
+                    css.push_str(&String::from_utf8_lossy(&data));
+                    continue;
+                }
+                Event::Comment(_) => continue,
+                Event::End(_) => {
+                    if is_dangerous_css(css) {
+                        report.element_removed("style");
+                    } else {
+                        let end = BytesEnd::new(element_name(start));
+                        write(&mut writer, Event::Start(start.clone()))?;
+                        write(&mut writer, Event::Text(BytesText::new(css)))?;
+                        write(&mut writer, Event::End(end))?;
+                    }
+                    style_buffer = None;
+                    continue;
+                }
+                Event::Start(_) | Event::Empty(_) => {
+                    // `<style>` only holds text; markup inside it is not valid SVG.
+                    report.element_removed("style");
+                    style_buffer = None;
+                    skipped_depth = 1;
+                    if let Event::Start(_) = event {
+                        skipped_depth += 1;
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            }
+        }
+
+        match event {
+            Event::Start(element) => {
+                let name = element_name(&element);
+                let Some(output_name) = allowed_element_name(&element, namespace) else {
+                    report.element_removed(&name);
+                    skipped_depth = 1;
+                    continue;
+                };
+                let element = clean_attributes(&element, &output_name, &reader, &mut report)?;
+                if namespace == ElementNamespace::Svg && output_name == "style" {
+                    style_buffer = Some((element, String::new()));
+                    continue;
+                }
+                write(&mut writer, Event::Start(element))?;
+                open_elements.push(output_name);
+            }
+            Event::Empty(element) => {
+                let name = element_name(&element);
+                let Some(output_name) = allowed_element_name(&element, namespace) else {
+                    report.element_removed(&name);
+                    continue;
+                };
+                let element = clean_attributes(&element, &output_name, &reader, &mut report)?;
+                write(&mut writer, Event::Empty(element))?;
+            }
+            Event::End(_) => {
+                let name = open_elements
+                    .pop()
+                    .ok_or_else(|| invalid_svg(&reader, "cierre sin apertura"))?;
+                write(&mut writer, Event::End(BytesEnd::new(name)))?;
+            }
+            Event::DocType(_) => report.removed_doctype = true,
+            Event::PI(_) => report.removed_processing_instructions += 1,
+            Event::Eof => break,
+            other => write(&mut writer, other)?,
+        }
+    }
+
+    let sanitized = String::from_utf8(writer.into_inner())
+        .map_err(|e| format!("Error al generar SVG: {}", e))?;
+
+    Ok((sanitized, report))
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<(), String> {
+    writer
+        .write_event(event)
+        .map_err(|e| format!("Error al generar SVG: {}", e))
+}
+
+/// Namespace an element or attribute name resolved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ElementNamespace {
+    /// SVG, or no namespace at all, as in files without an `xmlns`.
+    Svg,
+    Editor,
+    Other,
+}
+
+impl ElementNamespace {
+    fn of(resolved: &ResolveResult) -> Self {
+        match resolved {
+            ResolveResult::Unbound => Self::Svg,
+            ResolveResult::Bound(Namespace(uri)) if *uri == SVG_NAMESPACE.as_bytes() => Self::Svg,
+            ResolveResult::Bound(Namespace(uri)) if EDITOR_NAMESPACES.iter().any(|ns| ns.as_bytes() == *uri) => {
+                Self::Editor
+            }
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Name the element is written with, or `None` when it must be dropped. SVG elements are
+/// written without a prefix, whatever prefix the document bound to the SVG namespace.
+fn allowed_element_name(element: &BytesStart, namespace: ElementNamespace) -> Option<String> {
+    let name = element.name();
+    let local = String::from_utf8_lossy(name.local_name().as_ref()).into_owned();
+    match namespace {
+        ElementNamespace::Svg => ALLOWED_ELEMENTS.contains(&local.as_str()).then_some(local),
+        // Editor elements in the default namespace would read as plain (HTML) elements
+        // once the map is inlined in a page.
+        ElementNamespace::Editor => name.prefix().is_some().then(|| element_name(element)),
+        ElementNamespace::Other => None,
+    }
+}
+
+fn clean_attributes(
+    element: &BytesStart,
+    output_name: &str,
+    reader: &NsReader<&[u8]>,
+    report: &mut SanitizationReport,
+) -> Result<BytesStart<'static>, String> {
+    let attributes = read_attributes(element, reader)?;
+    let mut kept = Vec::with_capacity(attributes.len());
+    let renamed = element_name(element) != output_name;
+    let mut changed = renamed;
+
+    for (key, value) in attributes {
+        if key.starts_with("xmlns:") && value.trim() == SVG_NAMESPACE {
+            // SVG elements lose their prefix, so the declaration is no longer used.
+            changed = true;
+        } else if is_allowed_namespace(&key, &value, reader) && is_allowed_attribute(&key, &value) {
+            kept.push((key, value));
+        } else {
+            report.attribute_removed(&key);
+            changed = true;
+        }
+    }
+
+    if renamed && !kept.iter().any(|(key, _)| key == "xmlns") {
+        kept.insert(0, ("xmlns".to_string(), SVG_NAMESPACE.to_string()));
+    }
+
+    if changed {
+        Ok(build_element(output_name, &kept))
+    } else {
+        Ok(element.clone().into_owned())
+    }
+}
+
+/// Namespace declarations can't make the default namespace anything but SVG nor bind a
+/// prefix to SVG or XHTML, and attributes can't be in either of those namespaces.
+fn is_allowed_namespace(key: &str, value: &str, reader: &NsReader<&[u8]>) -> bool {
+    if key == "xmlns" {
+        return value.trim() == SVG_NAMESPACE;
+    }
+    if key.starts_with("xmlns:") {
+        return value.trim() != SVG_NAMESPACE && value.trim() != XHTML_NAMESPACE;
+    }
+
+    match reader.resolve_attribute(QName(key.as_bytes())).0 {
+        ResolveResult::Unbound => true,
+        ResolveResult::Bound(Namespace(uri)) => uri != SVG_NAMESPACE.as_bytes() && uri != XHTML_NAMESPACE.as_bytes(),
+        ResolveResult::Unknown(_) => false,
+    }
+}
+
+fn is_allowed_attribute(key: &str, value: &str) -> bool {
+    let local = key.rsplit(':').next().unwrap_or(key).to_ascii_lowercase();
+
+    if local.starts_with("on") {
+        return false;
+    }
+
+    let normalized = normalize_value(value);
+    if normalized.contains("javascript:") || normalized.contains("vbscript:") || normalized.contains("expression(") {
+        return false;
+    }
+
+    if local == "href" || key.eq_ignore_ascii_case("xml:base") {
+        return is_local_reference(&normalized);
+    }
+
+    !has_external_url(&normalized)
+}
+
+/// Lowercases and drops whitespace and control characters, which browsers ignore inside
+/// URL schemes (`java\nscript:`).
+fn normalize_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_local_reference(normalized: &str) -> bool {
+    normalized.starts_with('#') || ALLOWED_DATA_URIS.iter().any(|uri| normalized.starts_with(uri))
+}
+
+/// Whether a value holds a `url(...)` pointing anywhere but to an element of the same document.
+fn has_external_url(normalized: &str) -> bool {
+    normalized.match_indices("url(").any(|(index, _)| {
+        let target = normalized[index + 4..].trim_start_matches(['"', '\'']);
+        !is_local_reference(target)
+    })
+}
+
+fn is_dangerous_css(css: &str) -> bool {
+    let normalized = normalize_value(css);
+    normalized.contains("@import")
+        || normalized.contains("javascript:")
+        || normalized.contains("expression(")
+        || normalized.contains("-moz-binding")
+        || has_external_url(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(content: &str) -> (String, SanitizationReport) {
+        sanitize_svg(content).expect("the SVG should parse")
+    }
+
+    #[test]
+    fn keeps_clean_markup_unchanged() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><g id="lote1"><path d="M0 0h1v1z" fill="#fff"/></g></svg>"##;
+        let (sanitized, report) = sanitize(svg);
+        assert_eq!(sanitized, svg);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn removes_scripts_with_their_content() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><script>alert(1)</script><foreignObject><div/></foreignObject><rect/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect/></svg>"#);
+        assert_eq!(report.removed_elements.get("script"), Some(&1));
+        assert_eq!(report.removed_elements.get("foreignObject"), Some(&1));
+    }
+
+    #[test]
+    fn removes_event_handlers() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" onload="alert(1)"><rect id="a" ONCLICK="alert(2)"/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="a"/></svg>"#);
+        assert_eq!(report.removed_attributes.get("onload"), Some(&1));
+        assert_eq!(report.removed_attributes.get("ONCLICK"), Some(&1));
+    }
+
+    #[test]
+    fn removes_script_and_external_references() {
+        let (sanitized, report) = sanitize(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><a href="java&#10;script:alert(1)"><use xlink:href="https://evil.example/x.svg#a"/><use href="#lote1"/></a><image href="data:image/png;base64,AAAA"/><image href="data:image/svg+xml;base64,AAAA"/></svg>"##,
+        );
+        assert_eq!(
+            sanitized,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><a><use/><use href="#lote1"/></a><image href="data:image/png;base64,AAAA"/><image/></svg>"##
+        );
+        assert_eq!(report.removed_attributes.get("href"), Some(&2));
+        assert_eq!(report.removed_attributes.get("xlink:href"), Some(&1));
+    }
+
+    #[test]
+    fn removes_external_urls_in_attributes() {
+        let (sanitized, _) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><rect fill="url(#grad)"/><rect fill="url('https://evil.example/a')" style="filter:url(http://evil.example)"/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect fill="url(#grad)"/><rect/></svg>"#);
+    }
+
+    #[test]
+    fn removes_dangerous_style_sheets() {
+        for css in ["@import url(https://evil.example/a.css);", "rect{fill:url(https://evil.example/a)}", "rect{background:url( javascript:alert(1))}"] {
+            let (sanitized, report) = sanitize(&format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg"><style>{}</style><rect/></svg>"#,
+                css
+            ));
+            assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect/></svg>"#, "{}", css);
+            assert_eq!(report.removed_elements.get("style"), Some(&1));
+        }
+
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><style>.lote{fill:url(#grad)}</style></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><style>.lote{fill:url(#grad)}</style></svg>"#);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn removes_doctype_and_processing_instructions() {
+        let (sanitized, report) = sanitize(
+            r#"<?xml-stylesheet href="https://evil.example/a.css"?><!DOCTYPE svg [<!ENTITY a "b">]><svg xmlns="http://www.w3.org/2000/svg"/>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#);
+        assert!(report.removed_doctype);
+        assert_eq!(report.removed_processing_instructions, 1);
+    }
+
+    #[test]
+    fn resolves_svg_elements_by_namespace_not_prefix() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:x="http://www.w3.org/2000/svg"><x:script>alert(1)</x:script><x:foreignObject/><x:rect x:onclick="alert(2)"/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect xmlns="http://www.w3.org/2000/svg"/></svg>"#);
+        assert_eq!(report.removed_elements.get("x:script"), Some(&1));
+        assert_eq!(report.removed_elements.get("x:foreignObject"), Some(&1));
+        assert_eq!(report.removed_attributes.get("x:onclick"), Some(&1));
+    }
+
+    #[test]
+    fn removes_xhtml_under_an_editor_prefix() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><x:div xmlns:x="http://www.w3.org/1999/xhtml"><x:img src="a" onerror="alert(1)"/></x:div><g xmlns:i="http://www.w3.org/1999/xhtml" i:style="a"/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><g/></svg>"#);
+        assert_eq!(report.removed_elements.get("x:div"), Some(&1));
+        assert_eq!(report.removed_attributes.get("xmlns:i"), Some(&1));
+        assert_eq!(report.removed_attributes.get("i:style"), Some(&1));
+    }
+
+    #[test]
+    fn removes_elements_in_other_default_namespaces() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><g xmlns="http://www.w3.org/1999/xhtml"><script>alert(1)</script></g><metadata><img xmlns="http://www.inkscape.org/namespaces/inkscape"/></metadata></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><metadata></metadata></svg>"#);
+        assert_eq!(report.removed_elements.get("g"), Some(&1));
+        assert_eq!(report.removed_elements.get("img"), Some(&1));
+    }
+
+    #[test]
+    fn removes_unknown_prefixes() {
+        let (sanitized, report) = sanitize(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><inkscape:script>alert(1)</inkscape:script><rect foo:bar="1"/></svg>"#,
+        );
+        assert_eq!(sanitized, r#"<svg xmlns="http://www.w3.org/2000/svg"><rect/></svg>"#);
+        assert_eq!(report.removed_elements.get("inkscape:script"), Some(&1));
+        assert_eq!(report.removed_attributes.get("foo:bar"), Some(&1));
+    }
+
+    #[test]
+    fn keeps_editor_namespaces() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd"><sodipodi:namedview inkscape:zoom="1"/><g inkscape:label="Lotes"/></svg>"#;
+        let (sanitized, report) = sanitize(svg);
+        assert_eq!(sanitized, svg);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn unprefixes_svg_elements() {
+        let (sanitized, report) = sanitize(
+            r#"<svg:svg xmlns:svg="http://www.w3.org/2000/svg"><svg:g id="lote1"><svg:rect/></svg:g></svg:svg>"#,
+        );
+        assert_eq!(
+            sanitized,
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><g xmlns="http://www.w3.org/2000/svg" id="lote1"><rect xmlns="http://www.w3.org/2000/svg"/></g></svg>"#
+        );
+        assert!(report.is_clean());
+    }
+}
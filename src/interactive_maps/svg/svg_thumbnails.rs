@@ -0,0 +1,34 @@
+use resvg::{tiny_skia, usvg};
+
+/// Thumbnail sizes generated for every map, as the length in pixels of the longest side.
+pub const THUMBNAIL_SIZES: [i32; 3] = [128, 256, 512];
+pub const DEFAULT_THUMBNAIL_SIZE: i32 = 256;
+
+/// Rasterizes the map into a PNG for each of `THUMBNAIL_SIZES`, keeping its aspect ratio.
+/// No fonts are loaded, so text is skipped: lot numbers are unreadable at these sizes anyway.
+pub fn render_thumbnails(content: &str) -> Result<Vec<(i32, Vec<u8>)>, String> {
+    let tree = usvg::Tree::from_str(content, &usvg::Options::default())
+        .map_err(|e| format!("Error al rasterizar SVG: {}", e))?;
+
+    THUMBNAIL_SIZES
+        .iter()
+        .map(|&size| Ok((size, render_png(&tree, size)?)))
+        .collect()
+}
+
+fn render_png(tree: &usvg::Tree, size: i32) -> Result<Vec<u8>, String> {
+    let svg_size = tree.size();
+    let scale = size as f32 / svg_size.width().max(svg_size.height());
+    let width = ((svg_size.width() * scale).round() as u32).max(1);
+    let height = ((svg_size.height() * scale).round() as u32).max(1);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| format!("Tamaño de miniatura inválido: {}x{}", width, height))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    resvg::render(tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("Error al generar miniatura: {}", e))
+}
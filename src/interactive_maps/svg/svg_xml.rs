@@ -0,0 +1,56 @@
+use quick_xml::events::BytesStart;
+use quick_xml::Reader;
+
+/// Decodes the attributes of an element into owned `(name, value)` pairs, unescaping the values.
+pub fn read_attributes(element: &BytesStart, reader: &Reader<&[u8]>) -> Result<Vec<(String, String)>, String> {
+    element
+        .attributes()
+        .map(|attr| {
+            let attr = attr.map_err(|e| invalid_svg(reader, e))?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+            let value = attr
+                .unescape_value()
+                .map_err(|e| invalid_svg(reader, e))?
+                .into_owned();
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Builds an element with the given attributes, escaping their values.
+pub fn build_element(name: &str, attributes: &[(String, String)]) -> BytesStart<'static> {
+    let mut element = BytesStart::new(name.to_string());
+    for (key, value) in attributes {
+        element.push_attribute((key.as_str(), value.as_str()));
+    }
+    element
+}
+
+/// Returns the element name as an owned string.
+pub fn element_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.name().as_ref()).into_owned()
+}
+
+/// Replaces (or appends) a declaration inside an inline `style` attribute.
+pub fn set_style_property(style: &str, property: &str, value: &str) -> String {
+    let mut declarations: Vec<String> = style
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+        .filter(|declaration| {
+            declaration
+                .split(':')
+                .next()
+                .map(|name| !name.trim().eq_ignore_ascii_case(property))
+                .unwrap_or(true)
+        })
+        .map(str::to_string)
+        .collect();
+
+    declarations.push(format!("{}:{}", property, value));
+    declarations.join(";")
+}
+
+pub fn invalid_svg(reader: &Reader<&[u8]>, error: impl std::fmt::Display) -> String {
+    format!("SVG inválido en la posición {}: {}", reader.buffer_position(), error)
+}
@@ -6,4 +6,7 @@ pub mod auth;
 pub mod db;
 pub mod products;
 pub mod zoho_code;
-pub mod status_colors;
\ No newline at end of file
+pub mod status_colors;
+pub mod developments;
+pub mod access_codes;
+pub mod lots;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+use utoipa::ToSchema;
+use crate::products::entities::products_entity::Product;
+use crate::status_colors::dto::status_color_dto::StatusColorResponse;
+
+/// The cached Zoho product of a lot.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LotProductResponse {
+    pub id: String,
+    pub product_name: Option<String>,
+    pub estatus_venta: Option<String>,
+    #[schema(value_type = String)]
+    pub updated_at: NaiveDateTime,
+}
+
+/// A map a lot appears on, with the id of its element there.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LotMapResponse {
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+    pub element_id: String,
+}
+
+/// Everything the lot popup shows. `color` is the color of `status_color`, or the unknown
+/// status color when the lot has no product or its status has no color.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LotDetailResponse {
+    /// Product name of the lot: map prefix followed by the lot number, e.g. `TC6`
+    pub lot_id: String,
+    pub lot_number: String,
+    pub element_id: String,
+    pub product: Option<LotProductResponse>,
+    pub status_color: Option<StatusColorResponse>,
+    pub color: String,
+    pub maps: Vec<LotMapResponse>,
+}
+
+impl LotProductResponse {
+    pub fn from_entity(entity: &Product) -> Self {
+        Self {
+            id: entity.id.clone(),
+            product_name: entity.product_name.clone(),
+            estatus_venta: entity.estatus_venta.clone(),
+            updated_at: entity.updated_at,
+        }
+    }
+}
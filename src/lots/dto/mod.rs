@@ -0,0 +1 @@
+pub mod lot_dto;
\ No newline at end of file
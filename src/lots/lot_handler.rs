@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse, Result};
+use std::sync::Arc;
+use crate::auth::entities::auth_entities::Claims;
+use crate::common::errors::ApiError;
+use super::lot_service::LotService;
+use super::dto::lot_dto::LotDetailResponse;
+
+#[utoipa::path(
+    get,
+    path = "/lots/{lot_id}",
+    params(
+        ("lot_id" = String, Path, description = "Product name of the lot: map prefix followed by the lot number", example = "TC6")
+    ),
+    responses(
+        (status = 200, description = "Lote con su producto, el color de su estatus y los mapas en los que aparece", body = LotDetailResponse),
+        (status = 404, description = "El lote no aparece en ningún mapa accesible")
+    ),
+    tag = "Lots"
+)]
+#[actix_web::get("/{lot_id}")]
+pub async fn get_lot(
+    lot_id: web::Path<String>,
+    claims: web::ReqData<Claims>,
+    lot_service: web::Data<Arc<LotService>>,
+) -> Result<HttpResponse, ApiError> {
+    let lot = lot_service.get_lot(&lot_id, &claims.map_access()).await?;
+    Ok(HttpResponse::Ok().json(lot))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maps/{id}/lots/{element_id}",
+    params(
+        ("id" = String, Path, description = "Map id", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("element_id" = String, Path, description = "Id of the lot element in the map", example = "lote6")
+    ),
+    responses(
+        (status = 200, description = "Lote del elemento con su producto, el color de su estatus y los mapas en los que aparece", body = LotDetailResponse),
+        (status = 404, description = "Mapa no encontrado o el elemento no es un lote")
+    ),
+    tag = "Lots"
+)]
+#[actix_web::get("/{id}/lots/{element_id}")]
+pub async fn get_map_lot(
+    path: web::Path<(String, String)>,
+    claims: web::ReqData<Claims>,
+    lot_service: web::Data<Arc<LotService>>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, element_id) = path.into_inner();
+    let lot = lot_service.get_map_lot(&id, &element_id, &claims.map_access()).await?;
+    Ok(HttpResponse::Ok().json(lot))
+}
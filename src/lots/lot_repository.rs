@@ -0,0 +1,76 @@
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection, Pool};
+use diesel::mysql::MysqlConnection;
+use diesel::result::Error as DieselError;
+use crate::db::schema::{map_lots, maps_svg};
+use crate::interactive_maps::map_status_service::product_name_for_lot;
+
+/// A lot on one map.
+#[derive(Queryable, Debug, Clone)]
+pub struct LotPlacement {
+    pub map_id: String,
+    pub map_name: String,
+    pub map_version: i32,
+    pub prefix: String,
+    pub development_id: Option<i32>,
+    pub element_id: String,
+    pub lot_number: String,
+}
+
+pub struct LotRepository {
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+}
+
+impl LotRepository {
+    pub fn new(pool: Pool<ConnectionManager<MysqlConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DieselError> {
+        self.pool.get().map_err(|_| {
+            eprintln!("Failed to get DB connection");
+            DieselError::DatabaseError(
+                diesel::result::DatabaseErrorKind::UnableToSendCommand,
+                Box::new(String::from("Failed to get DB connection"))
+            )
+        })
+    }
+
+    /// Placements of the lot whose product name is `lot_id`, ordered by map name. Maps in the
+    /// trash are left out.
+    pub fn get_placements(&self, lot_id: &str) -> Result<Vec<LotPlacement>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        map_lots::table
+            .inner_join(maps_svg::table)
+            .filter(maps_svg::deleted_at.is_null())
+            .filter(maps_svg::prefix.concat(map_lots::lot_number).eq(lot_id))
+            .select((
+                maps_svg::id,
+                maps_svg::name,
+                maps_svg::version,
+                maps_svg::prefix,
+                maps_svg::development_id,
+                map_lots::element_id,
+                map_lots::lot_number,
+            ))
+            .order((maps_svg::name.asc(), map_lots::id.asc()))
+            .load(conn)
+    }
+
+    /// Product name of the lot behind an element of a map; `None` when the map is in the trash
+    /// or the element is not a lot.
+    pub fn find_lot_id(&self, map_id: &str, element_id: &str) -> Result<Option<String>, DieselError> {
+        let conn = &mut self.get_conn()?;
+
+        map_lots::table
+            .inner_join(maps_svg::table)
+            .filter(map_lots::map_id.eq(map_id))
+            .filter(map_lots::element_id.eq(element_id))
+            .filter(maps_svg::deleted_at.is_null())
+            .select((maps_svg::prefix, map_lots::lot_number))
+            .first::<(String, String)>(conn)
+            .optional()
+            .map(|lot| lot.map(|(prefix, lot_number)| product_name_for_lot(&prefix, &lot_number)))
+    }
+}
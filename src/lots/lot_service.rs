@@ -0,0 +1,113 @@
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use diesel::result::Error as DieselError;
+use crate::auth::entities::auth_entities::MapAccess;
+use crate::common::errors::ApiError;
+use crate::products::products_service::ProductService;
+use crate::status_colors::dto::status_color_dto::StatusColorResponse;
+use crate::status_colors::status_color_service::{normalize_status, StatusColorService, UNKNOWN_STATUS_COLOR};
+use super::lot_repository::{LotPlacement, LotRepository};
+use super::dto::lot_dto::{LotDetailResponse, LotMapResponse, LotProductResponse};
+
+/// Joins a lot with its cached product, the color of its status and the maps it appears on,
+/// so every client shows the same popup.
+pub struct LotService {
+    repository: LotRepository,
+    product_service: Arc<Mutex<ProductService>>,
+    status_color_service: Arc<StatusColorService>,
+}
+
+impl LotService {
+    pub fn new(
+        repository: LotRepository,
+        product_service: Arc<Mutex<ProductService>>,
+        status_color_service: Arc<StatusColorService>,
+    ) -> Self {
+        Self {
+            repository,
+            product_service,
+            status_color_service,
+        }
+    }
+
+    /// `lot_id` is the product name of the lot, e.g. `TC6`.
+    pub async fn get_lot(&self, lot_id: &str, access: &MapAccess) -> Result<LotDetailResponse, ApiError> {
+        self.lot_detail(lot_id.trim(), None, access).await
+    }
+
+    /// The lot behind an element of a map; `element_id` is reported for that map.
+    pub async fn get_map_lot(&self, map_id: &str, element_id: &str, access: &MapAccess) -> Result<LotDetailResponse, ApiError> {
+        let lot_id = self.repository
+            .find_lot_id(map_id, element_id)
+            .map_err(|e| internal_error("Error getting lot", e))?
+            .ok_or_else(lot_not_found)?;
+
+        self.lot_detail(&lot_id, Some((map_id, element_id)), access).await
+    }
+
+    async fn lot_detail(
+        &self,
+        lot_id: &str,
+        on_map: Option<(&str, &str)>,
+        access: &MapAccess,
+    ) -> Result<LotDetailResponse, ApiError> {
+        let placements: Vec<LotPlacement> = self.repository
+            .get_placements(lot_id)
+            .map_err(|e| internal_error("Error getting lot", e))?
+            .into_iter()
+            .filter(|placement| access.allows(&placement.prefix, placement.development_id))
+            .collect();
+
+        let placement = match on_map {
+            Some((map_id, element_id)) => placements
+                .iter()
+                .find(|placement| placement.map_id == map_id && placement.element_id == element_id),
+            None => placements.first(),
+        }
+        .ok_or_else(lot_not_found)?;
+
+        let product = {
+            let mut service = self.product_service.lock().await;
+            service.get_many_by_ids(vec![lot_id])?
+        }
+        .into_iter()
+        .next();
+
+        let colors = self.status_color_service.get_colors_by_status()?;
+        let status_color = product
+            .as_ref()
+            .and_then(|product| product.estatus_venta.as_deref())
+            .and_then(|status| colors.get(&normalize_status(status)))
+            .map(StatusColorResponse::from_entity);
+
+        Ok(LotDetailResponse {
+            lot_id: lot_id.to_string(),
+            lot_number: placement.lot_number.clone(),
+            element_id: placement.element_id.clone(),
+            product: product.as_ref().map(LotProductResponse::from_entity),
+            color: status_color
+                .as_ref()
+                .map(|status_color| status_color.hexadecimal.clone())
+                .unwrap_or_else(|| UNKNOWN_STATUS_COLOR.to_string()),
+            status_color,
+            maps: placements
+                .iter()
+                .map(|placement| LotMapResponse {
+                    id: placement.map_id.clone(),
+                    name: placement.map_name.clone(),
+                    version: placement.map_version,
+                    element_id: placement.element_id.clone(),
+                })
+                .collect(),
+        })
+    }
+}
+
+fn lot_not_found() -> ApiError {
+    ApiError::NotFound("Lote no encontrado".to_string())
+}
+
+fn internal_error(context: &str, error: DieselError) -> ApiError {
+    eprintln!("{}: {:?}", context, error);
+    ApiError::InternalError(context.to_string())
+}
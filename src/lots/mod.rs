@@ -0,0 +1,4 @@
+pub mod lot_repository;
+pub mod lot_service;
+pub mod lot_handler;
+pub mod dto;
\ No newline at end of file
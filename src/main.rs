@@ -5,8 +5,11 @@ use actix_web::{middleware, web, App, HttpServer, HttpResponse, Result as ActixR
 use actix_files::{Files, NamedFile};
 use auth::{auth_handler::{auth_refresh_token, get_me_handler, login_with_zoho_handler, logout_handler}, auth_service::AuthService, auth_service_trait::AuthServiceTrait};
 use status_colors::status_color_handler::get_all_status_colors;
+use access_codes::access_code_handler::{create_access_code, delete_access_code, get_access_codes};
+use lots::lot_handler::{get_lot, get_map_lot};
+use developments::development_handler::{create_development, delete_development, get_development, get_development_overview, get_developments, update_development};
 use common::auth_middleware::AuthGuard;
-use crate::{common::config::Config, interactive_maps::interactive_maps_handler::{get_paginated_svgs, get_svg_by_id, save_svg, save_svg_stream}};
+use crate::{common::config::Config, interactive_maps::{interactive_maps_handler::{create_svg_annotation, create_svg_share_link, delete_svg_annotation, delete_svg_layer, export_svg_pdf, export_svgs, get_composed_svg, get_embed_viewer_script, get_embedded_svg, get_paginated_svgs, get_raw_svg, get_rendered_svg, get_svg_annotations, get_svg_by_id, get_svg_diff, get_svg_layer, get_svg_layer_version, get_svg_layer_versions, get_svg_layers, get_svg_lots, get_svg_original, get_svg_reconciliation, get_svg_thumbnail, get_svg_version, get_svg_versions, get_svgs_by_hash, get_shared_svg, get_trashed_svgs, import_svgs, patch_svg, purge_svg, replace_svg, restore_svg, rollback_svg_version, save_svg, save_svg_layer, save_svg_stream, set_svg_scale, update_svg_annotation}, interactive_maps_service::SvgService, map_embed::EmbedCache, map_status_service::MapStatusService, storage::map_storage::{build_map_storage, MapStorage}}};
 use env_logger::Env;
 use interactive_maps::interactive_maps_handler::delete_svg_by_id;
 use zoho::{zoho_handler::{get_products_by_ids_handler, get_url_base_zoho}, zoho_service::ZohoService, zoho_trait::ZohoServiceTrait};
@@ -23,6 +26,11 @@ mod db;
 mod products;
 mod zoho_code;
 mod status_colors;
+mod developments;
+mod access_codes;
+mod lots;
+
+const MAP_TRASH_PURGE_INTERVAL_SECS: u64 = 60 * 60;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -46,6 +54,25 @@ async fn main() -> std::io::Result<()> {
     let status_color_repository = status_colors::status_color_repository::StatusColorRepository::new(pool.clone());
     let status_color_service = Arc::new(status_colors::status_color_service::StatusColorService::new(status_color_repository));
     let status_color_service_data = web::Data::new(status_color_service.clone());
+
+    let map_status_service = Arc::new(MapStatusService::new(product_service.clone(), status_color_service.clone()));
+    let map_status_service_data = web::Data::new(map_status_service.clone());
+
+    let development_repository = developments::development_repository::DevelopmentRepository::new(pool.clone());
+    let development_service = Arc::new(developments::development_service::DevelopmentService::new(development_repository, map_status_service.clone()));
+    let development_service_data = web::Data::new(development_service.clone());
+
+    let access_code_repository = access_codes::access_code_repository::AccessCodeRepository::new(pool.clone());
+    let access_code_service = Arc::new(access_codes::access_code_service::AccessCodeService::new(access_code_repository));
+    let access_code_service_data = web::Data::new(access_code_service.clone());
+
+    let lot_repository = lots::lot_repository::LotRepository::new(pool.clone());
+    let lot_service = Arc::new(lots::lot_service::LotService::new(lot_repository, product_service.clone(), status_color_service.clone()));
+    let lot_service_data = web::Data::new(lot_service.clone());
+
+    let map_storage: Arc<dyn MapStorage> = build_map_storage(&config.map_storage)
+        .expect("Failed to configure map storage");
+    let map_storage_data = web::Data::new(map_storage.clone());
     
     let auth_service: Arc<dyn AuthServiceTrait> = Arc::new(AuthService::new(
         config.jwt_secret.clone(),
@@ -53,6 +80,7 @@ async fn main() -> std::io::Result<()> {
         config.token_expiration,
         config.token_refresh_expiration,
         zoho_code_service.clone(),
+        access_code_service.clone(),
     ));
     let auth_service_data = web::Data::new(auth_service.clone());
     let auth_guard = AuthGuard::new(auth_service.clone());
@@ -99,6 +127,70 @@ async fn main() -> std::io::Result<()> {
         zoho_code_sync_service.start_automatic_sync(zoho_code_sync_interval).await;
     });
 
+    // Hash de contenido de los mapas guardados antes de la detección de duplicados
+    let hash_pool = pool.clone();
+    let hash_storage = map_storage.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = match hash_pool.get() {
+            Ok(conn) => conn,
+            Err(e) => return eprintln!("Map content hash backfill failed: {}", e),
+        };
+        match SvgService::new(conn, hash_storage).backfill_content_hashes() {
+            Ok(0) => {}
+            Ok(hashed) => println!("Hashed the content of {} maps.", hashed),
+            Err(err) => eprintln!("Map content hash backfill failed: {}", err),
+        }
+    });
+
+    // Índice y geometría de los lotes de los mapas guardados antes de que existieran
+    let lots_pool = pool.clone();
+    let lots_storage = map_storage.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = match lots_pool.get() {
+            Ok(conn) => conn,
+            Err(e) => return eprintln!("Map lot backfill failed: {}", e),
+        };
+        let mut service = SvgService::new(conn, lots_storage);
+        match service.backfill_lots() {
+            Ok(0) => {}
+            Ok(indexed) => println!("Indexed the lots of {} maps.", indexed),
+            Err(err) => eprintln!("Map lot backfill failed: {}", err),
+        }
+        match service.backfill_lot_geometry() {
+            Ok(0) => {}
+            Ok(measured) => println!("Measured the lots of {} maps.", measured),
+            Err(err) => eprintln!("Map lot geometry backfill failed: {}", err),
+        }
+    });
+
+    // Job para purgar la papelera de mapas
+    let trash_pool = pool.clone();
+    let trash_storage = map_storage.clone();
+    let trash_retention_days = config.map_trash_retention_days;
+    tokio::spawn(async move {
+        loop {
+            let pool = trash_pool.clone();
+            let storage = trash_storage.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                SvgService::new(conn, storage).purge_expired_trash(trash_retention_days)
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(purged)) => println!("Purged {} maps from the trash.", purged),
+                Ok(Err(err)) => eprintln!("Map trash purge failed: {}", err),
+                Err(err) => eprintln!("Map trash purge failed: {:?}", err),
+            }
+
+            time::sleep(Duration::from_secs(MAP_TRASH_PURGE_INTERVAL_SECS)).await;
+        }
+    });
+
+    let config_data = web::Data::new(config.clone());
+    let embed_cache_data = web::Data::new(Arc::new(EmbedCache::new(config.map_embed_cache_seconds)));
+
     HttpServer::new(move || {
         let unique_origins: HashSet<String> = config.cors_allowed_origins.iter().cloned().collect();
 
@@ -108,7 +200,7 @@ async fn main() -> std::io::Result<()> {
         }
     
         cors = cors
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE","OPTIONS"])
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE","OPTIONS"])
             .allow_any_header()
             .allow_any_method()
             .expose_any_header()
@@ -121,6 +213,13 @@ async fn main() -> std::io::Result<()> {
             .app_data(zoho_service_data.clone())
             .app_data(auth_service_data.clone())
             .app_data(status_color_service_data.clone())
+            .app_data(map_status_service_data.clone())
+            .app_data(map_storage_data.clone())
+            .app_data(development_service_data.clone())
+            .app_data(access_code_service_data.clone())
+            .app_data(lot_service_data.clone())
+            .app_data(config_data.clone())
+            .app_data(embed_cache_data.clone())
             .wrap(cors)
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}")
@@ -138,11 +237,72 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api/maps")
                     .wrap(auth_guard.clone())
                     .service(save_svg_stream)
+                    .service(import_svgs)
                     .service(save_svg)
+                    .service(get_svgs_by_hash)
+                    .service(export_svgs)
+                    .service(get_trashed_svgs)
+                    .service(restore_svg)
+                    .service(purge_svg)
                     .service(get_svg_by_id)
+                    .service(get_raw_svg)
+                    .service(replace_svg)
+                    .service(patch_svg)
+                    .service(get_svg_lots)
+                    .service(get_map_lot)
+                    .service(set_svg_scale)
+                    .service(get_svg_reconciliation)
+                    .service(get_rendered_svg)
+                    .service(get_composed_svg)
+                    .service(get_svg_layers)
+                    .service(get_svg_layer_versions)
+                    .service(get_svg_layer_version)
+                    .service(get_svg_layer)
+                    .service(save_svg_layer)
+                    .service(delete_svg_layer)
+                    .service(get_svg_annotations)
+                    .service(create_svg_annotation)
+                    .service(update_svg_annotation)
+                    .service(delete_svg_annotation)
+                    .service(create_svg_share_link)
+                    .service(export_svg_pdf)
+                    .service(get_svg_thumbnail)
+                    .service(get_svg_versions)
+                    .service(get_svg_version)
+                    .service(get_svg_original)
+                    .service(rollback_svg_version)
+                    .service(get_svg_diff)
                     .service(get_paginated_svgs)
                     .service(delete_svg_by_id),
             )
+            .service(
+                web::scope("/api/developments")
+                    .wrap(auth_guard.clone())
+                    .service(get_developments)
+                    .service(create_development)
+                    .service(get_development)
+                    .service(update_development)
+                    .service(delete_development)
+                    .service(get_development_overview),
+            )
+            .service(
+                web::scope("/api/access-codes")
+                    .wrap(auth_guard.clone())
+                    .service(get_access_codes)
+                    .service(create_access_code)
+                    .service(delete_access_code),
+            )
+            .service(
+                web::scope("/api/lots")
+                    .wrap(auth_guard.clone())
+                    .service(get_lot),
+            )
+            .service(
+                web::scope("/api/public")
+                    .service(get_shared_svg)
+                    .service(get_embedded_svg)
+                    .service(get_embed_viewer_script),
+            )
             .service(
                 web::scope("/api/zoho")
                     .service(get_products_by_ids_handler)
@@ -2,7 +2,7 @@ use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, PooledConnection, Pool};
 use diesel::mysql::MysqlConnection;
 use diesel::result::Error as DieselError;
-use crate::db::schema::products;
+use crate::db::{escape_like, schema::products};
 use super::entities::products_entity::{NewProduct, Product};
 use chrono::{Utc, NaiveDateTime};
 use diesel::debug_query;
@@ -71,4 +71,12 @@ impl ProductRepository {
             }
         }
     }
+
+    pub fn get_by_name_prefix(&self, prefix: &str) -> Result<Vec<Product>, DieselError> {
+        let conn = &mut self.get_conn()?;
+        products::table
+            .filter(products::product_name.like(format!("{}%", escape_like(prefix))))
+            .order(products::product_name.asc())
+            .load::<Product>(conn)
+    }
 }
@@ -33,4 +33,14 @@ impl ProductService {
             })
     }
 
+    /// Products whose name starts with `prefix`. Names like `TCX1` also match `TC`, so callers
+    /// must check the rest of the name.
+    pub fn get_by_name_prefix(&mut self, prefix: &str) -> Result<Vec<Product>, ApiError> {
+        self.repository
+            .get_by_name_prefix(prefix)
+            .map_err(|err| {
+                eprintln!("Error getting products by prefix: {:?}", err);
+                ApiError::InternalError("Failed to fetch products".to_string())
+            })
+    }
 }
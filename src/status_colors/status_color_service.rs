@@ -1,7 +1,17 @@
+use std::collections::HashMap;
 use crate::common::errors::ApiError;
 use super::status_color_repository::StatusColorRepository;
+use super::entities::status_color_entity::StatusColor;
 use super::dto::status_color_dto::{StatusColorResponse, StatusColorsListResponse};
 
+/// Color used when a lot has no product or its status has no entry in `status_colors`.
+pub const UNKNOWN_STATUS_COLOR: &str = "#CCCCCC";
+
+/// Normalizes a sales status so `estatus_venta` values from Zoho match `status_colors.status`.
+pub fn normalize_status(status: &str) -> String {
+    status.trim().to_lowercase()
+}
+
 pub struct StatusColorService {
     repository: StatusColorRepository,
 }
@@ -32,4 +42,17 @@ impl StatusColorService {
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_colors_by_status(&self) -> Result<HashMap<String, StatusColor>, ApiError> {
+        match self.repository.get_all_colors() {
+            Ok(colors) => Ok(colors
+                .into_iter()
+                .map(|color| (normalize_status(&color.status), color))
+                .collect()),
+            Err(e) => {
+                eprintln!("Error getting status colors: {:?}", e);
+                Err(ApiError::InternalError("Error retrieving status colors".to_string()))
+            }
+        }
+    }
+}